@@ -0,0 +1,332 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Access control common to all owned data types.
+//!
+//! MutableData, AppendOnlyData and unpublished ImmutableData each have their own permission model.
+//! `AccessControl` exposes them through a single interface in terms of `GenericAction`, so that
+//! authorisation can be written once for all of them. Actions a data type doesn't support are
+//! never allowed.
+
+use crate::{
+    AData, ADataAction, ADataIndex, ADataUser, MData, MDataAction, MDataUser, PublicKey,
+    UnpubImmutableData,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An action on any type of data.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum GenericAction {
+    /// Read the data.
+    Read,
+    /// Insert new entries (MutableData).
+    Insert,
+    /// Update existing entries (MutableData).
+    Update,
+    /// Delete entries (MutableData) or the whole data (unpublished ImmutableData).
+    Delete,
+    /// Append entries (AppendOnlyData).
+    Append,
+    /// Modify the permissions for other users.
+    ManagePermissions,
+}
+
+impl GenericAction {
+    /// Returns the corresponding MutableData action, if any.
+    pub fn to_mdata_action(self) -> Option<MDataAction> {
+        match self {
+            GenericAction::Read => Some(MDataAction::Read),
+            GenericAction::Insert => Some(MDataAction::Insert),
+            GenericAction::Update => Some(MDataAction::Update),
+            GenericAction::Delete => Some(MDataAction::Delete),
+            GenericAction::Append => None,
+            GenericAction::ManagePermissions => Some(MDataAction::ManagePermissions),
+        }
+    }
+
+    /// Returns the corresponding AppendOnlyData action, if any.
+    pub fn to_adata_action(self) -> Option<ADataAction> {
+        match self {
+            GenericAction::Read => Some(ADataAction::Read),
+            GenericAction::Append => Some(ADataAction::Append),
+            GenericAction::ManagePermissions => Some(ADataAction::ManagePermissions),
+            GenericAction::Insert | GenericAction::Update | GenericAction::Delete => None,
+        }
+    }
+}
+
+impl From<MDataAction> for GenericAction {
+    fn from(action: MDataAction) -> Self {
+        match action {
+            MDataAction::Read => GenericAction::Read,
+            MDataAction::Insert => GenericAction::Insert,
+            MDataAction::Update => GenericAction::Update,
+            MDataAction::Delete => GenericAction::Delete,
+            MDataAction::ManagePermissions => GenericAction::ManagePermissions,
+        }
+    }
+}
+
+impl From<ADataAction> for GenericAction {
+    fn from(action: ADataAction) -> Self {
+        match action {
+            ADataAction::Read => GenericAction::Read,
+            ADataAction::Append => GenericAction::Append,
+            ADataAction::ManagePermissions => GenericAction::ManagePermissions,
+        }
+    }
+}
+
+const MDATA_ACTIONS: [MDataAction; 5] = [
+    MDataAction::Read,
+    MDataAction::Insert,
+    MDataAction::Update,
+    MDataAction::Delete,
+    MDataAction::ManagePermissions,
+];
+
+const ADATA_ACTIONS: [ADataAction; 3] = [
+    ADataAction::Read,
+    ADataAction::Append,
+    ADataAction::ManagePermissions,
+];
+
+/// Access control for data which has owners and, optionally, per-user permissions.
+pub trait AccessControl {
+    /// User a permission entry is for, in the data type's own terms.
+    type User: Ord;
+
+    /// Returns the current owners.
+    fn owners(&self) -> Vec<PublicKey>;
+
+    /// Returns true if `requester` is allowed to perform `action`.
+    fn can(&self, requester: PublicKey, action: GenericAction) -> bool;
+
+    /// Returns the users with an entry in the current permissions, and the actions their own entry
+    /// allows. Owners are only included if they have an entry.
+    fn permitted_users(&self) -> BTreeMap<Self::User, BTreeSet<GenericAction>>;
+}
+
+impl AccessControl for MData {
+    type User = MDataUser;
+
+    fn owners(&self) -> Vec<PublicKey> {
+        vec![self.owner()]
+    }
+
+    fn can(&self, requester: PublicKey, action: GenericAction) -> bool {
+        match action.to_mdata_action() {
+            Some(action) => self.explain_access(requester, action).is_allowed(),
+            None => false,
+        }
+    }
+
+    fn permitted_users(&self) -> BTreeMap<MDataUser, BTreeSet<GenericAction>> {
+        self.permissions()
+            .into_iter()
+            .map(|(user, permissions)| {
+                let actions = MDATA_ACTIONS
+                    .iter()
+                    .filter(|action| permissions.is_allowed(**action))
+                    .map(|action| GenericAction::from(*action))
+                    .collect();
+                (user, actions)
+            })
+            .collect()
+    }
+}
+
+impl AccessControl for AData {
+    type User = ADataUser;
+
+    fn owners(&self) -> Vec<PublicKey> {
+        self.owner(ADataIndex::FromEnd(1))
+            .map(|owner| owner.public_key)
+            .into_iter()
+            .collect()
+    }
+
+    fn can(&self, requester: PublicKey, action: GenericAction) -> bool {
+        match action.to_adata_action() {
+            Some(action) => self.explain_access(requester, action).is_allowed(),
+            None => false,
+        }
+    }
+
+    fn permitted_users(&self) -> BTreeMap<ADataUser, BTreeSet<GenericAction>> {
+        if let Ok(permissions) = self.pub_permissions(ADataIndex::FromEnd(1)) {
+            permissions
+                .permissions()
+                .iter()
+                .map(|(user, permissions)| {
+                    let actions = ADATA_ACTIONS
+                        .iter()
+                        .filter(|action| permissions.is_allowed(**action) == Some(true))
+                        .map(|action| GenericAction::from(*action))
+                        .collect();
+                    (user.clone(), actions)
+                })
+                .collect()
+        } else if let Ok(permissions) = self.unpub_permissions(ADataIndex::FromEnd(1)) {
+            permissions
+                .permissions()
+                .iter()
+                .map(|(user, permissions)| {
+                    let actions = ADATA_ACTIONS
+                        .iter()
+                        .filter(|action| permissions.is_allowed(**action))
+                        .map(|action| GenericAction::from(*action))
+                        .collect();
+                    (user.clone(), actions)
+                })
+                .collect()
+        } else {
+            BTreeMap::new()
+        }
+    }
+}
+
+impl AccessControl for UnpubImmutableData {
+    /// Unpublished ImmutableData has no permission entries, only an owner.
+    type User = PublicKey;
+
+    fn owners(&self) -> Vec<PublicKey> {
+        vec![*self.owner()]
+    }
+
+    /// Only the owner can read or delete unpublished ImmutableData.
+    fn can(&self, requester: PublicKey, action: GenericAction) -> bool {
+        match action {
+            GenericAction::Read | GenericAction::Delete => *self.owner() == requester,
+            _ => false,
+        }
+    }
+
+    fn permitted_users(&self) -> BTreeMap<PublicKey, BTreeSet<GenericAction>> {
+        BTreeMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ADataOwner, ADataPubPermissionSet, ADataPubPermissions, AppendOnlyData, MDataPermissionSet,
+        PubSeqAppendOnlyData, UnseqMutableData, XorName,
+    };
+    use threshold_crypto::SecretKey;
+    use unwrap::unwrap;
+
+    fn gen_public_key() -> PublicKey {
+        PublicKey::Bls(SecretKey::random().public_key())
+    }
+
+    fn actions(actions: &[GenericAction]) -> BTreeSet<GenericAction> {
+        actions.iter().cloned().collect()
+    }
+
+    #[test]
+    fn mdata() {
+        let owner = gen_public_key();
+        let user = gen_public_key();
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            MDataUser::Key(user),
+            MDataPermissionSet::new()
+                .allow(MDataAction::Read)
+                .allow(MDataAction::Insert),
+        );
+        let data = MData::from(unwrap!(UnseqMutableData::new_with_data(
+            XorName([1; 32]),
+            10_000,
+            BTreeMap::new(),
+            permissions,
+            owner,
+        )));
+
+        assert_eq!(data.owners(), vec![owner]);
+        assert!(data.can(owner, GenericAction::ManagePermissions));
+        assert!(data.can(user, GenericAction::Insert));
+        assert!(!data.can(user, GenericAction::Delete));
+        assert!(!data.can(owner, GenericAction::Append));
+
+        let mut expected = BTreeMap::new();
+        let _ = expected.insert(
+            MDataUser::Key(user),
+            actions(&[GenericAction::Read, GenericAction::Insert]),
+        );
+        assert_eq!(data.permitted_users(), expected);
+    }
+
+    #[test]
+    fn adata() {
+        let owner = gen_public_key();
+        let user = gen_public_key();
+        let stranger = gen_public_key();
+        let mut inner = PubSeqAppendOnlyData::new(XorName([1; 32]), 100);
+
+        let data = AData::from(inner.clone());
+        assert!(data.owners().is_empty());
+        assert!(data.permitted_users().is_empty());
+
+        unwrap!(inner.append_owner(
+            ADataOwner {
+                public_key: owner,
+                entries_index: 0,
+                permissions_index: 0,
+            },
+            0,
+        ));
+        let mut permissions = ADataPubPermissions {
+            permissions: BTreeMap::new(),
+            entries_index: 0,
+            owners_index: 1,
+        };
+        let _ = permissions
+            .permissions
+            .insert(ADataUser::Anyone, ADataPubPermissionSet::new(true, false));
+        let _ = permissions
+            .permissions
+            .insert(ADataUser::Key(user), ADataPubPermissionSet::new(None, true));
+        unwrap!(inner.append_permissions(permissions, 0));
+        let data = AData::from(inner);
+
+        assert_eq!(data.owners(), vec![owner]);
+        assert!(data.can(stranger, GenericAction::Read));
+        assert!(data.can(stranger, GenericAction::Append));
+        assert!(!data.can(stranger, GenericAction::ManagePermissions));
+        assert!(data.can(user, GenericAction::ManagePermissions));
+        assert!(!data.can(owner, GenericAction::Insert));
+
+        let mut expected = BTreeMap::new();
+        let _ = expected.insert(
+            ADataUser::Anyone,
+            actions(&[GenericAction::Read, GenericAction::Append]),
+        );
+        let _ = expected.insert(
+            ADataUser::Key(user),
+            actions(&[GenericAction::Read, GenericAction::ManagePermissions]),
+        );
+        assert_eq!(data.permitted_users(), expected);
+    }
+
+    #[test]
+    fn unpub_idata() {
+        let owner = gen_public_key();
+        let data = UnpubImmutableData::new(vec![1, 2, 3], owner);
+
+        assert_eq!(data.owners(), vec![owner]);
+        assert!(data.can(owner, GenericAction::Read));
+        assert!(data.can(owner, GenericAction::Delete));
+        assert!(!data.can(owner, GenericAction::Update));
+        assert!(!data.can(gen_public_key(), GenericAction::Read));
+        assert!(data.permitted_users().is_empty());
+    }
+}
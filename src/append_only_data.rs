@@ -57,11 +57,14 @@
 //! index while appending. For unsequenced AppendOnlyData the client does not have to pass the
 //! index.
 
-use crate::{utils, Error, PublicKey, Result, XorName};
+use crate::{
+    group::resolve_groups, utils, Capability, DataAddress, Error, Group, GroupName, PublicKey,
+    Result, SigningContext, XorName,
+};
 use multibase::Decodable;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{btree_map, BTreeMap, BTreeSet},
     fmt::{self, Debug, Formatter},
     hash::Hash,
     ops::Range,
@@ -79,12 +82,20 @@ pub type UnpubUnseqAppendOnlyData = UnseqAppendOnlyData<UnpubPermissions>;
 pub type Entries = Vec<Entry>;
 
 /// User that can access AppendOnlyData.
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum User {
     /// Any user.
     Anyone,
     /// User identified by its public key.
     Key(PublicKey),
+    /// Every member of the named group.
+    Group(GroupName),
+}
+
+impl From<PublicKey> for User {
+    fn from(key: PublicKey) -> Self {
+        User::Key(key)
+    }
 }
 
 /// An action on AppendOnlyData.
@@ -98,6 +109,74 @@ pub enum Action {
     ManagePermissions,
 }
 
+/// The outcome of a permission check, and the reason for it.
+///
+/// Returned by `Data::explain_access`, which runs the same logic as `Data::check_permission`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessDecision {
+    /// Reading published data is always allowed.
+    PublishedRead,
+    /// The requester is the current owner, recorded at `owners_index`.
+    Owner {
+        /// Index of the owner entry which matched.
+        owners_index: u64,
+    },
+    /// The permission entry for `user` in the permissions at `permissions_index` allows the
+    /// action. `User::Group` is a group the requester belongs to, and `User::Anyone` means neither
+    /// the requester nor their groups had an applicable entry.
+    Granted {
+        /// The user whose entry decided the outcome.
+        user: User,
+        /// Index of the permissions which were checked.
+        permissions_index: u64,
+    },
+    /// The permission entry for `user` in the permissions at `permissions_index` forbids the
+    /// action. `User::Group` is a group the requester belongs to, and `User::Anyone` means neither
+    /// the requester nor their groups had an applicable entry.
+    Denied {
+        /// The user whose entry decided the outcome.
+        user: User,
+        /// Index of the permissions which were checked.
+        permissions_index: u64,
+    },
+    /// Neither the requester, their groups nor `User::Anyone` has a permission set for the action
+    /// in the permissions at `permissions_index`.
+    NoPermissionSet {
+        /// Index of the permissions which were checked.
+        permissions_index: u64,
+    },
+    /// The data has no owner.
+    InvalidOwners,
+    /// The requester isn't the owner and the data has no permissions.
+    InvalidPermissions,
+}
+
+impl AccessDecision {
+    /// Returns true if the action is allowed.
+    pub fn is_allowed(&self) -> bool {
+        match self {
+            AccessDecision::PublishedRead
+            | AccessDecision::Owner { .. }
+            | AccessDecision::Granted { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Converts the decision into the result returned by `Data::check_permission`.
+    pub fn into_result(self) -> Result<()> {
+        match self {
+            AccessDecision::PublishedRead
+            | AccessDecision::Owner { .. }
+            | AccessDecision::Granted { .. } => Ok(()),
+            AccessDecision::Denied { .. } => Err(Error::AccessDenied),
+            AccessDecision::NoPermissionSet { .. } | AccessDecision::InvalidPermissions => {
+                Err(Error::InvalidPermissions)
+            }
+            AccessDecision::InvalidOwners => Err(Error::InvalidOwners),
+        }
+    }
+}
+
 /// Index of some data.
 #[derive(Copy, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Index {
@@ -229,8 +308,29 @@ impl PubPermissionSet {
 }
 
 pub trait Perm {
-    /// Returns true if `action` is allowed for the provided user.
-    fn is_action_allowed(&self, requester: PublicKey, action: Action) -> Result<()>;
+    /// Returns the user whose entry decides whether `action` is allowed for `requester`, given
+    /// the data's `groups`, and whether it is allowed. `None` means no entry applies.
+    fn user_decision(
+        &self,
+        requester: PublicKey,
+        action: Action,
+        groups: &BTreeMap<GroupName, Group>,
+    ) -> Option<(User, bool)>;
+
+    /// Returns the actions allowed by at least one entry which the entry of the same user in
+    /// `previous` didn't allow, i.e. the actions newly granted to, or widened for, some user.
+    fn granted_actions(&self, previous: Option<&Self>) -> Vec<Action>
+    where
+        Self: Sized;
+
+    /// Returns the actions allowed by the entry of `user`.
+    fn user_granted_actions(&self, user: &User) -> Vec<Action>;
+
+    /// Returns the latest permissions of `data`, if they are of this type.
+    fn latest(data: &Data) -> Option<&Self>
+    where
+        Self: Sized;
+
     /// Gets the last entry index.
     fn entries_index(&self) -> u64;
     /// Gets the last owner index.
@@ -240,8 +340,8 @@ pub trait Perm {
 /// Unpublished permissions.
 #[derive(Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
 pub struct UnpubPermissions {
-    /// Map of users to their unpublished permission set.
-    pub permissions: BTreeMap<PublicKey, UnpubPermissionSet>,
+    /// Map of users to their unpublished permission set. Entries for `User::Anyone` are ignored.
+    pub permissions: BTreeMap<User, UnpubPermissionSet>,
     /// The current index of the data when this permission change happened.
     pub entries_index: u64,
     /// The current index of the owners when this permission change happened.
@@ -250,27 +350,62 @@ pub struct UnpubPermissions {
 
 impl UnpubPermissions {
     /// Gets the complete list of permissions.
-    pub fn permissions(&self) -> &BTreeMap<PublicKey, UnpubPermissionSet> {
+    pub fn permissions(&self) -> &BTreeMap<User, UnpubPermissionSet> {
         &self.permissions
     }
 }
 
 impl Perm for UnpubPermissions {
-    /// Returns `Ok(())` if `action` is allowed for the provided user and `Err(AccessDenied)` if
-    /// this action is not permitted.
-    fn is_action_allowed(&self, requester: PublicKey, action: Action) -> Result<()> {
-        match self.permissions.get(&requester) {
-            Some(perms) => {
-                if perms.is_allowed(action) {
-                    Ok(())
-                } else {
-                    Err(Error::AccessDenied)
-                }
+    /// Returns the requester's entry and whether it allows `action`, falling back to the entries
+    /// of the groups the requester belongs to.
+    fn user_decision(
+        &self,
+        requester: PublicKey,
+        action: Action,
+        groups: &BTreeMap<GroupName, Group>,
+    ) -> Option<(User, bool)> {
+        let user = User::Key(requester);
+        match self.permissions.get(&user) {
+            Some(perms) => Some((user, perms.is_allowed(action))),
+            None => resolve_groups(groups, &requester, |name| {
+                self.permissions
+                    .get(&User::Group(name.clone()))
+                    .map(|perms| perms.is_allowed(action))
+            })
+            .map(|(name, allowed)| (User::Group(name), allowed)),
+        }
+    }
+
+    /// Returns the actions newly allowed by at least one entry, ignoring entries for
+    /// `User::Anyone`.
+    fn granted_actions(&self, previous: Option<&Self>) -> Vec<Action> {
+        newly_granted_actions(
+            self,
+            previous,
+            self.permissions.keys(),
+            &[Action::Read, Action::Append, Action::ManagePermissions],
+        )
+    }
+
+    /// Returns the actions allowed by the entry of `user`, which is always empty for
+    /// `User::Anyone`.
+    fn user_granted_actions(&self, user: &User) -> Vec<Action> {
+        match self.permissions.get(user) {
+            Some(perms) if *user != User::Anyone => {
+                [Action::Read, Action::Append, Action::ManagePermissions]
+                    .iter()
+                    .filter(|action| perms.is_allowed(**action))
+                    .cloned()
+                    .collect()
             }
-            None => Err(Error::InvalidPermissions),
+            _ => Vec::new(),
         }
     }
 
+    fn latest(data: &Data) -> Option<&Self> {
+        data.unpub_permissions(Index::FromEnd(1)).ok()
+    }
+
     /// Returns the last entry index.
     fn entries_index(&self) -> u64 {
         self.entries_index
@@ -282,6 +417,29 @@ impl Perm for UnpubPermissions {
     }
 }
 
+// Returns the `actions` allowed by the entry of one of the `users` in `permissions` but not by
+// the entry of the same user in `previous`.
+fn newly_granted_actions<'a, P: Perm>(
+    permissions: &P,
+    previous: Option<&P>,
+    users: impl Iterator<Item = &'a User> + Clone,
+    actions: &[Action],
+) -> Vec<Action> {
+    actions
+        .iter()
+        .filter(|action| {
+            users.clone().any(|user| {
+                permissions.user_granted_actions(user).contains(action)
+                    && !previous
+                        .map(|previous| previous.user_granted_actions(user))
+                        .unwrap_or_default()
+                        .contains(action)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
 /// Published permissions.
 #[derive(Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
 pub struct PubPermissions {
@@ -309,17 +467,55 @@ impl PubPermissions {
 }
 
 impl Perm for PubPermissions {
-    /// Returns `Ok(())` if `action` is allowed for the provided user and `Err(AccessDenied)` if
-    /// this action is not permitted.
-    fn is_action_allowed(&self, requester: PublicKey, action: Action) -> Result<()> {
-        match self
-            .is_action_allowed_by_user(&User::Key(requester), action)
-            .or_else(|| self.is_action_allowed_by_user(&User::Anyone, action))
-        {
-            Some(true) => Ok(()),
-            Some(false) => Err(Error::AccessDenied),
-            None => Err(Error::InvalidPermissions),
+    /// Returns the requester's entry and whether it allows `action`, falling back to the entries
+    /// of the groups the requester belongs to and then to the entry for `User::Anyone` if the
+    /// requester's entry is missing or doesn't cover `action`.
+    fn user_decision(
+        &self,
+        requester: PublicKey,
+        action: Action,
+        groups: &BTreeMap<GroupName, Group>,
+    ) -> Option<(User, bool)> {
+        let user = User::Key(requester);
+        if let Some(allowed) = self.is_action_allowed_by_user(&user, action) {
+            return Some((user, allowed));
         }
+        resolve_groups(groups, &requester, |name| {
+            self.is_action_allowed_by_user(&User::Group(name.clone()), action)
+        })
+        .map(|(name, allowed)| (User::Group(name), allowed))
+        .or_else(|| {
+            self.is_action_allowed_by_user(&User::Anyone, action)
+                .map(|allowed| (User::Anyone, allowed))
+        })
+    }
+
+    /// Returns the actions newly allowed explicitly by at least one entry. Reading published
+    /// data is always allowed, so it's never included.
+    fn granted_actions(&self, previous: Option<&Self>) -> Vec<Action> {
+        newly_granted_actions(
+            self,
+            previous,
+            self.permissions.keys(),
+            &[Action::Append, Action::ManagePermissions],
+        )
+    }
+
+    /// Returns the actions explicitly allowed by the entry of `user`. Reading published data is
+    /// always allowed, so it's never included.
+    fn user_granted_actions(&self, user: &User) -> Vec<Action> {
+        match self.permissions.get(user) {
+            Some(perms) => [Action::Append, Action::ManagePermissions]
+                .iter()
+                .filter(|action| perms.is_allowed(**action) == Some(true))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn latest(data: &Data) -> Option<&Self> {
+        data.pub_permissions(Index::FromEnd(1)).ok()
     }
 
     /// Returns the last entry index.
@@ -370,8 +566,10 @@ pub struct Owner {
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default, Debug)]
 pub struct Entry {
     /// Key.
+    #[serde(with = "crate::human_readable::bytes")]
     pub key: Vec<u8>,
     /// Contained data.
+    #[serde(with = "crate::human_readable::bytes")]
     pub value: Vec<u8>,
 }
 
@@ -390,6 +588,9 @@ struct AppendOnly<P: Perm> {
     /// This is the history of owners, with each entry representing an owner. Each single owner
     /// could represent an individual user, or a group of users, depending on the `PublicKey` type.
     owners: Vec<Owner>,
+    /// Named groups of users, which can be given permissions as a whole. Unlike permissions and
+    /// owners, only the current membership is kept.
+    groups: BTreeMap<GroupName, Group>,
 }
 
 /// Common methods for all `AppendOnlyData` flavours.
@@ -451,6 +652,22 @@ pub trait AppendOnlyData<P> {
     /// will be returned.
     fn append_owner(&mut self, owner: Owner, owners_index: u64) -> Result<()>;
 
+    /// Returns the groups.
+    fn groups(&self) -> &BTreeMap<GroupName, Group>;
+
+    /// Adds a member to a group, creating the group if it doesn't exist.
+    ///
+    /// If the specified `version` does not match the current version of the group + 1, an error
+    /// will be returned.
+    fn add_group_member(&mut self, group: GroupName, member: PublicKey, version: u64)
+        -> Result<()>;
+
+    /// Removes a member from a group.
+    ///
+    /// If the specified `version` does not match the current version of the group + 1, an error
+    /// will be returned.
+    fn remove_group_member(&mut self, group: &str, member: &PublicKey, version: u64) -> Result<()>;
+
     /// Checks if the requester is the last owner.
     ///
     /// Returns:
@@ -517,6 +734,7 @@ macro_rules! impl_appendable_data {
                         data: Vec::new(),
                         permissions,
                         owners,
+                        groups: self.inner.groups.clone(),
                     },
                 })
             }
@@ -632,6 +850,42 @@ macro_rules! impl_appendable_data {
                 Ok(())
             }
 
+            fn groups(&self) -> &BTreeMap<GroupName, Group> {
+                &self.inner.groups
+            }
+
+            fn add_group_member(
+                &mut self,
+                group: GroupName,
+                member: PublicKey,
+                version: u64,
+            ) -> Result<()> {
+                match self.inner.groups.entry(group) {
+                    btree_map::Entry::Occupied(mut entry) => {
+                        entry.get_mut().add_member(member, version)
+                    }
+                    btree_map::Entry::Vacant(entry) => {
+                        let mut group = Group::new();
+                        group.add_member(member, version)?;
+                        let _ = entry.insert(group);
+                        Ok(())
+                    }
+                }
+            }
+
+            fn remove_group_member(
+                &mut self,
+                group: &str,
+                member: &PublicKey,
+                version: u64,
+            ) -> Result<()> {
+                self.inner
+                    .groups
+                    .get_mut(group)
+                    .ok_or(Error::NoSuchKey)?
+                    .remove_member(member, version)
+            }
+
             fn check_is_last_owner(&self, requester: PublicKey) -> Result<()> {
                 if self
                     .owner(Index::FromEnd(1))
@@ -659,6 +913,7 @@ impl SeqAppendOnlyData<PubPermissions> {
                 data: Vec::new(),
                 permissions: Vec::new(),
                 owners: Vec::new(),
+                groups: BTreeMap::new(),
             },
         }
     }
@@ -679,6 +934,7 @@ impl UnseqAppendOnlyData<PubPermissions> {
                 data: Vec::new(),
                 permissions: Vec::new(),
                 owners: Vec::new(),
+                groups: BTreeMap::new(),
             },
         }
     }
@@ -699,6 +955,7 @@ impl SeqAppendOnlyData<UnpubPermissions> {
                 data: Vec::new(),
                 permissions: Vec::new(),
                 owners: Vec::new(),
+                groups: BTreeMap::new(),
             },
         }
     }
@@ -718,6 +975,7 @@ impl UnseqAppendOnlyData<UnpubPermissions> {
                 data: Vec::new(),
                 permissions: Vec::new(),
                 owners: Vec::new(),
+                groups: BTreeMap::new(),
             },
         }
     }
@@ -776,20 +1034,30 @@ where
     }
 }
 
-macro_rules! check_perm {
+macro_rules! explain_access {
     ($data: ident, $requester: ident, $action: ident) => {
-        if $data
-            .owner(Index::FromEnd(1))
-            .ok_or(Error::InvalidOwners)?
-            .public_key
-            == $requester
-        {
-            Ok(())
-        } else {
-            $data
-                .permissions(Index::FromEnd(1))
-                .ok_or(Error::InvalidPermissions)?
-                .is_action_allowed($requester, $action)
+        match $data.owner(Index::FromEnd(1)) {
+            None => AccessDecision::InvalidOwners,
+            Some(owner) if owner.public_key == $requester => AccessDecision::Owner {
+                owners_index: $data.owners_index() - 1,
+            },
+            Some(_) => match $data.permissions(Index::FromEnd(1)) {
+                None => AccessDecision::InvalidPermissions,
+                Some(permissions) => {
+                    let permissions_index = $data.permissions_index() - 1;
+                    match permissions.user_decision($requester, $action, $data.groups()) {
+                        Some((user, true)) => AccessDecision::Granted {
+                            user,
+                            permissions_index,
+                        },
+                        Some((user, false)) => AccessDecision::Denied {
+                            user,
+                            permissions_index,
+                        },
+                        None => AccessDecision::NoPermissionSet { permissions_index },
+                    }
+                }
+            },
         }
     };
 }
@@ -1022,21 +1290,68 @@ impl Data {
     /// invalid,
     /// `Err::AccessDenied` if the action is not allowed.
     pub fn check_permission(&self, action: Action, requester: PublicKey) -> Result<()> {
+        self.explain_access(requester, action).into_result()
+    }
+
+    /// Checks that `capability` allows the provided user to perform `action` at time `now`, in
+    /// seconds since the UNIX epoch, on the network of `context`. A valid capability is an
+    /// alternative to a permission entry.
+    ///
+    /// Returns `Err::InvalidOwners` if the last owner is invalid, and otherwise the result of
+    /// `Capability::verify` against the last owner.
+    pub fn check_capability(
+        &self,
+        context: &SigningContext,
+        capability: &Capability,
+        action: Action,
+        requester: PublicKey,
+        now: u64,
+    ) -> Result<()> {
+        let owner = self.owner(Index::FromEnd(1)).ok_or(Error::InvalidOwners)?;
+        capability.verify(
+            context,
+            &owner.public_key,
+            &DataAddress::AppendOnly(*self.address()),
+            &requester,
+            action.into(),
+            now,
+        )
+    }
+
+    /// Checks that `requester` may append `permissions`: the owner may grant anything, other users
+    /// need `ManagePermissions` and may only grant actions they are allowed themselves. Only the
+    /// actions which the new permissions grant or widen compared to the latest ones are checked.
+    ///
+    /// Returns the error of `check_permission` if the requester can't manage permissions, and
+    /// `Err::PrivilegeEscalation` if the new permissions exceed the requester's own rights.
+    pub fn check_permissions_grant<P: Perm>(
+        &self,
+        permissions: &P,
+        requester: PublicKey,
+    ) -> Result<()> {
+        self.check_permission(Action::ManagePermissions, requester)?;
+        if permissions
+            .granted_actions(P::latest(self))
+            .into_iter()
+            .all(|action| self.explain_access(requester, action).is_allowed())
+        {
+            Ok(())
+        } else {
+            Err(Error::PrivilegeEscalation)
+        }
+    }
+
+    /// Explains the outcome of `check_permission` for given `action` and the provided user: which
+    /// owner or permission entry decided it, or why no decision could be made.
+    pub fn explain_access(&self, requester: PublicKey, action: Action) -> AccessDecision {
         match self {
-            Data::PubSeq(data) => {
-                if action == Action::Read {
-                    return Ok(());
-                }
-                check_perm!(data, requester, action)
+            Data::PubSeq(_) | Data::PubUnseq(_) if action == Action::Read => {
+                AccessDecision::PublishedRead
             }
-            Data::PubUnseq(data) => {
-                if action == Action::Read {
-                    return Ok(());
-                }
-                check_perm!(data, requester, action)
-            }
-            Data::UnpubSeq(data) => check_perm!(data, requester, action),
-            Data::UnpubUnseq(data) => check_perm!(data, requester, action),
+            Data::PubSeq(data) => explain_access!(data, requester, action),
+            Data::PubUnseq(data) => explain_access!(data, requester, action),
+            Data::UnpubSeq(data) => explain_access!(data, requester, action),
+            Data::UnpubUnseq(data) => explain_access!(data, requester, action),
         }
     }
 
@@ -1070,6 +1385,16 @@ impl Data {
         }
     }
 
+    /// Returns all entries.
+    pub fn entries(&self) -> &Entries {
+        match self {
+            Data::PubSeq(data) => data.entries(),
+            Data::PubUnseq(data) => data.entries(),
+            Data::UnpubSeq(data) => data.entries(),
+            Data::UnpubUnseq(data) => data.entries(),
+        }
+    }
+
     /// Gets a list of keys and values with the given indices.
     pub fn in_range(&self, start: Index, end: Index) -> Option<Entries> {
         match self {
@@ -1123,6 +1448,115 @@ impl Data {
         }
     }
 
+    /// Returns the groups.
+    pub fn groups(&self) -> &BTreeMap<GroupName, Group> {
+        match self {
+            Data::PubSeq(data) => data.groups(),
+            Data::PubUnseq(data) => data.groups(),
+            Data::UnpubSeq(data) => data.groups(),
+            Data::UnpubUnseq(data) => data.groups(),
+        }
+    }
+
+    /// Adds a member to a group, creating the group if it doesn't exist.
+    pub fn add_group_member(
+        &mut self,
+        group: GroupName,
+        member: PublicKey,
+        version: u64,
+    ) -> Result<()> {
+        match self {
+            Data::PubSeq(data) => data.add_group_member(group, member, version),
+            Data::PubUnseq(data) => data.add_group_member(group, member, version),
+            Data::UnpubSeq(data) => data.add_group_member(group, member, version),
+            Data::UnpubUnseq(data) => data.add_group_member(group, member, version),
+        }
+    }
+
+    /// Adds a member to a group on behalf of `requester`: the owner may add anyone, other users
+    /// need `ManagePermissions` and must themselves hold every action the group is allowed, since
+    /// the new member gains them all.
+    ///
+    /// Returns the error of `check_permission` if the requester can't manage permissions, and
+    /// `Err::PrivilegeEscalation` if the group's permissions exceed the requester's own rights.
+    pub fn add_group_member_checked(
+        &mut self,
+        group: GroupName,
+        member: PublicKey,
+        version: u64,
+        requester: PublicKey,
+    ) -> Result<()> {
+        self.check_permission(Action::ManagePermissions, requester)?;
+        let user = User::Group(group.clone());
+        let granted = match self {
+            Data::PubSeq(data) => data
+                .permissions(Index::FromEnd(1))
+                .map(|perms| perms.user_granted_actions(&user)),
+            Data::PubUnseq(data) => data
+                .permissions(Index::FromEnd(1))
+                .map(|perms| perms.user_granted_actions(&user)),
+            Data::UnpubSeq(data) => data
+                .permissions(Index::FromEnd(1))
+                .map(|perms| perms.user_granted_actions(&user)),
+            Data::UnpubUnseq(data) => data
+                .permissions(Index::FromEnd(1))
+                .map(|perms| perms.user_granted_actions(&user)),
+        }
+        .unwrap_or_default();
+        if !granted
+            .into_iter()
+            .all(|action| self.explain_access(requester, action).is_allowed())
+        {
+            return Err(Error::PrivilegeEscalation);
+        }
+        self.add_group_member(group, member, version)
+    }
+
+    /// Removes a member from a group.
+    pub fn remove_group_member(
+        &mut self,
+        group: &str,
+        member: &PublicKey,
+        version: u64,
+    ) -> Result<()> {
+        match self {
+            Data::PubSeq(data) => data.remove_group_member(group, member, version),
+            Data::PubUnseq(data) => data.remove_group_member(group, member, version),
+            Data::UnpubSeq(data) => data.remove_group_member(group, member, version),
+            Data::UnpubUnseq(data) => data.remove_group_member(group, member, version),
+        }
+    }
+
+    /// Removes a member from a group on behalf of `requester`: the owner may remove anyone, other
+    /// users need `ManagePermissions` and mustn't give the member any action they aren't allowed
+    /// themselves, since the member may fall back to broader permissions, e.g. those of
+    /// `User::Anyone`.
+    ///
+    /// Returns the error of `check_permission` if the requester can't manage permissions, and
+    /// `Err::PrivilegeEscalation` if the removal exceeds the requester's own rights.
+    pub fn remove_group_member_checked(
+        &mut self,
+        group: &str,
+        member: &PublicKey,
+        version: u64,
+        requester: PublicKey,
+    ) -> Result<()> {
+        self.check_permission(Action::ManagePermissions, requester)?;
+        let mut changed = self.shell(Index::FromEnd(0))?;
+        changed.remove_group_member(group, member, version)?;
+        let escalates = [Action::Read, Action::Append, Action::ManagePermissions]
+            .iter()
+            .any(|&action| {
+                changed.explain_access(*member, action).is_allowed()
+                    && !self.explain_access(*member, action).is_allowed()
+                    && !self.explain_access(requester, action).is_allowed()
+            });
+        if escalates {
+            return Err(Error::PrivilegeEscalation);
+        }
+        self.remove_group_member(group, member, version)
+    }
+
     /// Checks if the requester is the last owner.
     ///
     /// Returns:
@@ -1154,7 +1588,7 @@ impl Data {
     /// Returns unpublished user permissions, if applicable.
     pub fn unpub_user_permissions(
         &self,
-        user: PublicKey,
+        user: User,
         index: impl Into<Index>,
     ) -> Result<UnpubPermissionSet> {
         self.unpub_permissions(index)?
@@ -1537,9 +1971,10 @@ mod tests {
             entries_index: 0,
             owners_index: 0,
         };
-        let _ = unpub_perms
-            .permissions
-            .insert(public_key, UnpubPermissionSet::new(false, false, false));
+        let _ = unpub_perms.permissions.insert(
+            User::Key(public_key),
+            UnpubPermissionSet::new(false, false, false),
+        );
 
         // pub, unseq
         let mut data = PubUnseqAppendOnlyData::new(rand::random(), 20);
@@ -1554,7 +1989,7 @@ mod tests {
             Ok(PubPermissionSet::new(false, false))
         );
         assert_eq!(
-            data.unpub_user_permissions(public_key, 0),
+            data.unpub_user_permissions(User::Key(public_key), 0),
             Err(Error::NoSuchData)
         );
         assert_eq!(
@@ -1575,7 +2010,7 @@ mod tests {
             Ok(PubPermissionSet::new(false, false))
         );
         assert_eq!(
-            data.unpub_user_permissions(public_key, 0),
+            data.unpub_user_permissions(User::Key(public_key), 0),
             Err(Error::NoSuchData)
         );
         assert_eq!(
@@ -1592,7 +2027,7 @@ mod tests {
         assert_eq!(data.pub_permissions(0), Err(Error::NoSuchData));
 
         assert_eq!(
-            data.unpub_user_permissions(public_key, 0),
+            data.unpub_user_permissions(User::Key(public_key), 0),
             Ok(UnpubPermissionSet::new(false, false, false))
         );
         assert_eq!(
@@ -1600,7 +2035,7 @@ mod tests {
             Err(Error::NoSuchData)
         );
         assert_eq!(
-            data.unpub_user_permissions(invalid_public_key, 0),
+            data.unpub_user_permissions(User::Key(invalid_public_key), 0),
             Err(Error::NoSuchEntry)
         );
 
@@ -1613,7 +2048,7 @@ mod tests {
         assert_eq!(data.pub_permissions(0), Err(Error::NoSuchData));
 
         assert_eq!(
-            data.unpub_user_permissions(public_key, 0),
+            data.unpub_user_permissions(User::Key(public_key), 0),
             Ok(UnpubPermissionSet::new(false, false, false))
         );
         assert_eq!(
@@ -1621,7 +2056,7 @@ mod tests {
             Err(Error::NoSuchData)
         );
         assert_eq!(
-            data.unpub_user_permissions(invalid_public_key, 0),
+            data.unpub_user_permissions(User::Key(invalid_public_key), 0),
             Err(Error::NoSuchEntry)
         );
     }
@@ -1737,9 +2172,10 @@ mod tests {
             entries_index: 0,
             owners_index: 1,
         };
-        let _ = permissions
-            .permissions
-            .insert(public_key_1, UnpubPermissionSet::new(true, true, false));
+        let _ = permissions.permissions.insert(
+            User::Key(public_key_1),
+            UnpubPermissionSet::new(true, true, false),
+        );
         unwrap!(inner.append_permissions(permissions, 0));
         let data = Data::from(inner);
 
@@ -1765,4 +2201,342 @@ mod tests {
             Err(Error::InvalidPermissions)
         );
     }
+
+    #[test]
+    fn explain_access() {
+        let public_key_0 = gen_public_key();
+        let public_key_1 = gen_public_key();
+        let public_key_2 = gen_public_key();
+        let mut pub_inner = SeqAppendOnlyData::<PubPermissions>::new(XorName([1; 32]), 100);
+        let mut unpub_inner = SeqAppendOnlyData::<UnpubPermissions>::new(XorName([1; 32]), 100);
+
+        // no owner
+        let data = Data::from(pub_inner.clone());
+        assert_eq!(
+            data.explain_access(public_key_0, Action::Append),
+            AccessDecision::InvalidOwners
+        );
+        assert_eq!(
+            data.explain_access(public_key_0, Action::Read),
+            AccessDecision::PublishedRead
+        );
+
+        // no permissions
+        let owner = Owner {
+            public_key: public_key_0,
+            entries_index: 0,
+            permissions_index: 0,
+        };
+        unwrap!(pub_inner.append_owner(owner, 0));
+        unwrap!(unpub_inner.append_owner(owner, 0));
+        let data = Data::from(pub_inner.clone());
+        assert_eq!(
+            data.explain_access(public_key_0, Action::Append),
+            AccessDecision::Owner { owners_index: 0 }
+        );
+        assert_eq!(
+            data.explain_access(public_key_1, Action::Append),
+            AccessDecision::InvalidPermissions
+        );
+
+        // with permissions
+        let mut pub_permissions = PubPermissions {
+            permissions: BTreeMap::new(),
+            entries_index: 0,
+            owners_index: 1,
+        };
+        let _ = pub_permissions
+            .permissions
+            .insert(User::Anyone, PubPermissionSet::new(true, false));
+        let _ = pub_permissions
+            .permissions
+            .insert(User::Key(public_key_1), PubPermissionSet::new(None, true));
+        unwrap!(pub_inner.append_permissions(pub_permissions, 0));
+        let data = Data::from(pub_inner);
+
+        assert_eq!(
+            data.explain_access(public_key_1, Action::ManagePermissions),
+            AccessDecision::Granted {
+                user: User::Key(public_key_1),
+                permissions_index: 0,
+            }
+        );
+        assert_eq!(
+            data.explain_access(public_key_1, Action::Append),
+            AccessDecision::Granted {
+                user: User::Anyone,
+                permissions_index: 0,
+            }
+        );
+        assert_eq!(
+            data.explain_access(public_key_2, Action::ManagePermissions),
+            AccessDecision::Denied {
+                user: User::Anyone,
+                permissions_index: 0,
+            }
+        );
+
+        let mut unpub_permissions = UnpubPermissions {
+            permissions: BTreeMap::new(),
+            entries_index: 0,
+            owners_index: 1,
+        };
+        let _ = unpub_permissions.permissions.insert(
+            User::Key(public_key_1),
+            UnpubPermissionSet::new(true, false, false),
+        );
+        unwrap!(unpub_inner.append_permissions(unpub_permissions, 0));
+        let data = Data::from(unpub_inner);
+
+        assert_eq!(
+            data.explain_access(public_key_1, Action::Append),
+            AccessDecision::Denied {
+                user: User::Key(public_key_1),
+                permissions_index: 0,
+            }
+        );
+        assert_eq!(
+            data.explain_access(public_key_2, Action::Read),
+            AccessDecision::NoPermissionSet {
+                permissions_index: 0,
+            }
+        );
+
+        // the decision agrees with the permission check
+        for key in &[public_key_0, public_key_1, public_key_2] {
+            for action in &[Action::Read, Action::Append, Action::ManagePermissions] {
+                assert_eq!(
+                    data.explain_access(*key, *action).into_result(),
+                    data.check_permission(*action, *key)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn group_permissions() {
+        let owner = gen_public_key();
+        let member = gen_public_key();
+        let mut inner = SeqAppendOnlyData::<PubPermissions>::new(XorName([1; 32]), 100);
+        unwrap!(inner.append_owner(
+            Owner {
+                public_key: owner,
+                entries_index: 0,
+                permissions_index: 0,
+            },
+            0,
+        ));
+        let mut permissions = PubPermissions {
+            permissions: BTreeMap::new(),
+            entries_index: 0,
+            owners_index: 1,
+        };
+        let _ = permissions
+            .permissions
+            .insert(User::Anyone, PubPermissionSet::new(false, false));
+        let _ = permissions.permissions.insert(
+            User::Group("writers".to_string()),
+            PubPermissionSet::new(true, None),
+        );
+        unwrap!(inner.append_permissions(permissions, 0));
+
+        assert_eq!(
+            Data::from(inner.clone()).check_permission(Action::Append, member),
+            Err(Error::AccessDenied)
+        );
+
+        unwrap!(inner.add_group_member("writers".to_string(), member, 1));
+        let data = Data::from(inner.clone());
+        assert_eq!(
+            data.explain_access(member, Action::Append),
+            AccessDecision::Granted {
+                user: User::Group("writers".to_string()),
+                permissions_index: 0,
+            }
+        );
+        // the group has no entry for the action, so `Anyone` applies
+        assert_eq!(
+            data.explain_access(member, Action::ManagePermissions),
+            AccessDecision::Denied {
+                user: User::Anyone,
+                permissions_index: 0,
+            }
+        );
+
+        assert_eq!(
+            inner.add_group_member("writers".to_string(), member, 2),
+            Err(Error::GroupMemberExists)
+        );
+        unwrap!(inner.remove_group_member("writers", &member, 2));
+        assert_eq!(
+            Data::from(inner.clone()).check_permission(Action::Append, member),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(inner.permissions_index(), 1);
+    }
+
+    #[test]
+    fn check_permissions_grant() {
+        let owner = gen_public_key();
+        let manager = gen_public_key();
+        let user = gen_public_key();
+        let appender = gen_public_key();
+        let mut inner = SeqAppendOnlyData::<UnpubPermissions>::new(XorName([1; 32]), 100);
+        unwrap!(inner.append_owner(
+            Owner {
+                public_key: owner,
+                entries_index: 0,
+                permissions_index: 0,
+            },
+            0,
+        ));
+        let mut permissions = UnpubPermissions {
+            permissions: BTreeMap::new(),
+            entries_index: 0,
+            owners_index: 1,
+        };
+        let _ = permissions.permissions.insert(
+            User::Key(manager),
+            UnpubPermissionSet::new(true, false, true),
+        );
+        let _ = permissions.permissions.insert(
+            User::Key(appender),
+            UnpubPermissionSet::new(true, true, false),
+        );
+        unwrap!(inner.append_permissions(permissions.clone(), 0));
+        let data = Data::from(inner);
+
+        // the manager can pass on what it holds, keeping the existing appender
+        let _ = permissions
+            .permissions
+            .insert(User::Key(user), UnpubPermissionSet::new(true, false, false));
+        assert_eq!(data.check_permissions_grant(&permissions, manager), Ok(()));
+
+        // and revoke what others hold
+        let _ = permissions.permissions.remove(&User::Key(appender));
+        assert_eq!(data.check_permissions_grant(&permissions, manager), Ok(()));
+
+        // but can't grant appending, to anyone
+        let _ = permissions
+            .permissions
+            .insert(User::Key(user), UnpubPermissionSet::new(true, true, false));
+        assert_eq!(
+            data.check_permissions_grant(&permissions, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(data.check_permissions_grant(&permissions, owner), Ok(()));
+        assert_eq!(
+            data.check_permissions_grant(&permissions, user),
+            Err(Error::InvalidPermissions)
+        );
+
+        // entries for `Anyone` are ignored in unpublished permissions
+        let _ = permissions
+            .permissions
+            .insert(User::Key(user), UnpubPermissionSet::new(true, false, false));
+        let _ = permissions
+            .permissions
+            .insert(User::Anyone, UnpubPermissionSet::new(true, true, true));
+        assert_eq!(data.check_permissions_grant(&permissions, manager), Ok(()));
+    }
+
+    #[test]
+    fn add_group_member_checked() {
+        let owner = gen_public_key();
+        let manager = gen_public_key();
+        let user = gen_public_key();
+        let mut inner = SeqAppendOnlyData::<UnpubPermissions>::new(XorName([1; 32]), 100);
+        unwrap!(inner.append_owner(
+            Owner {
+                public_key: owner,
+                entries_index: 0,
+                permissions_index: 0,
+            },
+            0,
+        ));
+        let mut permissions = UnpubPermissions {
+            permissions: BTreeMap::new(),
+            entries_index: 0,
+            owners_index: 1,
+        };
+        let _ = permissions.permissions.insert(
+            User::Key(manager),
+            UnpubPermissionSet::new(true, false, true),
+        );
+        let _ = permissions.permissions.insert(
+            User::Group("editors".to_string()),
+            UnpubPermissionSet::new(true, true, false),
+        );
+        let _ = permissions.permissions.insert(
+            User::Group("readers".to_string()),
+            UnpubPermissionSet::new(true, false, false),
+        );
+        unwrap!(inner.append_permissions(permissions, 0));
+        let mut data = Data::from(inner);
+
+        // joining a group grants its permissions, so the manager can't add to "editors"
+        assert_eq!(
+            data.add_group_member_checked("editors".to_string(), manager, 1, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(
+            data.add_group_member_checked("editors".to_string(), user, 1, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        unwrap!(data.add_group_member_checked("readers".to_string(), user, 1, manager));
+        unwrap!(data.add_group_member_checked("editors".to_string(), user, 1, owner));
+        assert_eq!(
+            data.add_group_member_checked("readers".to_string(), manager, 2, user),
+            Err(Error::AccessDenied)
+        );
+    }
+
+    #[test]
+    fn remove_group_member_checked() {
+        let owner = gen_public_key();
+        let manager = gen_public_key();
+        let user = gen_public_key();
+        let mut inner = SeqAppendOnlyData::<PubPermissions>::new(XorName([1; 32]), 100);
+        unwrap!(inner.append_owner(
+            Owner {
+                public_key: owner,
+                entries_index: 0,
+                permissions_index: 0,
+            },
+            0,
+        ));
+        let mut permissions = PubPermissions {
+            permissions: BTreeMap::new(),
+            entries_index: 0,
+            owners_index: 1,
+        };
+        let _ = permissions
+            .permissions
+            .insert(User::Anyone, PubPermissionSet::new(true, false));
+        let _ = permissions
+            .permissions
+            .insert(User::Key(manager), PubPermissionSet::new(false, true));
+        let _ = permissions.permissions.insert(
+            User::Group("muted".to_string()),
+            PubPermissionSet::new(false, None),
+        );
+        unwrap!(inner.append_permissions(permissions, 0));
+        let mut data = Data::from(inner);
+        unwrap!(data.add_group_member("muted".to_string(), user, 1));
+        unwrap!(data.add_group_member("muted".to_string(), manager, 2));
+
+        // leaving "muted" falls back to `Anyone`, which allows appending
+        assert_eq!(
+            data.remove_group_member_checked("muted", &user, 3, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(
+            data.remove_group_member_checked("muted", &manager, 3, user),
+            Err(Error::AccessDenied)
+        );
+        // the manager's own entry still denies appending
+        unwrap!(data.remove_group_member_checked("muted", &manager, 3, manager));
+        unwrap!(data.remove_group_member_checked("muted", &user, 4, owner));
+        assert_eq!(data.check_permission(Action::Append, user), Ok(()));
+    }
 }
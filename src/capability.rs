@@ -0,0 +1,611 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Signed capability tokens.
+//!
+//! A `Capability` lets the owner of some data share it without changing the data's permissions.
+//! The owner signs a grant of some actions on the data to a key, or to whoever holds the token,
+//! until an expiry time. A holder can delegate the capability by signing a further grant of a
+//! subset of those actions, expiring no later. The resulting chain of grants is attached to a
+//! request and checked against the current owner of the data.
+//!
+//! Grants are signed via a `SigningContext`, so a capability issued on one network is rejected by
+//! every other network.
+
+use crate::{
+    DataAddress, Error, GenericAction, PublicKey, Result, Signature, SignatureDomain, Signer,
+    SigningContext,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, iter};
+
+/// Maximum number of grants in a capability, i.e. the owner's grant and its delegations.
+pub const MAX_CAPABILITY_CHAIN_LEN: usize = 8;
+
+/// Who can use a grant.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Grantee {
+    /// Only the holder of the given key.
+    Key(PublicKey),
+    /// Anyone holding the token.
+    Bearer,
+}
+
+impl Grantee {
+    fn includes(self, key: &PublicKey) -> bool {
+        match self {
+            Grantee::Key(grantee) => grantee == *key,
+            Grantee::Bearer => true,
+        }
+    }
+}
+
+/// A single signed grant of actions on some data.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Grant {
+    address: DataAddress,
+    actions: BTreeSet<GenericAction>,
+    grantee: Grantee,
+    expiry: u64,
+    nonce: u64,
+    issuer: PublicKey,
+    signature: Signature,
+}
+
+impl Grant {
+    fn new<S: Signer>(
+        context: &SigningContext,
+        signer: &S,
+        address: DataAddress,
+        actions: BTreeSet<GenericAction>,
+        grantee: Grantee,
+        expiry: u64,
+        parent: Option<&Grant>,
+    ) -> Self {
+        let issuer = signer.signer_public_id().signing_key();
+        let nonce = rand::random();
+        let payload = signing_bytes(
+            context,
+            &address,
+            &actions,
+            grantee,
+            expiry,
+            nonce,
+            &issuer,
+            parent.map(|parent| &parent.signature),
+        );
+        Self {
+            address,
+            actions,
+            grantee,
+            expiry,
+            nonce,
+            issuer,
+            signature: signer.sign_data(&payload),
+        }
+    }
+
+    /// Returns the address of the data the grant applies to.
+    pub fn address(&self) -> &DataAddress {
+        &self.address
+    }
+
+    /// Returns the granted actions.
+    pub fn actions(&self) -> &BTreeSet<GenericAction> {
+        &self.actions
+    }
+
+    /// Returns who can use the grant.
+    pub fn grantee(&self) -> Grantee {
+        self.grantee
+    }
+
+    /// Returns the time, in seconds since the UNIX epoch, from which the grant is no longer valid.
+    pub fn expiry(&self) -> u64 {
+        self.expiry
+    }
+
+    /// Returns the random nonce identifying the grant, e.g. for revoking it.
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Returns the key which signed the grant.
+    pub fn issuer(&self) -> &PublicKey {
+        &self.issuer
+    }
+
+    fn verify_signature(&self, context: &SigningContext, parent: Option<&Grant>) -> Result<()> {
+        self.issuer.verify(
+            &self.signature,
+            signing_bytes(
+                context,
+                &self.address,
+                &self.actions,
+                self.grantee,
+                self.expiry,
+                self.nonce,
+                &self.issuer,
+                parent.map(|parent| &parent.signature),
+            ),
+        )
+    }
+
+    /// Returns true if `self` grants nothing beyond `parent` and may be issued by its grantee.
+    fn is_attenuation_of(&self, parent: &Grant) -> bool {
+        self.address == parent.address
+            && self.actions.is_subset(&parent.actions)
+            && self.expiry <= parent.expiry
+            && parent.grantee.includes(&self.issuer)
+    }
+}
+
+/// A grant by the owner of some data, followed by any delegations of it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Capability {
+    root: Grant,
+    delegations: Vec<Grant>,
+}
+
+impl Capability {
+    /// Creates a capability granting `actions` on the data at `address` to `grantee` until
+    /// `expiry`, in seconds since the UNIX epoch, on the network of `context`. `signer` must be the
+    /// owner of the data for the capability to be valid.
+    ///
+    /// Returns `Err(InvalidOperation)` if any of `actions` can't be granted by a capability on
+    /// the kind of data at `address`, e.g. managing permissions.
+    pub fn new<S: Signer>(
+        context: &SigningContext,
+        signer: &S,
+        address: DataAddress,
+        actions: BTreeSet<GenericAction>,
+        grantee: Grantee,
+        expiry: u64,
+    ) -> Result<Self> {
+        let grantable = grantable_actions(&address);
+        if !actions.iter().all(|action| grantable.contains(action)) {
+            return Err(Error::InvalidOperation);
+        }
+        Ok(Self {
+            root: Grant::new(context, signer, address, actions, grantee, expiry, None),
+            delegations: Vec::new(),
+        })
+    }
+
+    /// Delegates the capability on the network of `context`, signed by `signer`, which must be
+    /// its grantee.
+    ///
+    /// Returns `Err(AccessDenied)` if `signer` can't use the capability, and
+    /// `Err(PrivilegeEscalation)` if `actions` or `expiry` exceed those of the capability.
+    pub fn delegate<S: Signer>(
+        &self,
+        context: &SigningContext,
+        signer: &S,
+        actions: BTreeSet<GenericAction>,
+        grantee: Grantee,
+        expiry: u64,
+    ) -> Result<Self> {
+        if self.delegations.len() + 1 >= MAX_CAPABILITY_CHAIN_LEN {
+            return Err(Error::AccessDenied);
+        }
+        let parent = self.last();
+        if !parent
+            .grantee
+            .includes(&signer.signer_public_id().signing_key())
+        {
+            return Err(Error::AccessDenied);
+        }
+        if !actions.is_subset(&parent.actions) || expiry > parent.expiry {
+            return Err(Error::PrivilegeEscalation);
+        }
+
+        let grant = Grant::new(
+            context,
+            signer,
+            parent.address,
+            actions,
+            grantee,
+            expiry,
+            Some(parent),
+        );
+        let mut delegated = self.clone();
+        delegated.delegations.push(grant);
+        Ok(delegated)
+    }
+
+    /// Returns the grants, starting with the owner's.
+    pub fn grants(&self) -> impl Iterator<Item = &Grant> {
+        iter::once(&self.root).chain(&self.delegations)
+    }
+
+    /// Returns the address of the data the capability applies to.
+    pub fn address(&self) -> &DataAddress {
+        &self.root.address
+    }
+
+    /// Returns the actions the holder of the capability can perform.
+    pub fn actions(&self) -> &BTreeSet<GenericAction> {
+        &self.last().actions
+    }
+
+    /// Returns who can use the capability.
+    pub fn grantee(&self) -> Grantee {
+        self.last().grantee
+    }
+
+    /// Returns the time, in seconds since the UNIX epoch, from which the capability is no longer
+    /// valid.
+    pub fn expiry(&self) -> u64 {
+        self.last().expiry
+    }
+
+    /// Verifies that the capability allows `requester` to perform `action` on the data at
+    /// `address`, owned by `owner`, at time `now` in seconds since the UNIX epoch, on the network
+    /// of `context`.
+    ///
+    /// Returns:
+    /// `Err::InvalidSignature` if any of the grants isn't validly signed for the network,
+    /// `Err::PrivilegeEscalation` if a delegation exceeds the grant it's based on,
+    /// `Err::CapabilityExpired` if the capability has expired,
+    /// `Err::AccessDenied` if the capability isn't for the data or the owner, or doesn't allow
+    /// the requester to perform `action`.
+    pub fn verify(
+        &self,
+        context: &SigningContext,
+        owner: &PublicKey,
+        address: &DataAddress,
+        requester: &PublicKey,
+        action: GenericAction,
+        now: u64,
+    ) -> Result<()> {
+        if self.delegations.len() >= MAX_CAPABILITY_CHAIN_LEN {
+            return Err(Error::AccessDenied);
+        }
+
+        let mut parent: Option<&Grant> = None;
+        for grant in self.grants() {
+            grant.verify_signature(context, parent)?;
+            match parent {
+                None if grant.issuer != *owner || grant.address != *address => {
+                    return Err(Error::AccessDenied)
+                }
+                Some(parent) if !grant.is_attenuation_of(parent) => {
+                    return Err(Error::PrivilegeEscalation)
+                }
+                _ => (),
+            }
+            parent = Some(grant);
+        }
+
+        let grant = self.last();
+        if now >= grant.expiry {
+            return Err(Error::CapabilityExpired);
+        }
+        if !grant.grantee.includes(requester) || !grant.actions.contains(&action) {
+            return Err(Error::AccessDenied);
+        }
+        Ok(())
+    }
+
+    fn last(&self) -> &Grant {
+        self.delegations.last().unwrap_or(&self.root)
+    }
+}
+
+// Actions a capability can stand in for a permission entry for on the data at `address`.
+// Permissions and owners can only be changed by users allowed to do so by the data itself.
+fn grantable_actions(address: &DataAddress) -> &'static [GenericAction] {
+    match address {
+        DataAddress::Immutable(_) => &[GenericAction::Read, GenericAction::Delete],
+        DataAddress::Mutable(_) => &[
+            GenericAction::Read,
+            GenericAction::Insert,
+            GenericAction::Update,
+            GenericAction::Delete,
+        ],
+        DataAddress::AppendOnly(_) => &[GenericAction::Read, GenericAction::Append],
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn signing_bytes(
+    context: &SigningContext,
+    address: &DataAddress,
+    actions: &BTreeSet<GenericAction>,
+    grantee: Grantee,
+    expiry: u64,
+    nonce: u64,
+    issuer: &PublicKey,
+    parent_signature: Option<&Signature>,
+) -> Vec<u8> {
+    context.payload(
+        SignatureDomain::Capability,
+        &(
+            address,
+            actions,
+            grantee,
+            expiry,
+            nonce,
+            issuer,
+            parent_signature,
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientFullId, IDataAddress, NetworkId};
+    use unwrap::unwrap;
+
+    const NOW: u64 = 1_000_000;
+
+    fn actions(actions: &[GenericAction]) -> BTreeSet<GenericAction> {
+        actions.iter().cloned().collect()
+    }
+
+    fn key(id: &ClientFullId) -> PublicKey {
+        *id.public_id().public_key()
+    }
+
+    #[test]
+    fn owner_grant() {
+        let mut rng = rand::thread_rng();
+        let context = SigningContext::new(NetworkId([1; 32]));
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let user = ClientFullId::new_bls(&mut rng);
+        let address = DataAddress::Immutable(IDataAddress::Unpub(rand::random()));
+
+        let capability = unwrap!(Capability::new(
+            &context,
+            &owner,
+            address,
+            actions(&[GenericAction::Read]),
+            Grantee::Key(key(&user)),
+            NOW + 1,
+        ));
+        unwrap!(capability.verify(
+            &context,
+            &key(&owner),
+            &address,
+            &key(&user),
+            GenericAction::Read,
+            NOW
+        ));
+
+        // wrong action, requester, data, owner or time
+        assert_eq!(
+            capability.verify(
+                &context,
+                &key(&owner),
+                &address,
+                &key(&user),
+                GenericAction::Delete,
+                NOW
+            ),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(
+            capability.verify(
+                &context,
+                &key(&owner),
+                &address,
+                &key(&owner),
+                GenericAction::Read,
+                NOW
+            ),
+            Err(Error::AccessDenied)
+        );
+        let other_address = DataAddress::Immutable(IDataAddress::Unpub(rand::random()));
+        assert_eq!(
+            capability.verify(
+                &context,
+                &key(&owner),
+                &other_address,
+                &key(&user),
+                GenericAction::Read,
+                NOW
+            ),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(
+            capability.verify(
+                &context,
+                &key(&user),
+                &address,
+                &key(&user),
+                GenericAction::Read,
+                NOW
+            ),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(
+            capability.verify(
+                &context,
+                &key(&owner),
+                &address,
+                &key(&user),
+                GenericAction::Read,
+                NOW + 1
+            ),
+            Err(Error::CapabilityExpired)
+        );
+
+        // the grant is bound to the network it was issued for
+        assert_eq!(
+            capability.verify(
+                &SigningContext::new(NetworkId([2; 32])),
+                &key(&owner),
+                &address,
+                &key(&user),
+                GenericAction::Read,
+                NOW
+            ),
+            Err(Error::InvalidSignature)
+        );
+
+        // permissions can't be managed through capabilities
+        let manage = actions(&[GenericAction::Read, GenericAction::ManagePermissions]);
+        assert_eq!(
+            Capability::new(&context, &owner, address, manage, Grantee::Bearer, NOW + 1),
+            Err(Error::InvalidOperation)
+        );
+
+        // tampering invalidates the signature
+        let mut tampered = capability.clone();
+        let _ = tampered.root.actions.insert(GenericAction::Delete);
+        assert_eq!(
+            tampered.verify(
+                &context,
+                &key(&owner),
+                &address,
+                &key(&user),
+                GenericAction::Delete,
+                NOW
+            ),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn delegation() {
+        let mut rng = rand::thread_rng();
+        let context = SigningContext::new(NetworkId([1; 32]));
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let user = ClientFullId::new_ed25519(&mut rng);
+        let delegate = ClientFullId::new_bls(&mut rng);
+        let address = DataAddress::Immutable(IDataAddress::Unpub(rand::random()));
+
+        let capability = unwrap!(Capability::new(
+            &context,
+            &owner,
+            address,
+            actions(&[GenericAction::Read, GenericAction::Delete]),
+            Grantee::Key(key(&user)),
+            NOW + 10,
+        ));
+
+        // only the grantee can delegate, and only narrower rights
+        assert_eq!(
+            capability.delegate(
+                &context,
+                &delegate,
+                actions(&[GenericAction::Read]),
+                Grantee::Bearer,
+                NOW + 5
+            ),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(
+            capability.delegate(
+                &context,
+                &user,
+                actions(&[GenericAction::Read, GenericAction::Update]),
+                Grantee::Bearer,
+                NOW + 5
+            ),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(
+            capability.delegate(
+                &context,
+                &user,
+                actions(&[GenericAction::Read]),
+                Grantee::Bearer,
+                NOW + 11
+            ),
+            Err(Error::PrivilegeEscalation)
+        );
+
+        let delegated = unwrap!(capability.delegate(
+            &context,
+            &user,
+            actions(&[GenericAction::Read]),
+            Grantee::Bearer,
+            NOW + 5
+        ));
+        assert_eq!(delegated.grants().count(), 2);
+        unwrap!(delegated.verify(
+            &context,
+            &key(&owner),
+            &address,
+            &key(&delegate),
+            GenericAction::Read,
+            NOW
+        ));
+        assert_eq!(
+            delegated.verify(
+                &context,
+                &key(&owner),
+                &address,
+                &key(&delegate),
+                GenericAction::Delete,
+                NOW
+            ),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(
+            delegated.verify(
+                &context,
+                &key(&owner),
+                &address,
+                &key(&delegate),
+                GenericAction::Read,
+                NOW + 5
+            ),
+            Err(Error::CapabilityExpired)
+        );
+
+        // a widened delegation is rejected even if validly signed
+        let mut widened = delegated.clone();
+        widened.delegations[0] = Grant::new(
+            &context,
+            &user,
+            address,
+            actions(&[GenericAction::Read, GenericAction::Update]),
+            Grantee::Bearer,
+            NOW + 5,
+            Some(&capability.root),
+        );
+        assert_eq!(
+            widened.verify(
+                &context,
+                &key(&owner),
+                &address,
+                &key(&delegate),
+                GenericAction::Update,
+                NOW
+            ),
+            Err(Error::PrivilegeEscalation)
+        );
+
+        // a delegation can't be moved to another chain
+        let other = unwrap!(Capability::new(
+            &context,
+            &owner,
+            address,
+            actions(&[GenericAction::Read, GenericAction::Delete]),
+            Grantee::Key(key(&user)),
+            NOW + 10,
+        ));
+        let mut moved = other.clone();
+        moved.delegations.push(delegated.delegations[0].clone());
+        assert_eq!(
+            moved.verify(
+                &context,
+                &key(&owner),
+                &address,
+                &key(&delegate),
+                GenericAction::Read,
+                NOW
+            ),
+            Err(Error::InvalidSignature)
+        );
+    }
+}
@@ -0,0 +1,222 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{Challenge, Error, PublicId, Result, SigningContext};
+use rand::{CryptoRng, Rng};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Length in bytes of the nonces generated by a `ChallengeIssuer`.
+pub const CHALLENGE_NONCE_LEN: usize = 32;
+
+/// Issues challenges to peers claiming a given identity and verifies their responses.
+///
+/// At most one challenge is outstanding per identity: issuing a new one replaces the previous one.
+/// Each challenge can only be answered successfully once. Invalid responses leave it pending, so
+/// other peers can't cancel a challenge by answering it first.
+pub struct ChallengeIssuer {
+    our_id: PublicId,
+    context: SigningContext,
+    lifetime: Duration,
+    pending: HashMap<PublicId, PendingChallenge>,
+}
+
+struct PendingChallenge {
+    nonce: Vec<u8>,
+    expires_at: Instant,
+}
+
+impl ChallengeIssuer {
+    /// Constructs an issuer which sends challenges from `our_id`, expects them to be signed for
+    /// the network of `context`, and rejects responses received more than `lifetime` after the
+    /// challenge was issued.
+    pub fn new(our_id: PublicId, context: SigningContext, lifetime: Duration) -> Self {
+        Self {
+            our_id,
+            context,
+            lifetime,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Creates a `Challenge::Request` with a random nonce, to be answered by `expected`.
+    pub fn issue<T: CryptoRng + Rng>(&mut self, rng: &mut T, expected: PublicId) -> Challenge {
+        let mut nonce = vec![0; CHALLENGE_NONCE_LEN];
+        rng.fill_bytes(&mut nonce);
+        let pending = PendingChallenge {
+            nonce: nonce.clone(),
+            expires_at: Instant::now() + self.lifetime,
+        };
+        let _ = self.pending.insert(expected, pending);
+        Challenge::Request(self.our_id.clone(), nonce)
+    }
+
+    /// Verifies `response` to the challenge issued to `expected`.
+    ///
+    /// The responder must be `expected` itself; for apps, this includes being owned by the
+    /// expected client. The signature is checked against the responder's public key. The challenge
+    /// is discarded once it has been answered successfully or has expired.
+    pub fn verify(&mut self, expected: &PublicId, response: &Challenge) -> Result<()> {
+        let (responder, signature) = match response {
+            Challenge::Response(responder, signature) => (responder, signature),
+            Challenge::Request(..) => return Err(Error::InvalidOperation),
+        };
+        let pending = self.pending.get(expected).ok_or(Error::NoSuchChallenge)?;
+        if Instant::now() >= pending.expires_at {
+            let _ = self.pending.remove(expected);
+            return Err(Error::ChallengeExpired);
+        }
+
+        let is_expected = match (expected, responder) {
+            (PublicId::App(expected), PublicId::App(responder)) => {
+                expected.public_key() == responder.public_key()
+                    && expected.owner() == responder.owner()
+            }
+            (expected, responder) => expected == responder,
+        };
+        if !is_expected {
+            return Err(Error::AccessDenied);
+        }
+
+        self.context
+            .verify_challenge_response(responder, signature, &pending.nonce)?;
+        let _ = self.pending.remove(expected);
+        Ok(())
+    }
+
+    /// Discards all challenges which have expired without being answered.
+    pub fn remove_expired(&mut self) {
+        let now = Instant::now();
+        self.pending.retain(|_, pending| pending.expires_at > now);
+    }
+
+    /// Returns the number of challenges awaiting a response.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AppFullId, ClientFullId, NetworkId, NodeFullId};
+    use threshold_crypto::SecretKey as BlsSecretKey;
+    use unwrap::unwrap;
+
+    const LIFETIME: Duration = Duration::from_secs(60);
+
+    fn nonce(challenge: &Challenge) -> Vec<u8> {
+        match challenge {
+            Challenge::Request(_, nonce) => nonce.clone(),
+            Challenge::Response(..) => panic!("Unexpected challenge response"),
+        }
+    }
+
+    fn issuer(lifetime: Duration) -> (ChallengeIssuer, SigningContext) {
+        let context = SigningContext::new(NetworkId([1; 32]));
+        let node = NodeFullId::new(&mut rand::thread_rng());
+        let issuer =
+            ChallengeIssuer::new(PublicId::Node(node.public_id().clone()), context, lifetime);
+        (issuer, context)
+    }
+
+    #[test]
+    fn client() {
+        let mut rng = rand::thread_rng();
+        let (mut issuer, context) = issuer(LIFETIME);
+        let client = ClientFullId::new_ed25519(&mut rng);
+        let client_id = PublicId::Client(client.public_id().clone());
+
+        let challenge = issuer.issue(&mut rng, client_id.clone());
+        assert_eq!(nonce(&challenge).len(), CHALLENGE_NONCE_LEN);
+        assert_eq!(issuer.pending_count(), 1);
+        let response = context.new_challenge_response(&client, &nonce(&challenge));
+        unwrap!(issuer.verify(&client_id, &response));
+
+        // Each challenge can only be answered once.
+        assert_eq!(issuer.pending_count(), 0);
+        assert_eq!(
+            issuer.verify(&client_id, &response),
+            Err(Error::NoSuchChallenge)
+        );
+
+        // Signature over a different nonce.
+        let challenge = issuer.issue(&mut rng, client_id.clone());
+        let response = context.new_challenge_response(&client, &[0; CHALLENGE_NONCE_LEN]);
+        assert_eq!(
+            issuer.verify(&client_id, &response),
+            Err(Error::InvalidSignature)
+        );
+
+        // Response from someone else, which doesn't cancel the challenge.
+        let other = ClientFullId::new_ed25519(&mut rng);
+        let response = context.new_challenge_response(&other, &nonce(&challenge));
+        assert_eq!(
+            issuer.verify(&client_id, &response),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(issuer.pending_count(), 1);
+        let response = context.new_challenge_response(&client, &nonce(&challenge));
+        unwrap!(issuer.verify(&client_id, &response));
+
+        // A request isn't a valid response.
+        assert_eq!(
+            issuer.verify(&client_id, &challenge),
+            Err(Error::InvalidOperation)
+        );
+    }
+
+    #[test]
+    fn app() {
+        let mut rng = rand::thread_rng();
+        let (mut issuer, context) = issuer(LIFETIME);
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let app = AppFullId::new_ed25519(&mut rng, owner.public_id().clone());
+        let app_id = PublicId::App(app.public_id().clone());
+
+        let challenge = issuer.issue(&mut rng, app_id.clone());
+        let response = context.new_challenge_response(&app, &nonce(&challenge));
+        unwrap!(issuer.verify(&app_id, &response));
+
+        // The same app key, but owned by someone else.
+        let secret_key = BlsSecretKey::random();
+        let owner_key = *owner.public_id().public_key();
+        let other_owner_key = *ClientFullId::new_ed25519(&mut rng).public_id().public_key();
+        let app = AppFullId::with_keys(secret_key.clone(), owner_key);
+        let impostor = AppFullId::with_keys(secret_key, other_owner_key);
+        let app_id = PublicId::App(app.public_id().clone());
+
+        let challenge = issuer.issue(&mut rng, app_id.clone());
+        let response = context.new_challenge_response(&impostor, &nonce(&challenge));
+        assert_eq!(issuer.verify(&app_id, &response), Err(Error::AccessDenied));
+    }
+
+    #[test]
+    fn expiry() {
+        let mut rng = rand::thread_rng();
+        let (mut issuer, context) = issuer(Duration::from_secs(0));
+        let client = ClientFullId::new_ed25519(&mut rng);
+        let client_id = PublicId::Client(client.public_id().clone());
+
+        let challenge = issuer.issue(&mut rng, client_id.clone());
+        let response = context.new_challenge_response(&client, &nonce(&challenge));
+        assert_eq!(
+            issuer.verify(&client_id, &response),
+            Err(Error::ChallengeExpired)
+        );
+        assert_eq!(issuer.pending_count(), 0);
+
+        let _ = issuer.issue(&mut rng, client_id);
+        assert_eq!(issuer.pending_count(), 1);
+        issuer.remove_expired();
+        assert_eq!(issuer.pending_count(), 0);
+    }
+}
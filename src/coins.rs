@@ -7,7 +7,7 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use crate::errors::{Error, Result};
+use crate::errors::{Error, ParseError, Result};
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::{self, Debug, Display, Formatter},
@@ -71,7 +71,7 @@ impl FromStr for Coins {
             let units = itr
                 .next()
                 .and_then(|s| s.parse::<u64>().ok())
-                .ok_or_else(|| Error::FailedToParse("Can't parse coin units".to_string()))?;
+                .ok_or(Error::FailedToParse(ParseError::InvalidCoinUnits))?;
 
             units
                 .checked_mul(COIN_TO_RAW_CONVERSION)
@@ -86,7 +86,7 @@ impl FromStr for Coins {
             } else {
                 let parsed_remainder = remainder_str
                     .parse::<u64>()
-                    .map_err(|_| Error::FailedToParse("Can't parse coin remainder".to_string()))?;
+                    .map_err(|_| Error::FailedToParse(ParseError::InvalidCoinRemainder))?;
 
                 let remainder_conversion = COIN_TO_RAW_POWER_OF_10_CONVERSION
                     .checked_sub(remainder_str.len() as u32)
@@ -151,19 +151,15 @@ mod tests {
         );
 
         assert_eq!(
-            Err(Error::FailedToParse("Can't parse coin units".to_string())),
+            Err(Error::FailedToParse(ParseError::InvalidCoinUnits)),
             Coins::from_str("a")
         );
         assert_eq!(
-            Err(Error::FailedToParse(
-                "Can't parse coin remainder".to_string()
-            )),
+            Err(Error::FailedToParse(ParseError::InvalidCoinRemainder)),
             Coins::from_str("0.a")
         );
         assert_eq!(
-            Err(Error::FailedToParse(
-                "Can't parse coin remainder".to_string()
-            )),
+            Err(Error::FailedToParse(ParseError::InvalidCoinRemainder)),
             Coins::from_str("0.0.0")
         );
         assert_eq!(Err(Error::LossOfPrecision), Coins::from_str("0.0000000009"));
@@ -50,7 +50,9 @@ pub enum Error {
     /// Exceeded a limit on a number of entries
     TooManyEntries,
     /// Some entry actions are not valid.
-    InvalidEntryActions(BTreeMap<Vec<u8>, EntryError>),
+    InvalidEntryActions(
+        #[serde(with = "crate::human_readable::byte_keys")] BTreeMap<Vec<u8>, EntryError>,
+    ),
     /// Key does not exist
     NoSuchKey,
     /// The key(s) of the entry or entries contained in this error already exist
@@ -78,16 +80,16 @@ pub enum Error {
     InvalidSignature,
     /// Received a request with a duplicate MessageId
     DuplicateMessageId,
-    /// Network error occurring at Vault level which has no bearing on clients, e.g. serialisation
-    /// failure or database failure
-    NetworkOther(String),
+    /// Failure occurring at Vault level which has no bearing on clients, e.g. a database failure.
+    /// Contains a description of the failure.
+    Internal(String),
     /// While parsing, precision would be lost.
     LossOfPrecision,
     /// The coin amount would exceed
     /// [the maximum value for `Coins`](constant.MAX_COINS_VALUE.html).
     ExcessiveValue,
-    /// Failed to parse the string as [`Coins`](struct.Coins.html).
-    FailedToParse(String),
+    /// Failed to parse a string or decode bytes. Contains the reason.
+    FailedToParse(ParseError),
     /// Transaction ID already exists.
     TransactionIdExists,
     /// Insufficient coins.
@@ -98,11 +100,185 @@ pub enum Error {
     BalanceExists,
     /// Expected data size exceeded.
     ExceededSize,
+    /// No challenge has been issued to the given identity, or it has already been answered.
+    NoSuchChallenge,
+    /// The challenge was answered after it had expired.
+    ChallengeExpired,
+    /// The encoded message exceeds the maximum size.
+    ExceededMessageSize,
+    /// An entry key exceeds the maximum length.
+    ExceededKeyLength,
+    /// An entry value exceeds the maximum length.
+    ExceededValueLength,
+    /// The data of a login packet exceeds the maximum size.
+    ExceededLoginPacketSize,
+    /// The wire protocol version is not supported by this release. Contains the received version.
+    UnsupportedProtocolVersion(u16),
+    /// The key is already a member of the group.
+    GroupMemberExists,
+    /// Attempt to grant permissions exceeding the requester's own.
+    PrivilegeEscalation,
+    /// The capability attached to the request has expired.
+    CapabilityExpired,
+    /// The capability attached to the request, or one it was delegated from, has been revoked.
+    CapabilityRevoked,
+}
+
+/// Broad class of an `Error`, for deciding how to react to it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum ErrorCategory {
+    /// The request is malformed, or can't succeed however often it's retried.
+    Client,
+    /// The requester isn't allowed to perform the request.
+    Permission,
+    /// The request conflicts with the current state, e.g. the data exists or the version is stale.
+    Conflict,
+    /// The data, entry, balance or challenge doesn't exist.
+    NotFound,
+    /// Temporary failure: the same request may succeed if retried.
+    Transient,
+}
+
+impl Error {
+    /// Returns the stable numeric code of the error.
+    ///
+    /// Codes never change meaning across releases, and the hundreds digit identifies the
+    /// category: 1 for `Client`, 2 for `Permission`, 3 for `Conflict`, 4 for `NotFound` and 5 for
+    /// `Transient`. Payloads aren't part of the code, except for the reason of `FailedToParse`.
+    pub fn code(&self) -> u32 {
+        match *self {
+            // Client
+            Error::InvalidOperation => 100,
+            Error::TooManyEntries => 101,
+            Error::DuplicateEntryKeys => 102,
+            Error::InvalidOwners => 103,
+            Error::InvalidPermissions => 104,
+            Error::LossOfPrecision => 105,
+            Error::ExcessiveValue => 106,
+            Error::InsufficientBalance => 107,
+            Error::ExceededSize => 108,
+            Error::ExceededMessageSize => 109,
+            Error::ExceededKeyLength => 110,
+            Error::ExceededValueLength => 111,
+            Error::ExceededLoginPacketSize => 112,
+            Error::UnsupportedProtocolVersion(_) => 113,
+            Error::ChallengeExpired => 114,
+            Error::FailedToParse(ParseError::InvalidMultibase) => 150,
+            Error::FailedToParse(ParseError::UnexpectedBase) => 151,
+            Error::FailedToParse(ParseError::InvalidSerialisation) => 152,
+            Error::FailedToParse(ParseError::InvalidCoinUnits) => 153,
+            Error::FailedToParse(ParseError::InvalidCoinRemainder) => 154,
+            // Permission
+            Error::AccessDenied => 200,
+            Error::InvalidSignature => 201,
+            Error::SigningKeyTypeMismatch => 202,
+            Error::PrivilegeEscalation => 203,
+            Error::CapabilityExpired => 204,
+            Error::CapabilityRevoked => 205,
+            // Conflict
+            Error::DataExists => 300,
+            Error::LoginPacketExists => 301,
+            Error::BalanceExists => 302,
+            Error::KeysExist(_) => 303,
+            Error::InvalidEntryActions(_) => 304,
+            Error::InvalidSuccessor(_) => 305,
+            Error::InvalidOwnersSuccessor(_) => 306,
+            Error::InvalidPermissionsSuccessor(_) => 307,
+            Error::TransactionIdExists => 308,
+            Error::DuplicateMessageId => 309,
+            Error::GroupMemberExists => 310,
+            // NotFound
+            Error::NoSuchData => 400,
+            Error::NoSuchEntry => 401,
+            Error::NoSuchKey => 402,
+            Error::NoSuchLoginPacket => 403,
+            Error::NoSuchBalance => 404,
+            Error::NoSuchChallenge => 405,
+            // Transient
+            Error::Internal(_) => 500,
+        }
+    }
+
+    /// Returns the error with the given code, or `None` if the code is unknown.
+    ///
+    /// Payloads can't be recovered from a code, so variants which carry one are returned with an
+    /// empty or zero payload.
+    pub fn from_code(code: u32) -> Option<Self> {
+        let error = match code {
+            // Client
+            100 => Error::InvalidOperation,
+            101 => Error::TooManyEntries,
+            102 => Error::DuplicateEntryKeys,
+            103 => Error::InvalidOwners,
+            104 => Error::InvalidPermissions,
+            105 => Error::LossOfPrecision,
+            106 => Error::ExcessiveValue,
+            107 => Error::InsufficientBalance,
+            108 => Error::ExceededSize,
+            109 => Error::ExceededMessageSize,
+            110 => Error::ExceededKeyLength,
+            111 => Error::ExceededValueLength,
+            112 => Error::ExceededLoginPacketSize,
+            113 => Error::UnsupportedProtocolVersion(0),
+            114 => Error::ChallengeExpired,
+            150 => Error::FailedToParse(ParseError::InvalidMultibase),
+            151 => Error::FailedToParse(ParseError::UnexpectedBase),
+            152 => Error::FailedToParse(ParseError::InvalidSerialisation),
+            153 => Error::FailedToParse(ParseError::InvalidCoinUnits),
+            154 => Error::FailedToParse(ParseError::InvalidCoinRemainder),
+            // Permission
+            200 => Error::AccessDenied,
+            201 => Error::InvalidSignature,
+            202 => Error::SigningKeyTypeMismatch,
+            203 => Error::PrivilegeEscalation,
+            204 => Error::CapabilityExpired,
+            205 => Error::CapabilityRevoked,
+            // Conflict
+            300 => Error::DataExists,
+            301 => Error::LoginPacketExists,
+            302 => Error::BalanceExists,
+            303 => Error::KeysExist(Vec::new()),
+            304 => Error::InvalidEntryActions(BTreeMap::new()),
+            305 => Error::InvalidSuccessor(0),
+            306 => Error::InvalidOwnersSuccessor(0),
+            307 => Error::InvalidPermissionsSuccessor(0),
+            308 => Error::TransactionIdExists,
+            309 => Error::DuplicateMessageId,
+            310 => Error::GroupMemberExists,
+            // NotFound
+            400 => Error::NoSuchData,
+            401 => Error::NoSuchEntry,
+            402 => Error::NoSuchKey,
+            403 => Error::NoSuchLoginPacket,
+            404 => Error::NoSuchBalance,
+            405 => Error::NoSuchChallenge,
+            // Transient
+            500 => Error::Internal(String::new()),
+            _ => return None,
+        };
+        Some(error)
+    }
+
+    /// Returns the category of the error.
+    pub fn category(&self) -> ErrorCategory {
+        match self.code() / 100 {
+            1 => ErrorCategory::Client,
+            2 => ErrorCategory::Permission,
+            3 => ErrorCategory::Conflict,
+            4 => ErrorCategory::NotFound,
+            _ => ErrorCategory::Transient,
+        }
+    }
+
+    /// Returns `true` if the same request may succeed if retried later.
+    pub fn is_retryable(&self) -> bool {
+        self.category() == ErrorCategory::Transient
+    }
 }
 
 impl<T: Into<String>> From<T> for Error {
     fn from(err: T) -> Self {
-        Error::NetworkOther(err.into())
+        Error::Internal(err.into())
     }
 }
 
@@ -140,7 +316,7 @@ impl Display for Error {
                 write!(f, "Mismatch between key type and signature type")
             }
             Error::InvalidSignature => write!(f, "Failed signature validation"),
-            Error::NetworkOther(ref error) => write!(f, "Error on Vault network: {}", error),
+            Error::Internal(ref error) => write!(f, "Internal error on Vault network: {}", error),
             Error::LossOfPrecision => {
                 write!(f, "Lost precision on the number of coins during parsing")
             }
@@ -148,15 +324,30 @@ impl Display for Error {
                 f,
                 "Overflow on number of coins (check the MAX_COINS_VALUE const)"
             ),
-            Error::FailedToParse(ref error) => {
-                write!(f, "Failed to parse from a string: {}", error)
-            }
+            Error::FailedToParse(ref error) => write!(f, "Failed to parse: {}", error),
             Error::TransactionIdExists => write!(f, "Transaction with a given ID already exists"),
             Error::InsufficientBalance => write!(f, "Not enough coins to complete this operation"),
             Error::NoSuchBalance => write!(f, "Balance does not exist"),
             Error::BalanceExists => write!(f, "Balance already exists"),
             Error::DuplicateMessageId => write!(f, "MessageId already exists"),
             Error::ExceededSize => write!(f, "Size of the structure exceeds the limit"),
+            Error::NoSuchChallenge => write!(f, "No outstanding challenge for this identity"),
+            Error::ChallengeExpired => write!(f, "Challenge has expired"),
+            Error::ExceededMessageSize => write!(f, "Size of the message exceeds the limit"),
+            Error::ExceededKeyLength => write!(f, "Length of an entry key exceeds the limit"),
+            Error::ExceededValueLength => write!(f, "Length of an entry value exceeds the limit"),
+            Error::ExceededLoginPacketSize => {
+                write!(f, "Size of the login packet exceeds the limit")
+            }
+            Error::UnsupportedProtocolVersion(version) => {
+                write!(f, "Unsupported wire protocol version: {}", version)
+            }
+            Error::GroupMemberExists => write!(f, "Key is already a member of the group"),
+            Error::PrivilegeEscalation => {
+                write!(f, "Requester can't grant permissions it doesn't hold")
+            }
+            Error::CapabilityExpired => write!(f, "Capability has expired"),
+            Error::CapabilityRevoked => write!(f, "Capability has been revoked"),
         }
     }
 }
@@ -183,7 +374,7 @@ impl error::Error for Error {
             Error::InvalidOperation => "Invalid operation",
             Error::SigningKeyTypeMismatch => "Key type and signature type mismatch",
             Error::InvalidSignature => "Invalid signature",
-            Error::NetworkOther(ref error) => error,
+            Error::Internal(_) => "Internal error",
             Error::LossOfPrecision => "Lost precision on the number of coins during parsing",
             Error::ExcessiveValue => {
                 "Overflow on number of coins (check the MAX_COINS_VALUE const)"
@@ -195,6 +386,17 @@ impl error::Error for Error {
             Error::BalanceExists => "Balance already exists",
             Error::DuplicateMessageId => "MessageId already exists",
             Error::ExceededSize => "Exceeded the size limit",
+            Error::NoSuchChallenge => "No such challenge",
+            Error::ChallengeExpired => "Challenge expired",
+            Error::ExceededMessageSize => "Exceeded the message size limit",
+            Error::ExceededKeyLength => "Exceeded the key length limit",
+            Error::ExceededValueLength => "Exceeded the value length limit",
+            Error::ExceededLoginPacketSize => "Exceeded the login packet size limit",
+            Error::UnsupportedProtocolVersion(_) => "Unsupported wire protocol version",
+            Error::GroupMemberExists => "Group member exists",
+            Error::PrivilegeEscalation => "Privilege escalation",
+            Error::CapabilityExpired => "Capability expired",
+            Error::CapabilityRevoked => "Capability revoked",
         }
     }
 }
@@ -208,4 +410,119 @@ pub enum EntryError {
     EntryExists(u8),
     /// Invalid version when updating an entry. Contains the current entry Key.
     InvalidSuccessor(u8),
+    /// Entry would exceed the limit on the number of entries.
+    TooManyEntries,
+    /// Entry key or value, or the data as a whole, would exceed the size limit.
+    ExceededSize,
+}
+
+/// Reason for `Error::FailedToParse`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub enum ParseError {
+    /// The string isn't valid multibase.
+    InvalidMultibase,
+    /// The string is encoded in a different base than expected.
+    UnexpectedBase,
+    /// The bytes aren't a valid serialisation of the expected type.
+    InvalidSerialisation,
+    /// The whole coins part of a [`Coins`](struct.Coins.html) string isn't a number.
+    InvalidCoinUnits,
+    /// The fractional part of a [`Coins`](struct.Coins.html) string isn't a number.
+    InvalidCoinRemainder,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            ParseError::InvalidMultibase => write!(f, "Invalid multibase string"),
+            ParseError::UnexpectedBase => write!(f, "Unexpected multibase encoding"),
+            ParseError::InvalidSerialisation => write!(f, "Invalid serialisation"),
+            ParseError::InvalidCoinUnits => write!(f, "Can't parse coin units"),
+            ParseError::InvalidCoinRemainder => write!(f, "Can't parse coin remainder"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::{self, Fixtures};
+    use std::{collections::BTreeSet, mem};
+    use unwrap::unwrap;
+
+    #[test]
+    fn codes() {
+        let mut errors = test_vectors::errors(&mut Fixtures::new());
+        errors.extend(
+            [
+                ParseError::InvalidMultibase,
+                ParseError::UnexpectedBase,
+                ParseError::InvalidCoinUnits,
+                ParseError::InvalidCoinRemainder,
+            ]
+            .iter()
+            .map(|&error| Error::FailedToParse(error)),
+        );
+
+        // `test_vectors::errors` covers every variant, so each code must round-trip to it.
+        let mut codes = BTreeSet::new();
+        for error in errors {
+            assert!(codes.insert(error.code()), "Duplicate code for {:?}", error);
+            let decoded = unwrap!(Error::from_code(error.code()));
+            assert_eq!(mem::discriminant(&decoded), mem::discriminant(&error));
+            assert_eq!(decoded.code(), error.code());
+            assert_eq!(decoded.category(), error.category());
+        }
+        // `from_code` mustn't know any code which `code` doesn't return.
+        let decodable: BTreeSet<_> = (0..1000)
+            .filter(|&code| Error::from_code(code).is_some())
+            .collect();
+        assert_eq!(decodable, codes);
+
+        // Codes must never change.
+        assert_eq!(Error::AccessDenied.code(), 200);
+        assert_eq!(Error::NoSuchData.code(), 400);
+        assert_eq!(Error::from_code(305), Some(Error::InvalidSuccessor(0)));
+        assert_eq!(
+            Error::from_code(153),
+            Some(Error::FailedToParse(ParseError::InvalidCoinUnits))
+        );
+    }
+
+    #[test]
+    fn from_string() {
+        fn fail() -> Result<()> {
+            Err("database failure")?
+        }
+        assert_eq!(fail(), Err(Error::Internal("database failure".to_string())));
+    }
+
+    #[test]
+    fn category() {
+        assert_eq!(Error::TooManyEntries.category(), ErrorCategory::Client);
+        assert_eq!(
+            Error::FailedToParse(ParseError::InvalidSerialisation).category(),
+            ErrorCategory::Client
+        );
+        assert_eq!(
+            Error::InvalidSignature.category(),
+            ErrorCategory::Permission
+        );
+        assert_eq!(
+            Error::InvalidSuccessor(1).category(),
+            ErrorCategory::Conflict
+        );
+        assert_eq!(Error::NoSuchBalance.category(), ErrorCategory::NotFound);
+        assert_eq!(
+            Error::Internal("database failure".to_string()).category(),
+            ErrorCategory::Transient
+        );
+        // the client has to request a new challenge instead
+        assert_eq!(Error::ChallengeExpired.category(), ErrorCategory::Client);
+
+        assert!(Error::Internal(String::new()).is_retryable());
+        assert!(!Error::ChallengeExpired.is_retryable());
+        assert!(!Error::AccessDenied.is_retryable());
+        assert!(!Error::DataExists.is_retryable());
+    }
 }
@@ -0,0 +1,127 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{Error, PublicKey, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Name of a permission group, unique within the data holding it.
+pub type GroupName = String;
+
+/// A named set of users which can be given permissions as a whole.
+///
+/// Groups are held by the MutableData or AppendOnlyData they apply to. Each group has its own
+/// version, incremented on every membership change independently of the data's version.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Group {
+    members: BTreeSet<PublicKey>,
+    version: u64,
+}
+
+impl Group {
+    /// Constructs a new, empty group.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the members.
+    pub fn members(&self) -> &BTreeSet<PublicKey> {
+        &self.members
+    }
+
+    /// Returns the version of the membership.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns true if `key` is a member.
+    pub fn is_member(&self, key: &PublicKey) -> bool {
+        self.members.contains(key)
+    }
+
+    /// Adds a member.
+    ///
+    /// Requires the new `version` of the group. If it does not match the current version + 1, an
+    /// error will be returned.
+    pub fn add_member(&mut self, member: PublicKey, version: u64) -> Result<()> {
+        if version != self.version + 1 {
+            return Err(Error::InvalidSuccessor(self.version));
+        }
+        if !self.members.insert(member) {
+            return Err(Error::GroupMemberExists);
+        }
+
+        self.version = version;
+        Ok(())
+    }
+
+    /// Removes a member.
+    ///
+    /// Requires the new `version` of the group. If it does not match the current version + 1, an
+    /// error will be returned.
+    pub fn remove_member(&mut self, member: &PublicKey, version: u64) -> Result<()> {
+        if version != self.version + 1 {
+            return Err(Error::InvalidSuccessor(self.version));
+        }
+        if !self.members.remove(member) {
+            return Err(Error::NoSuchKey);
+        }
+
+        self.version = version;
+        Ok(())
+    }
+}
+
+/// Looks up the entries of the groups `member` belongs to. Returns the first group whose entry
+/// allows the action (`Some(true)`), or failing that the first whose entry forbids it
+/// (`Some(false)`).
+pub(crate) fn resolve_groups<F>(
+    groups: &BTreeMap<GroupName, Group>,
+    member: &PublicKey,
+    is_allowed: F,
+) -> Option<(GroupName, bool)>
+where
+    F: Fn(&GroupName) -> Option<bool>,
+{
+    let mut denied = None;
+    for name in groups
+        .iter()
+        .filter(|(_, group)| group.is_member(member))
+        .map(|(name, _)| name)
+    {
+        match is_allowed(name) {
+            Some(true) => return Some((name.clone(), true)),
+            Some(false) if denied.is_none() => denied = Some((name.clone(), false)),
+            _ => (),
+        }
+    }
+    denied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use threshold_crypto::SecretKey;
+
+    #[test]
+    fn membership() {
+        let member = PublicKey::Bls(SecretKey::random().public_key());
+        let mut group = Group::new();
+
+        assert_eq!(group.add_member(member, 2), Err(Error::InvalidSuccessor(0)));
+        assert_eq!(group.add_member(member, 1), Ok(()));
+        assert!(group.is_member(&member));
+        assert_eq!(group.add_member(member, 2), Err(Error::GroupMemberExists));
+
+        assert_eq!(group.remove_member(&member, 2), Ok(()));
+        assert!(!group.is_member(&member));
+        assert_eq!(group.remove_member(&member, 3), Err(Error::NoSuchKey));
+        assert_eq!(group.version(), 2);
+    }
+}
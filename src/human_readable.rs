@@ -0,0 +1,432 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Human-readable serde representations, enabled by the `human-readable` feature.
+//!
+//! With the feature enabled, formats which report `is_human_readable()`, e.g. JSON, get names,
+//! public keys and signatures as multibase z-base-32 strings of their bincode encoding (the same
+//! strings as the `encode_to_zbase32` functions produce), and binary keys and values as standard
+//! (RFC 4648) base64 strings. Other formats, including bincode, always get the same representation
+//! as `derive` would generate.
+
+use crate::{utils, ADataUser, GroupName, MDataUser, PublicKey, Signature, XorName};
+use serde::{
+    de::{DeserializeOwned, Error as _},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{collections::BTreeMap, result::Result};
+
+// Representations used by non-human-readable formats. These must match what `derive` would
+// generate for the original types.
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "XorName", rename = "XorName")]
+struct XorNameDef(pub [u8; 32]);
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "PublicKey", rename = "PublicKey")]
+enum PublicKeyDef {
+    Ed25519(ed25519_dalek::PublicKey),
+    Bls(threshold_crypto::PublicKey),
+    BlsShare(threshold_crypto::PublicKeyShare),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Signature", rename = "Signature")]
+#[allow(clippy::large_enum_variant)]
+enum SignatureDef {
+    Ed25519(ed25519_dalek::Signature),
+    Bls(threshold_crypto::Signature),
+    BlsShare(threshold_crypto::SignatureShare),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ADataUser", rename = "User")]
+enum ADataUserDef {
+    Anyone,
+    Key(PublicKey),
+    Group(GroupName),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MDataUser", rename = "User")]
+enum MDataUserDef {
+    Key(PublicKey),
+    Group(GroupName),
+}
+
+fn is_human_readable<S: Serializer>(serializer: &S) -> bool {
+    cfg!(feature = "human-readable") && serializer.is_human_readable()
+}
+
+fn is_human_readable_de<'de, D: Deserializer<'de>>(deserializer: &D) -> bool {
+    cfg!(feature = "human-readable") && deserializer.is_human_readable()
+}
+
+/// Implements `Serialize` and `Deserialize` for `$type` as a z-base-32 string in human-readable
+/// formats, and as `$def` otherwise.
+macro_rules! impl_zbase32 {
+    ($type:ty, $def:ident) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if is_human_readable(&serializer) {
+                    serializer.serialize_str(&utils::encode(self))
+                } else {
+                    $def::serialize(self, serializer)
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                if is_human_readable_de(&deserializer) {
+                    decode_zbase32(deserializer)
+                } else {
+                    $def::deserialize(deserializer)
+                }
+            }
+        }
+    };
+}
+
+impl_zbase32!(XorName, XorNameDef);
+impl_zbase32!(PublicKey, PublicKeyDef);
+impl_zbase32!(Signature, SignatureDef);
+
+/// `ADataUser::Anyone` is written as `"Anyone"`, `ADataUser::Key` as the key's string and
+/// `ADataUser::Group` as `"group:"` followed by the group name.
+impl Serialize for ADataUser {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ADataUser::Anyone if is_human_readable(&serializer) => serializer.serialize_str(ANYONE),
+            ADataUser::Key(public_key) if is_human_readable(&serializer) => {
+                public_key.serialize(serializer)
+            }
+            ADataUser::Group(name) if is_human_readable(&serializer) => {
+                serializer.serialize_str(&format!("{}{}", GROUP_PREFIX, name))
+            }
+            _ => ADataUserDef::serialize(self, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ADataUser {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !is_human_readable_de(&deserializer) {
+            return ADataUserDef::deserialize(deserializer);
+        }
+        let encoded = String::deserialize(deserializer)?;
+        if encoded == ANYONE {
+            Ok(ADataUser::Anyone)
+        } else if encoded.starts_with(GROUP_PREFIX) {
+            Ok(ADataUser::Group(encoded[GROUP_PREFIX.len()..].to_string()))
+        } else {
+            utils::decode(encoded)
+                .map(ADataUser::Key)
+                .map_err(D::Error::custom)
+        }
+    }
+}
+
+/// `MDataUser` is written the same way as the corresponding `ADataUser`.
+impl Serialize for MDataUser {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !is_human_readable(&serializer) {
+            return MDataUserDef::serialize(self, serializer);
+        }
+        match self {
+            MDataUser::Key(public_key) => ADataUser::Key(*public_key).serialize(serializer),
+            MDataUser::Group(name) => ADataUser::Group(name.clone()).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MDataUser {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !is_human_readable_de(&deserializer) {
+            return MDataUserDef::deserialize(deserializer);
+        }
+        match ADataUser::deserialize(deserializer)? {
+            ADataUser::Key(public_key) => Ok(MDataUser::Key(public_key)),
+            ADataUser::Group(name) => Ok(MDataUser::Group(name)),
+            ADataUser::Anyone => Err(D::Error::custom("MutableData has no `Anyone` user")),
+        }
+    }
+}
+
+const ANYONE: &str = "Anyone";
+const GROUP_PREFIX: &str = "group:";
+
+fn decode_zbase32<'de, D: Deserializer<'de>, T: DeserializeOwned>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    let encoded = String::deserialize(deserializer)?;
+    utils::decode(encoded).map_err(D::Error::custom)
+}
+
+// ===== Binary values =====
+
+/// Binary value, written as a base64 string in human-readable formats.
+pub(crate) struct Bytes<'a>(pub &'a [u8]);
+
+impl<'a> Serialize for Bytes<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if is_human_readable(&serializer) {
+            serializer.serialize_str(&base64::encode(self.0))
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+/// Binary value, read from a base64 string in human-readable formats.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ByteBuf(pub Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !is_human_readable_de(&deserializer) {
+            return Vec::deserialize(deserializer).map(ByteBuf);
+        }
+        let encoded = String::deserialize(deserializer)?;
+        base64::decode(&encoded)
+            .map(ByteBuf)
+            .map_err(D::Error::custom)
+    }
+}
+
+/// `#[serde(with)]` module for `Vec<u8>` fields holding binary values.
+pub(crate) mod bytes {
+    use super::*;
+
+    #[allow(clippy::ptr_arg)]
+    pub fn serialize<S: Serializer>(bytes: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+        Bytes(bytes).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        ByteBuf::deserialize(deserializer).map(|bytes| bytes.0)
+    }
+}
+
+/// `#[serde(with)]` module for `Vec<Vec<u8>>` fields holding lists of binary values.
+pub(crate) mod byte_list {
+    use super::*;
+
+    #[allow(clippy::ptr_arg)]
+    pub fn serialize<S: Serializer>(list: &Vec<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+        if is_human_readable(&serializer) {
+            serializer.collect_seq(list.iter().map(|bytes| Bytes(bytes)))
+        } else {
+            list.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        if is_human_readable_de(&deserializer) {
+            let list: Vec<ByteBuf> = Deserialize::deserialize(deserializer)?;
+            Ok(list.into_iter().map(|bytes| bytes.0).collect())
+        } else {
+            Vec::deserialize(deserializer)
+        }
+    }
+}
+
+/// `#[serde(with)]` module for maps with binary keys, e.g. MutableData entries.
+///
+/// Human-readable formats such as JSON only allow strings as map keys.
+pub(crate) mod byte_keys {
+    use super::*;
+
+    pub fn serialize<S: Serializer, V: Serialize>(
+        map: &BTreeMap<Vec<u8>, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if is_human_readable(&serializer) {
+            serializer.collect_map(map.iter().map(|(key, value)| (Bytes(key), value)))
+        } else {
+            map.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<Vec<u8>, V>, D::Error> {
+        if is_human_readable_de(&deserializer) {
+            let map: BTreeMap<ByteBuf, V> = Deserialize::deserialize(deserializer)?;
+            Ok(map.into_iter().map(|(key, value)| (key.0, value)).collect())
+        } else {
+            BTreeMap::deserialize(deserializer)
+        }
+    }
+}
+
+/// `#[serde(with)]` module for maps with both binary keys and binary values.
+pub(crate) mod byte_map {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        map: &BTreeMap<Vec<u8>, Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if is_human_readable(&serializer) {
+            serializer.collect_map(map.iter().map(|(key, value)| (Bytes(key), Bytes(value))))
+        } else {
+            map.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<Vec<u8>, Vec<u8>>, D::Error> {
+        if is_human_readable_de(&deserializer) {
+            let map: BTreeMap<ByteBuf, ByteBuf> = Deserialize::deserialize(deserializer)?;
+            Ok(map
+                .into_iter()
+                .map(|(key, value)| (key.0, value.0))
+                .collect())
+        } else {
+            BTreeMap::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_vectors::{self, Fixtures},
+        Message,
+    };
+    use unwrap::unwrap;
+
+    /// Every request, response and notification, each wrapped in a `Message`.
+    fn messages() -> Vec<Message> {
+        let mut fixtures = Fixtures::new();
+        let mut messages: Vec<_> = test_vectors::requests(&mut fixtures)
+            .into_iter()
+            .map(|request| fixtures.signed_request(request))
+            .collect();
+        for response in test_vectors::responses(&mut fixtures) {
+            messages.push(Message::Response {
+                response,
+                message_id: fixtures.message_id(),
+            });
+        }
+        for notification in test_vectors::notifications(&mut fixtures) {
+            messages.push(Message::Notification { notification });
+        }
+        messages
+    }
+
+    // Without the feature, maps with binary keys can't be represented in JSON.
+    #[cfg(feature = "human-readable")]
+    #[test]
+    fn json_round_trip() {
+        for message in messages() {
+            let json = unwrap!(serde_json::to_string(&message));
+            let decoded: Message = unwrap!(serde_json::from_str(&json));
+            assert!(decoded == message, "{}", json);
+        }
+    }
+
+    #[test]
+    fn cbor_round_trip() {
+        for message in messages() {
+            let cbor = unwrap!(serde_cbor::to_vec(&message));
+            let decoded: Message = unwrap!(serde_cbor::from_slice(&cbor));
+            assert!(decoded == message);
+        }
+    }
+
+    #[cfg(feature = "human-readable")]
+    #[test]
+    fn json_representation() {
+        use super::*;
+        use crate::{ADataEntry, ClientFullId, MDataEntries};
+        use serde_json::{json, to_value};
+
+        let client = ClientFullId::new_ed25519(&mut rand::thread_rng());
+        let name = *client.public_id().name();
+        let public_key = *client.public_id().public_key();
+        let signature = client.sign(b"data");
+
+        assert_eq!(unwrap!(to_value(name)), json!(name.encode_to_zbase32()));
+        assert_eq!(
+            unwrap!(to_value(public_key)),
+            json!(utils::encode(&public_key))
+        );
+        assert_eq!(
+            unwrap!(to_value(&signature)),
+            json!(utils::encode(&signature))
+        );
+        assert_eq!(unwrap!(to_value(ADataUser::Anyone)), json!("Anyone"));
+        assert_eq!(
+            unwrap!(to_value(ADataUser::Key(public_key))),
+            json!(utils::encode(&public_key))
+        );
+        let group = ADataUser::Group("team".to_string());
+        assert_eq!(unwrap!(to_value(&group)), json!("group:team"));
+        assert!(unwrap!(serde_json::from_value::<ADataUser>(json!("group:team"))) == group);
+        assert_eq!(
+            unwrap!(to_value(MDataUser::Key(public_key))),
+            json!(utils::encode(&public_key))
+        );
+        assert!(serde_json::from_value::<MDataUser>(json!("Anyone")).is_err());
+
+        let entry = ADataEntry::new(b"key".to_vec(), b"value".to_vec());
+        assert_eq!(
+            unwrap!(to_value(&entry)),
+            json!({ "key": "a2V5", "value": "dmFsdWU=" })
+        );
+
+        let mut entries = BTreeMap::new();
+        let _ = entries.insert(b"key".to_vec(), b"value".to_vec());
+        assert_eq!(
+            unwrap!(to_value(MDataEntries::Unseq(entries))),
+            json!({ "Unseq": { "a2V5": "dmFsdWU=" } })
+        );
+
+        let invalid = json!({ "key": "a2V5", "value": "not base64" });
+        assert!(serde_json::from_value::<ADataEntry>(invalid).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn message_json() {
+        use crate::{Error, MessageLimits};
+
+        let limits = MessageLimits::default();
+        for message in messages() {
+            assert!(unwrap!(Message::from_json(&message.to_json(), &limits)) == message);
+        }
+
+        let message = unwrap!(messages().into_iter().next());
+        let limits = MessageLimits {
+            max_message_size: 16,
+            ..MessageLimits::default()
+        };
+        assert!(match Message::from_json(&message.to_json(), &limits) {
+            Err(Error::ExceededMessageSize) => true,
+            _ => false,
+        });
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn message_cbor() {
+        use crate::MessageLimits;
+
+        let limits = MessageLimits::default();
+        for message in messages() {
+            assert!(unwrap!(Message::from_cbor(&message.to_cbor(), &limits)) == message);
+        }
+    }
+}
@@ -7,7 +7,7 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::client::Keypair;
+use super::{client::Keypair, PublicId as PublicIdEnum, Signer};
 use crate::{
     utils, ClientFullId, ClientPublicId, Ed25519Digest, Error, PublicKey, Signature, XorName,
 };
@@ -77,6 +77,16 @@ impl FullId {
     }
 }
 
+impl Signer for FullId {
+    fn signer_public_id(&self) -> PublicIdEnum {
+        PublicIdEnum::App(self.public_id.clone())
+    }
+
+    fn sign_data(&self, data: &[u8]) -> Signature {
+        self.sign(data)
+    }
+}
+
 /// A struct representing the public identity of a network App.
 ///
 /// It includes the public signing key, and the App owner's `ClientPublicId`.  The owner's `name()`
@@ -7,7 +7,7 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::{BlsKeypair, BlsKeypairShare};
+use super::{BlsKeypair, BlsKeypairShare, PublicId as PublicIdEnum, Signer};
 use crate::{utils, Ed25519Digest, Error, PublicKey, Signature, XorName};
 use ed25519_dalek::Keypair as Ed25519Keypair;
 use multibase::Decodable;
@@ -120,6 +120,16 @@ impl FullId {
     }
 }
 
+impl Signer for FullId {
+    fn signer_public_id(&self) -> PublicIdEnum {
+        PublicIdEnum::Client(self.public_id.clone())
+    }
+
+    fn sign_data(&self, data: &[u8]) -> Signature {
+        self.sign(data)
+    }
+}
+
 /// A struct representing the public identity of a network Client.
 ///
 /// It includes the public signing key, and this provides the Client's network address, i.e.
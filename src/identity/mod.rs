@@ -11,7 +11,7 @@ pub mod app;
 pub mod client;
 pub mod node;
 
-use crate::{utils, Result, XorName};
+use crate::{utils, PublicKey, Result, Signature, XorName};
 use multibase::Decodable;
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Debug, Display, Formatter};
@@ -43,6 +43,16 @@ impl PublicId {
         }
     }
 
+    /// Returns the key used to verify the entity's signatures: the ed25519 key for Nodes, and the
+    /// public key for Clients and Apps.
+    pub(crate) fn signing_key(&self) -> PublicKey {
+        match self {
+            PublicId::Node(pub_id) => PublicKey::Ed25519(*pub_id.ed25519_public_key()),
+            PublicId::Client(pub_id) => *pub_id.public_key(),
+            PublicId::App(pub_id) => *pub_id.public_key(),
+        }
+    }
+
     /// Returns the PublicId serialised and encoded in z-base-32.
     pub fn encode_to_zbase32(&self) -> String {
         utils::encode(&self)
@@ -54,6 +64,16 @@ impl PublicId {
     }
 }
 
+/// A full identity which can sign requests on behalf of a client, i.e. a `ClientFullId` or an
+/// `AppFullId`.
+pub trait Signer {
+    /// Returns the public identity of the signer.
+    fn signer_public_id(&self) -> PublicId;
+
+    /// Creates a detached signature of `data`.
+    fn sign_data(&self, data: &[u8]) -> Signature;
+}
+
 impl Debug for PublicId {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         match self {
@@ -86,7 +106,8 @@ struct BlsKeypairShare {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{ClientFullId, Error};
+    use crate::{ClientFullId, Error, IDataAddress};
+    use std::cmp::Ordering;
     use unwrap::unwrap;
 
     #[test]
@@ -149,4 +170,27 @@ mod tests {
         );
         assert!(PublicId::decode_from_zbase32("c419cxim9").is_err());
     }
+
+    #[test]
+    fn closest_nodes() {
+        let mut rng = rand::thread_rng();
+        let ids: Vec<_> = (0..8).map(|_| node::FullId::new(&mut rng)).collect();
+        let nodes: Vec<_> = ids.iter().map(|id| id.public_id().clone()).collect();
+        let address = IDataAddress::Pub(rand::random());
+        let target = address.name();
+
+        let sorted: Vec<_> = node::nodes_by_distance(target, &nodes).collect();
+        assert_eq!(sorted.len(), nodes.len());
+        for pair in sorted.windows(2) {
+            assert_eq!(
+                target.cmp_distance(pair[0].name(), pair[1].name()),
+                Ordering::Less
+            );
+        }
+
+        let closest = node::closest_nodes(target, &nodes, 3);
+        assert_eq!(closest, sorted[..3].to_vec());
+        assert_eq!(node::closest_nodes(target, &nodes, 20).len(), nodes.len());
+        assert!(node::closest_nodes(target, &nodes, 0).is_empty());
+    }
 }
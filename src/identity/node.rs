@@ -187,3 +187,24 @@ impl Display for PublicId {
         (self as &Debug).fmt(formatter)
     }
 }
+
+/// Returns the given nodes sorted by the XOR distance of their names to `target`, closest first.
+///
+/// `target` is usually the name of a data address, e.g. `IDataAddress::name()`.
+pub fn nodes_by_distance<'a, I>(target: &XorName, nodes: I) -> impl Iterator<Item = &'a PublicId>
+where
+    I: IntoIterator<Item = &'a PublicId>,
+{
+    let mut nodes: Vec<_> = nodes.into_iter().collect();
+    nodes.sort_by(|lhs, rhs| target.cmp_distance(lhs.name(), rhs.name()));
+    nodes.into_iter()
+}
+
+/// Returns up to `count` of the given nodes which are closest to `target` in XOR distance, i.e.
+/// the holders of data stored at `target`. The result is sorted, closest first.
+pub fn closest_nodes<'a, I>(target: &XorName, nodes: I, count: usize) -> Vec<&'a PublicId>
+where
+    I: IntoIterator<Item = &'a PublicId>,
+{
+    nodes_by_distance(target, nodes).take(count).collect()
+}
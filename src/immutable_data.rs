@@ -7,7 +7,10 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use crate::{utils, Error, PublicKey, XorName};
+use crate::{
+    human_readable::{ByteBuf, Bytes},
+    utils, Capability, DataAddress, Error, GenericAction, PublicKey, SigningContext, XorName,
+};
 use bincode::serialized_size;
 use multibase::Decodable;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -58,6 +61,27 @@ impl UnpubImmutableData {
         &self.owner
     }
 
+    /// Checks that `capability` allows `requester` to perform `action` at time `now`, in seconds
+    /// since the UNIX epoch, on the network of `context`. Without a capability, only the owner can
+    /// read or delete the data.
+    pub fn check_capability(
+        &self,
+        context: &SigningContext,
+        capability: &Capability,
+        action: GenericAction,
+        requester: PublicKey,
+        now: u64,
+    ) -> Result<(), Error> {
+        capability.verify(
+            context,
+            &self.owner,
+            &DataAddress::Immutable(self.address),
+            &requester,
+            action,
+            now,
+        )
+    }
+
     /// Returns the address.
     pub fn address(&self) -> &Address {
         &self.address
@@ -86,14 +110,14 @@ impl UnpubImmutableData {
 
 impl Serialize for UnpubImmutableData {
     fn serialize<S: Serializer>(&self, serialiser: S) -> Result<S::Ok, S::Error> {
-        (&self.value, &self.owner).serialize(serialiser)
+        (Bytes(&self.value), &self.owner).serialize(serialiser)
     }
 }
 
 impl<'de> Deserialize<'de> for UnpubImmutableData {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let (value, owner): (Vec<u8>, PublicKey) = Deserialize::deserialize(deserializer)?;
-        Ok(UnpubImmutableData::new(value, owner))
+        let (value, owner): (ByteBuf, PublicKey) = Deserialize::deserialize(deserializer)?;
+        Ok(UnpubImmutableData::new(value.0, owner))
     }
 }
 
@@ -156,14 +180,14 @@ impl PubImmutableData {
 
 impl Serialize for PubImmutableData {
     fn serialize<S: Serializer>(&self, serialiser: S) -> Result<S::Ok, S::Error> {
-        self.value.serialize(serialiser)
+        Bytes(&self.value).serialize(serialiser)
     }
 }
 
 impl<'de> Deserialize<'de> for PubImmutableData {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let value: Vec<u8> = Deserialize::deserialize(deserializer)?;
-        Ok(PubImmutableData::new(value))
+        let value = ByteBuf::deserialize(deserializer)?;
+        Ok(PubImmutableData::new(value.0))
     }
 }
 
@@ -55,55 +55,87 @@
     variant_size_differences
 )]
 
+mod access_control;
 mod append_only_data;
+mod capability;
+mod challenge;
 mod coins;
 mod errors;
+mod group;
+mod human_readable;
 mod identity;
 mod immutable_data;
+mod limits;
+mod mdata_diff;
+mod mock_vault;
 mod mutable_data;
+mod prefix;
 mod public_key;
 mod request;
 mod response;
+mod signing;
+#[cfg(test)]
+mod test_vectors;
 mod transaction;
 mod utils;
+mod wire;
 
+pub use access_control::{AccessControl, GenericAction};
 pub use append_only_data::{
-    Action as ADataAction, Address as ADataAddress, AppendOnlyData,
-    AppendOperation as ADataAppendOperation, Data as AData, Entries as ADataEntries,
-    Entry as ADataEntry, Index as ADataIndex, Indices as ADataIndices, Kind as ADataKind,
-    Owner as ADataOwner, Permissions as ADataPermissions,
+    AccessDecision as ADataAccessDecision, Action as ADataAction, Address as ADataAddress,
+    AppendOnlyData, AppendOperation as ADataAppendOperation, Data as AData,
+    Entries as ADataEntries, Entry as ADataEntry, Index as ADataIndex, Indices as ADataIndices,
+    Kind as ADataKind, Owner as ADataOwner, Permissions as ADataPermissions,
     PubPermissionSet as ADataPubPermissionSet, PubPermissions as ADataPubPermissions,
     PubSeqAppendOnlyData, PubUnseqAppendOnlyData, SeqAppendOnly,
     UnpubPermissionSet as ADataUnpubPermissionSet, UnpubPermissions as ADataUnpubPermissions,
     UnpubSeqAppendOnlyData, UnpubUnseqAppendOnlyData, UnseqAppendOnly, User as ADataUser,
 };
+pub use capability::{
+    Capability, Grant as CapabilityGrant, Grantee as CapabilityGrantee, MAX_CAPABILITY_CHAIN_LEN,
+};
+pub use challenge::{ChallengeIssuer, CHALLENGE_NONCE_LEN};
 pub use coins::{Coins, MAX_COINS_VALUE};
-pub use errors::{EntryError, Error, Result};
+pub use errors::{EntryError, Error, ErrorCategory, ParseError, Result};
+pub use group::{Group, GroupName};
 pub use identity::{
     app::{FullId as AppFullId, PublicId as AppPublicId},
     client::{FullId as ClientFullId, PublicId as ClientPublicId},
-    node::{FullId as NodeFullId, PublicId as NodePublicId},
-    PublicId,
+    node::{closest_nodes, nodes_by_distance, FullId as NodeFullId, PublicId as NodePublicId},
+    PublicId, Signer,
 };
 pub use immutable_data::{
     Address as IDataAddress, Data as IData, Kind as IDataKind, PubImmutableData,
     UnpubImmutableData, MAX_IMMUTABLE_DATA_SIZE_IN_BYTES,
 };
+pub use limits::MessageLimits;
+pub use mdata_diff::{Merge as MDataMerge, ShellDiff as MDataShellDiff};
+pub use mock_vault::MockVault;
 pub use mutable_data::{
-    Action as MDataAction, Address as MDataAddress, Data as MData, Entries as MDataEntries,
-    EntryActions as MDataEntryActions, Kind as MDataKind, PermissionSet as MDataPermissionSet,
+    AccessDecision as MDataAccessDecision, Action as MDataAction, Address as MDataAddress,
+    Data as MData, Entries as MDataEntries, EntryActions as MDataEntryActions,
+    KeyFilter as MDataKeyFilter, Kind as MDataKind, PermissionSet as MDataPermissionSet,
     SeqEntries as MDataSeqEntries, SeqEntryAction as MDataSeqEntryAction,
     SeqEntryActions as MDataSeqEntryActions, SeqMutableData, SeqValue as MDataSeqValue,
     UnseqEntries as MDataUnseqEntries, UnseqEntryAction as MDataUnseqEntryAction,
-    UnseqEntryActions as MDataUnseqEntryActions, UnseqMutableData, Value as MDataValue,
-    Values as MDataValues,
+    UnseqEntryActions as MDataUnseqEntryActions, UnseqMutableData, User as MDataUser,
+    Value as MDataValue, Values as MDataValues, MAX_MDATA_ENTRIES, MAX_MDATA_KEY_SIZE_IN_BYTES,
+    MAX_MDATA_SIZE_IN_BYTES, MAX_MDATA_VALUE_SIZE_IN_BYTES,
 };
+pub use prefix::Prefix;
 pub use public_key::{PublicKey, Signature};
-pub use request::{LoginPacket, Request, MAX_LOGIN_PACKET_BYTES};
+pub use request::{
+    DataType, Destination, LoginPacket, Request, RequestKind, MAX_LOGIN_PACKET_BYTES,
+};
 pub use response::{Response, TryFromError};
 pub use sha3::Sha3_512 as Ed25519Digest;
+pub use signing::{NetworkId, SignatureDomain, SigningContext};
 pub use transaction::{Transaction, TransactionId};
 pub use utils::verify_signature;
+pub use wire::{
+    is_supported_protocol_version, ContentKind, WireContent, WireEnvelope, COMPATIBILITY_TABLE,
+    PROTOCOL_VERSION, SUPPORTED_PROTOCOL_VERSIONS,
+};
 
 use hex_fmt::HexFmt;
 use multibase::Decodable;
@@ -112,7 +144,11 @@ use rand::{
     Rng,
 };
 use serde::{Deserialize, Serialize};
-use std::fmt::{self, Debug, Display, Formatter};
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    fmt::{self, Debug, Display, Formatter},
+};
 
 /// Object storing a data variant.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
@@ -139,6 +175,24 @@ impl Data {
     pub fn is_unpub(&self) -> bool {
         !self.is_pub()
     }
+
+    /// Returns the address.
+    pub fn address(&self) -> DataAddress {
+        match *self {
+            Data::Immutable(ref idata) => DataAddress::Immutable(*idata.address()),
+            Data::Mutable(ref mdata) => DataAddress::Mutable(*mdata.address()),
+            Data::AppendOnly(ref adata) => DataAddress::AppendOnly(*adata.address()),
+        }
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        match *self {
+            Data::Immutable(ref idata) => idata.name(),
+            Data::Mutable(ref mdata) => mdata.name(),
+            Data::AppendOnly(ref adata) => adata.name(),
+        }
+    }
 }
 
 impl From<IData> for Data {
@@ -159,6 +213,70 @@ impl From<AData> for Data {
     }
 }
 
+/// Address of a data variant.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum DataAddress {
+    /// ImmutableData address.
+    Immutable(IDataAddress),
+    /// MutableData address.
+    Mutable(MDataAddress),
+    /// AppendOnlyData address.
+    AppendOnly(ADataAddress),
+}
+
+impl DataAddress {
+    /// Returns the name.
+    pub fn name(&self) -> &XorName {
+        match self {
+            DataAddress::Immutable(address) => address.name(),
+            DataAddress::Mutable(address) => address.name(),
+            DataAddress::AppendOnly(address) => address.name(),
+        }
+    }
+
+    /// Returns true if published.
+    pub fn is_pub(&self) -> bool {
+        match self {
+            DataAddress::Immutable(address) => address.is_pub(),
+            DataAddress::Mutable(_) => false,
+            DataAddress::AppendOnly(address) => address.is_pub(),
+        }
+    }
+
+    /// Returns true if unpublished.
+    pub fn is_unpub(&self) -> bool {
+        !self.is_pub()
+    }
+
+    /// Returns the `DataAddress` serialised and encoded in z-base-32.
+    pub fn encode_to_zbase32(&self) -> String {
+        utils::encode(&self)
+    }
+
+    /// Creates from z-base-32 encoded string.
+    pub fn decode_from_zbase32<I: Decodable>(encoded: I) -> Result<Self> {
+        utils::decode(encoded)
+    }
+}
+
+impl From<IDataAddress> for DataAddress {
+    fn from(address: IDataAddress) -> Self {
+        DataAddress::Immutable(address)
+    }
+}
+
+impl From<MDataAddress> for DataAddress {
+    fn from(address: MDataAddress) -> Self {
+        DataAddress::Mutable(address)
+    }
+}
+
+impl From<ADataAddress> for DataAddress {
+    fn from(address: ADataAddress) -> Self {
+        DataAddress::AppendOnly(address)
+    }
+}
+
 /// Permissions for an app stored by the Client Handlers.
 #[derive(
     Copy, Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Default, Debug,
@@ -171,6 +289,9 @@ pub struct AppPermissions {
 /// Constant byte length of `XorName`.
 pub const XOR_NAME_LEN: usize = 32;
 
+/// Constant bit length of `XorName`.
+pub const XOR_NAME_BITS: usize = 8 * XOR_NAME_LEN;
+
 /// A [`XOR_NAME_BITS`](constant.XOR_NAME_BITS.html)-bit number, viewed as a point in XOR space.
 ///
 /// This wraps an array of [`XOR_NAME_LEN`](constant.XOR_NAME_LEN.html) bytes, i.e. a number
@@ -180,7 +301,7 @@ pub const XOR_NAME_LEN: usize = 32;
 /// i. e. the points with IDs `x` and `y` are considered to have distance `x xor y`.
 ///
 /// [1]: https://en.wikipedia.org/wiki/Kademlia#System_details
-#[derive(Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct XorName(pub [u8; XOR_NAME_LEN]);
 
 impl XorName {
@@ -193,6 +314,86 @@ impl XorName {
     pub fn decode_from_zbase32<I: Decodable>(encoded: I) -> Result<Self> {
         utils::decode(encoded)
     }
+
+    /// Returns the XOR distance between `self` and `other`, viewed as a point in XOR space.
+    pub fn distance(&self, other: &XorName) -> XorName {
+        let mut distance = XorName::default();
+        for (byte, (lhs, rhs)) in distance.0.iter_mut().zip(self.0.iter().zip(other.0.iter())) {
+            *byte = lhs ^ rhs;
+        }
+        distance
+    }
+
+    /// Compares the distance of the arguments to `self`. Returns `Less` if `lhs` is closer,
+    /// `Greater` if `rhs` is closer, and `Equal` if `lhs == rhs`. (The XOR distance can only be
+    /// equal if the arguments are equal.)
+    pub fn cmp_distance(&self, lhs: &XorName, rhs: &XorName) -> Ordering {
+        for i in 0..XOR_NAME_LEN {
+            if lhs.0[i] != rhs.0[i] {
+                return Ord::cmp(&(lhs.0[i] ^ self.0[i]), &(rhs.0[i] ^ self.0[i]));
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Returns `true` if the `i`-th bit is `1`. The bits are numbered from the most significant
+    /// one, so bit `0` determines which half of the XOR space the name is in.
+    ///
+    /// Returns `false` if `i` is not less than [`XOR_NAME_BITS`](constant.XOR_NAME_BITS.html).
+    pub fn bit(&self, i: usize) -> bool {
+        if i >= XOR_NAME_BITS {
+            return false;
+        }
+        (self.0[i / 8] >> (7 - i % 8)) & 1 != 0
+    }
+
+    /// Returns the number of leading bits in which `self` and `other` agree.
+    pub fn common_prefix_len(&self, other: &XorName) -> usize {
+        for i in 0..XOR_NAME_LEN {
+            if self.0[i] != other.0[i] {
+                return i * 8 + (self.0[i] ^ other.0[i]).leading_zeros() as usize;
+            }
+        }
+        XOR_NAME_BITS
+    }
+
+    /// Returns a copy of `self`, with the `i`-th bit flipped.
+    ///
+    /// Returns `self` unchanged if `i` is not less than
+    /// [`XOR_NAME_BITS`](constant.XOR_NAME_BITS.html).
+    pub fn with_flipped_bit(mut self, i: usize) -> XorName {
+        if i < XOR_NAME_BITS {
+            self.0[i / 8] ^= 1 << (7 - i % 8);
+        }
+        self
+    }
+
+    /// Returns a copy of `self`, with the `i`-th bit set to `bit`.
+    pub(crate) fn with_bit(self, i: usize, bit: bool) -> XorName {
+        if self.bit(i) == bit {
+            self
+        } else {
+            self.with_flipped_bit(i)
+        }
+    }
+
+    /// Returns a copy of `self`, with all the bits from the `n`-th one onwards set to `bit`.
+    pub(crate) fn with_remaining_bits(mut self, n: usize, bit: bool) -> XorName {
+        for (i, byte) in self.0.iter_mut().enumerate() {
+            // Mask of the bits in this byte which are at or after position `n`.
+            let mask = match n.checked_sub(i * 8) {
+                None => 0xff,
+                Some(skipped) if skipped >= 8 => 0,
+                Some(skipped) => 0xff >> skipped,
+            };
+            if bit {
+                *byte |= mask;
+            } else {
+                *byte &= !mask;
+            }
+        }
+        self
+    }
 }
 
 impl Debug for XorName {
@@ -227,6 +428,9 @@ pub enum Message {
         message_id: MessageId,
         /// Signature of `(request, message_id)`. Optional if the request is read-only.
         signature: Option<Signature>,
+        /// Capability allowing the requester to perform the request in the absence of a
+        /// permission entry. Not covered by the signature, as it's signed by its issuers.
+        capability: Option<Capability>,
     },
     /// Response matched to the message ID.
     Response {
@@ -235,7 +439,7 @@ pub enum Message {
         /// Associated message ID.
         message_id: MessageId,
     },
-    /// Notification of a transaction.
+    /// Notification of a transaction or of a change to subscribed data.
     Notification {
         /// Notification.
         notification: Notification,
@@ -243,6 +447,126 @@ pub enum Message {
 }
 
 impl Message {
+    /// Creates a `Message::Request` with a new random message ID, signed by `signer` for the
+    /// network of `context`.
+    pub fn new_signed_request<S: Signer>(
+        context: &SigningContext,
+        signer: &S,
+        request: Request,
+    ) -> Self {
+        let message_id = MessageId::new();
+        let signature = signer.sign_data(&context.request_payload(&request, &message_id));
+        Message::Request {
+            request,
+            message_id,
+            signature: Some(signature),
+            capability: None,
+        }
+    }
+
+    /// Verifies that this request was signed by `requester` for the network of `context`.
+    ///
+    /// Unsigned requests are only accepted if they don't [require a
+    /// signature](enum.Request.html#method.requires_signature). Returns `Err(InvalidOperation)`
+    /// if `self` is not a `Message::Request`.
+    pub fn verify(&self, context: &SigningContext, requester: &PublicId) -> Result<()> {
+        match self {
+            Message::Request {
+                request,
+                message_id,
+                signature: Some(signature),
+                ..
+            } => requester
+                .signing_key()
+                .verify(signature, context.request_payload(request, message_id)),
+            Message::Request {
+                request,
+                signature: None,
+                ..
+            } => {
+                if request.requires_signature() {
+                    Err(Error::InvalidSignature)
+                } else {
+                    Ok(())
+                }
+            }
+            Message::Response { .. } | Message::Notification { .. } => Err(Error::InvalidOperation),
+        }
+    }
+
+    /// Decodes a message from untrusted `bytes`, rejecting it if it exceeds any of `limits`.
+    ///
+    /// The size limit is enforced while decoding, so a malicious length prefix can't cause
+    /// allocations beyond that limit.
+    pub fn from_bytes(bytes: &[u8], limits: &MessageLimits) -> Result<Self> {
+        limits.decode(bytes)
+    }
+
+    /// Encodes the message into the form expected by [`from_bytes`](#method.from_bytes).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        utils::serialise(self)
+    }
+
+    /// Decodes a message from untrusted `json`, rejecting it if it exceeds any of `limits`.
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str, limits: &MessageLimits) -> Result<Self> {
+        if json.len() > limits.max_message_size {
+            return Err(Error::ExceededMessageSize);
+        }
+        let message = serde_json::from_str(json)
+            .map_err(|_| Error::FailedToParse(ParseError::InvalidSerialisation))?;
+        limits.validate(&message)?;
+        Ok(message)
+    }
+
+    /// Encodes the message as JSON, using the human-readable representations of its contents.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        unwrap::unwrap!(serde_json::to_string(self))
+    }
+
+    /// Decodes a message from untrusted CBOR `bytes`, rejecting it if it exceeds any of `limits`.
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor(bytes: &[u8], limits: &MessageLimits) -> Result<Self> {
+        if bytes.len() > limits.max_message_size {
+            return Err(Error::ExceededMessageSize);
+        }
+        let message = serde_cbor::from_slice(bytes)
+            .map_err(|_| Error::FailedToParse(ParseError::InvalidSerialisation))?;
+        limits.validate(&message)?;
+        Ok(message)
+    }
+
+    /// Encodes the message as CBOR.
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Vec<u8> {
+        unwrap::unwrap!(serde_cbor::to_vec(self))
+    }
+
+    /// Attaches `capability` to a `Message::Request`, replacing any previously attached one.
+    ///
+    /// Returns `Err(InvalidOperation)` if `self` is not a `Message::Request`.
+    pub fn attach_capability(&mut self, capability: Capability) -> Result<()> {
+        match self {
+            Message::Request {
+                capability: attached,
+                ..
+            } => {
+                *attached = Some(capability);
+                Ok(())
+            }
+            Message::Response { .. } | Message::Notification { .. } => Err(Error::InvalidOperation),
+        }
+    }
+
+    /// Gets the capability attached to a `Message::Request`, if any.
+    pub fn capability(&self) -> Option<&Capability> {
+        match self {
+            Message::Request { capability, .. } => capability.as_ref(),
+            Message::Response { .. } | Message::Notification { .. } => None,
+        }
+    }
+
     /// Gets the message ID, if applicable.
     pub fn message_id(&self) -> Option<MessageId> {
         match self {
@@ -285,13 +609,57 @@ pub enum Challenge {
     Response(PublicId, Signature),
 }
 
-/// Notification of a transaction.
+/// Notification pushed to clients.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
-pub struct Notification(pub Transaction);
+pub enum Notification {
+    /// Coins have been credited to the client's balance.
+    Transaction(Transaction),
+    /// A subscribed MutableData has been mutated.
+    MDataMutated {
+        /// MutableData address.
+        address: MDataAddress,
+        /// Version of the MutableData fields after the mutation.
+        version: u64,
+        /// Keys of the inserted, updated or deleted entries the subscriber is allowed to read.
+        keys: BTreeSet<Vec<u8>>,
+    },
+    /// Entries have been appended to a subscribed AppendOnlyData.
+    ADataAppended {
+        /// AppendOnlyData address.
+        address: ADataAddress,
+        /// Entries index after the append.
+        index: u64,
+        /// Keys of the appended entries, in order.
+        keys: Vec<Vec<u8>>,
+    },
+    /// The permissions or the membership of a permission group of a subscribed MutableData or
+    /// AppendOnlyData have changed, so the subscriber may need to fetch its shell again.
+    ShellChanged {
+        /// Address of the data.
+        address: DataAddress,
+    },
+}
+
+impl Notification {
+    /// Returns the address of the data this notification is about, if any.
+    pub fn data_address(&self) -> Option<DataAddress> {
+        match self {
+            Notification::Transaction(_) => None,
+            Notification::MDataMutated { address, .. } => Some((*address).into()),
+            Notification::ADataAppended { address, .. } => Some((*address).into()),
+            Notification::ShellChanged { address } => Some(*address),
+        }
+    }
+}
 
 #[cfg(test)]
 mod test {
-    use crate::XorName;
+    use crate::{
+        AppFullId, ClientFullId, DataAddress, Error, IDataAddress, MDataAddress, Message,
+        MessageId, NetworkId, PublicId, Request, SigningContext, XorName, XOR_NAME_BITS,
+        XOR_NAME_LEN,
+    };
+    use std::cmp::Ordering;
     use unwrap::unwrap;
 
     #[test]
@@ -301,4 +669,94 @@ mod test {
         let decoded = unwrap!(XorName::decode_from_zbase32(&encoded));
         assert_eq!(name, decoded);
     }
+
+    #[test]
+    fn zbase32_encode_decode_data_address() {
+        let address = DataAddress::from(MDataAddress::Seq {
+            name: rand::random(),
+            tag: 15000,
+        });
+        let encoded = address.encode_to_zbase32();
+        let decoded = unwrap!(DataAddress::decode_from_zbase32(&encoded));
+        assert_eq!(address, decoded);
+    }
+
+    #[test]
+    fn xorname_bits() {
+        let mut bytes = [0; XOR_NAME_LEN];
+        bytes[0] = 0b1000_0001;
+        let name = XorName(bytes);
+        assert!(name.bit(0));
+        assert!(!name.bit(1));
+        assert!(name.bit(7));
+        assert!(!name.bit(XOR_NAME_BITS));
+
+        let flipped = name.with_flipped_bit(9);
+        assert!(flipped.bit(9));
+        assert_eq!(name.common_prefix_len(&flipped), 9);
+        assert_eq!(name.common_prefix_len(&name), XOR_NAME_BITS);
+        assert_eq!(name.with_flipped_bit(XOR_NAME_BITS), name);
+    }
+
+    #[test]
+    fn xorname_distance() {
+        let origin = XorName::default();
+        let near = origin.with_flipped_bit(XOR_NAME_BITS - 1);
+        let far = origin.with_flipped_bit(0);
+
+        assert_eq!(origin.distance(&near), near);
+        assert_eq!(near.distance(&near), origin);
+        assert_eq!(origin.cmp_distance(&near, &far), Ordering::Less);
+        assert_eq!(origin.cmp_distance(&far, &near), Ordering::Greater);
+        assert_eq!(far.cmp_distance(&near, &origin), Ordering::Greater);
+        assert_eq!(origin.cmp_distance(&far, &far), Ordering::Equal);
+    }
+
+    #[test]
+    fn signed_request() {
+        let mut rng = rand::thread_rng();
+        let client = ClientFullId::new_ed25519(&mut rng);
+        let client_id = PublicId::Client(client.public_id().clone());
+        let app = AppFullId::new_bls(&mut rng, client.public_id().clone());
+        let app_id = PublicId::App(app.public_id().clone());
+
+        let context = SigningContext::new(NetworkId::default());
+        let message = Message::new_signed_request(
+            &context,
+            &client,
+            Request::DeleteMData(MDataAddress::Seq {
+                name: rand::random(),
+                tag: 100,
+            }),
+        );
+        unwrap!(message.verify(&context, &client_id));
+        assert_eq!(
+            message.verify(&context, &app_id),
+            Err(Error::SigningKeyTypeMismatch)
+        );
+
+        let message = Message::new_signed_request(&context, &app, Request::GetBalance);
+        unwrap!(message.verify(&context, &app_id));
+        let other_app = AppFullId::new_bls(&mut rng, client.public_id().clone());
+        assert_eq!(
+            message.verify(&context, &PublicId::App(other_app.public_id().clone())),
+            Err(Error::InvalidSignature)
+        );
+
+        // Only read-only requests may be unsigned.
+        let unsigned = |request| Message::Request {
+            request,
+            message_id: MessageId::new(),
+            signature: None,
+            capability: None,
+        };
+        unwrap!(unsigned(Request::GetBalance).verify(&context, &client_id));
+        assert_eq!(
+            unsigned(Request::DeleteUnpubIData(IDataAddress::Unpub(
+                rand::random()
+            )))
+            .verify(&context, &client_id),
+            Err(Error::InvalidSignature)
+        );
+    }
 }
@@ -0,0 +1,445 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{
+    ADataEntry, Error, LoginPacket, MData, MDataEntryActions, MDataKeyFilter, MDataSeqEntryAction,
+    MDataUnseqEntryAction, Message, ParseError, Request, Result, MAX_IMMUTABLE_DATA_SIZE_IN_BYTES,
+    MAX_LOGIN_PACKET_BYTES, MAX_MDATA_ENTRIES, MAX_MDATA_KEY_SIZE_IN_BYTES,
+    MAX_MDATA_VALUE_SIZE_IN_BYTES,
+};
+use bincode::{self, ErrorKind};
+use std::collections::BTreeMap;
+
+/// Limits enforced when decoding a `Message` from untrusted bytes.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct MessageLimits {
+    /// Maximum size of the encoded message in bytes.
+    pub max_message_size: usize,
+    /// Maximum number of entries stored, appended or mutated by a single request.
+    pub max_entries: usize,
+    /// Maximum length of an entry key in bytes.
+    pub max_key_len: usize,
+    /// Maximum length of an entry value in bytes.
+    pub max_value_len: usize,
+    /// Maximum size of the data in a login packet in bytes.
+    pub max_login_packet_size: usize,
+}
+
+impl Default for MessageLimits {
+    fn default() -> Self {
+        Self {
+            max_message_size: 2 * MAX_IMMUTABLE_DATA_SIZE_IN_BYTES as usize,
+            max_entries: MAX_MDATA_ENTRIES as usize,
+            max_key_len: MAX_MDATA_KEY_SIZE_IN_BYTES as usize,
+            max_value_len: MAX_MDATA_VALUE_SIZE_IN_BYTES as usize,
+            max_login_packet_size: MAX_LOGIN_PACKET_BYTES,
+        }
+    }
+}
+
+impl MessageLimits {
+    /// Decodes `bytes` into a `Message`, failing as soon as any of the limits is exceeded.
+    pub(crate) fn decode(&self, bytes: &[u8]) -> Result<Message> {
+        if bytes.len() > self.max_message_size {
+            return Err(Error::ExceededMessageSize);
+        }
+        let message = bincode::config()
+            .limit(self.max_message_size as u64)
+            .deserialize(bytes)
+            .map_err(|error| match *error {
+                ErrorKind::SizeLimit => Error::ExceededMessageSize,
+                _ => Error::FailedToParse(ParseError::InvalidSerialisation),
+            })?;
+        self.validate(&message)?;
+        Ok(message)
+    }
+
+    /// Checks the contents of `message` against the limits.
+    ///
+    /// Only requests are checked beyond the overall message size.
+    pub fn validate(&self, message: &Message) -> Result<()> {
+        match message {
+            Message::Request { request, .. } => self.validate_request(request),
+            Message::Response { .. } | Message::Notification { .. } => Ok(()),
+        }
+    }
+
+    fn validate_request(&self, request: &Request) -> Result<()> {
+        use Request::*;
+
+        match request {
+            // IData
+            PutIData(data) => {
+                if data.validate_size() {
+                    Ok(())
+                } else {
+                    Err(Error::ExceededSize)
+                }
+            }
+            GetIData(_) | DeleteUnpubIData(_) => Ok(()),
+            // MData
+            PutMData(data) => self.validate_mdata(data),
+            GetMDataValue { key, .. } => self.validate_key(key),
+            MutateMDataEntries { actions, .. } => self.validate_entry_actions(actions),
+            GetMData(_)
+            | DeleteMData(_)
+            | GetMDataShell(_)
+            | GetMDataVersion(_)
+            | ListMDataEntries(_)
+            | ListMDataKeys(_)
+            | ListMDataValues(_)
+            | SetMDataUserPermissions { .. }
+            | DelMDataUserPermissions { .. }
+            | ListMDataPermissions(_)
+            | ListMDataUserPermissions { .. } => Ok(()),
+            // AData
+            PutAData(data) => self.validate_adata_entries(data.entries()),
+            GetADataValue { key, .. } => self.validate_key(key),
+            AppendSeq { append, .. } | AppendUnseq(append) => {
+                self.validate_adata_entries(&append.values)
+            }
+            GetAData(_)
+            | GetADataShell { .. }
+            | DeleteAData(_)
+            | GetADataRange { .. }
+            | GetADataIndices(_)
+            | GetADataLastEntry(_)
+            | GetADataPermissions { .. }
+            | GetPubADataUserPermissions { .. }
+            | GetUnpubADataUserPermissions { .. }
+            | GetADataOwners { .. }
+            | AddPubADataPermissions { .. }
+            | AddUnpubADataPermissions { .. }
+            | SetADataOwner { .. } => Ok(()),
+            // Coins
+            TransferCoins { .. } | GetBalance | CreateBalance { .. } => Ok(()),
+            // Login Packet
+            CreateLoginPacket(login_packet)
+            | UpdateLoginPacket(login_packet)
+            | CreateLoginPacketFor {
+                new_login_packet: login_packet,
+                ..
+            } => self.validate_login_packet(login_packet),
+            GetLoginPacket(_) => Ok(()),
+            // Client (Owner) to SrcElders
+            ListAuthKeysAndVersion | InsAuthKey { .. } | DelAuthKey { .. } => Ok(()),
+            // Subscriptions
+            SubscribeMData(_) | UnsubscribeMData(_) | SubscribeAData(_) | UnsubscribeAData(_) => {
+                Ok(())
+            }
+            // Permission Groups
+            AddMDataGroupMember { .. }
+            | RemoveMDataGroupMember { .. }
+            | AddADataGroupMember { .. }
+            | RemoveADataGroupMember { .. } => Ok(()),
+            // Capabilities
+            RevokeCapability { .. } => Ok(()),
+            // Entry Permissions
+            SetMDataEntryPermissions { prefix, .. } | DelMDataEntryPermissions { prefix, .. } => {
+                self.validate_key(prefix)
+            }
+            // Tombstones
+            PurgeMDataTombstones(_) => Ok(()),
+            // Paginated Listing
+            ListMDataEntriesPage {
+                filter,
+                limit,
+                cursor,
+                ..
+            }
+            | ListMDataKeysPage {
+                filter,
+                limit,
+                cursor,
+                ..
+            }
+            | ListMDataValuesPage {
+                filter,
+                limit,
+                cursor,
+                ..
+            } => self.validate_page(filter, *limit, cursor.as_ref()),
+        }
+    }
+
+    fn validate_page(
+        &self,
+        filter: &MDataKeyFilter,
+        limit: u64,
+        cursor: Option<&Vec<u8>>,
+    ) -> Result<()> {
+        if limit == 0 {
+            return Err(Error::InvalidOperation);
+        }
+        if limit > self.max_entries as u64 {
+            return Err(Error::TooManyEntries);
+        }
+        match filter {
+            MDataKeyFilter::All => (),
+            MDataKeyFilter::Range { start, end } => start
+                .iter()
+                .chain(end)
+                .try_for_each(|key| self.validate_key(key))?,
+            MDataKeyFilter::Prefix(prefix) => self.validate_key(prefix)?,
+        }
+        cursor.map_or(Ok(()), |cursor| self.validate_key(cursor))
+    }
+
+    fn validate_mdata(&self, data: &MData) -> Result<()> {
+        data.validate_limits()?;
+        match data {
+            MData::Seq(data) => {
+                self.validate_entry_count(data.entries().len())?;
+                self.validate_keys(data.entry_permissions())?;
+                self.validate_keys(data.tombstones())?;
+                data.entries()
+                    .iter()
+                    .try_for_each(|(key, value)| self.validate_entry(key, &value.data))
+            }
+            MData::Unseq(data) => {
+                self.validate_entry_count(data.entries().len())?;
+                self.validate_keys(data.entry_permissions())?;
+                data.entries()
+                    .iter()
+                    .try_for_each(|(key, value)| self.validate_entry(key, value))
+            }
+        }
+    }
+
+    fn validate_entry_actions(&self, actions: &MDataEntryActions) -> Result<()> {
+        match actions {
+            MDataEntryActions::Seq(actions) => {
+                self.validate_entry_count(actions.actions().len())?;
+                actions
+                    .actions()
+                    .iter()
+                    .try_for_each(|(key, action)| match action {
+                        MDataSeqEntryAction::Ins(value) | MDataSeqEntryAction::Update(value) => {
+                            self.validate_entry(key, &value.data)
+                        }
+                        MDataSeqEntryAction::Del(_) => self.validate_key(key),
+                    })
+            }
+            MDataEntryActions::Unseq(actions) => {
+                self.validate_entry_count(actions.actions().len())?;
+                actions
+                    .actions()
+                    .iter()
+                    .try_for_each(|(key, action)| match action {
+                        MDataUnseqEntryAction::Ins(value)
+                        | MDataUnseqEntryAction::Update(value) => self.validate_entry(key, value),
+                        MDataUnseqEntryAction::Del => self.validate_key(key),
+                    })
+            }
+        }
+    }
+
+    fn validate_adata_entries(&self, entries: &[ADataEntry]) -> Result<()> {
+        self.validate_entry_count(entries.len())?;
+        entries
+            .iter()
+            .try_for_each(|entry| self.validate_entry(&entry.key, &entry.value))
+    }
+
+    fn validate_login_packet(&self, login_packet: &LoginPacket) -> Result<()> {
+        if login_packet.data().len() > self.max_login_packet_size {
+            return Err(Error::ExceededLoginPacketSize);
+        }
+        Ok(())
+    }
+
+    fn validate_entry_count(&self, count: usize) -> Result<()> {
+        if count > self.max_entries {
+            return Err(Error::TooManyEntries);
+        }
+        Ok(())
+    }
+
+    fn validate_keys<V>(&self, map: &BTreeMap<Vec<u8>, V>) -> Result<()> {
+        self.validate_entry_count(map.len())?;
+        map.keys().try_for_each(|prefix| self.validate_key(prefix))
+    }
+
+    fn validate_entry(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.validate_key(key)?;
+        if value.len() > self.max_value_len {
+            return Err(Error::ExceededValueLength);
+        }
+        Ok(())
+    }
+
+    fn validate_key(&self, key: &[u8]) -> Result<()> {
+        if key.len() > self.max_key_len {
+            return Err(Error::ExceededKeyLength);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ADataAddress, ADataAppendOperation, ClientFullId, MDataAddress, MDataUnseqEntryActions,
+        MessageId, PubImmutableData, UnseqMutableData,
+    };
+    use unwrap::unwrap;
+
+    fn request(request: Request) -> Message {
+        Message::Request {
+            request,
+            message_id: MessageId::new(),
+            signature: None,
+            capability: None,
+        }
+    }
+
+    fn error(bytes: &[u8], limits: &MessageLimits) -> Error {
+        match Message::from_bytes(bytes, limits) {
+            Ok(_) => panic!("Unexpected success"),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let limits = MessageLimits::default();
+        let message = request(Request::PutIData(
+            PubImmutableData::new(vec![1, 2, 3]).into(),
+        ));
+        assert!(unwrap!(Message::from_bytes(&message.to_bytes(), &limits)) == message);
+
+        assert!(match error(&[0xff; 16], &limits) {
+            Error::FailedToParse(_) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn message_size() {
+        let message = request(Request::PutIData(
+            PubImmutableData::new(vec![0; 1000]).into(),
+        ));
+        let bytes = message.to_bytes();
+        let limits = MessageLimits {
+            max_message_size: 500,
+            ..Default::default()
+        };
+        assert_eq!(error(&bytes, &limits), Error::ExceededMessageSize);
+
+        // A length prefix claiming far more data than is present fails cleanly, without
+        // allocating for the claimed length.
+        let value = vec![0xab; 100];
+        let mut bytes = request(Request::PutIData(
+            PubImmutableData::new(value.clone()).into(),
+        ))
+        .to_bytes();
+        let mut prefix = (value.len() as u64).to_le_bytes().to_vec();
+        prefix.extend_from_slice(&value);
+        let position = unwrap!(bytes
+            .windows(prefix.len())
+            .position(|window| window == &prefix[..]));
+        bytes[position..position + 8].copy_from_slice(&u64::max_value().to_le_bytes());
+        assert!(match error(&bytes, &MessageLimits::default()) {
+            Error::FailedToParse(_) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn entries() {
+        let limits = MessageLimits {
+            max_entries: 2,
+            max_key_len: 4,
+            max_value_len: 8,
+            ..Default::default()
+        };
+        let address = MDataAddress::Unseq {
+            name: rand::random(),
+            tag: 100,
+        };
+        let mutate = |actions: MDataUnseqEntryActions| {
+            request(Request::MutateMDataEntries {
+                address,
+                actions: actions.into(),
+            })
+            .to_bytes()
+        };
+
+        let actions = MDataUnseqEntryActions::new()
+            .ins(vec![1], vec![1; 8])
+            .del(vec![2]);
+        let _ = unwrap!(Message::from_bytes(&mutate(actions), &limits));
+        let actions = MDataUnseqEntryActions::new()
+            .ins(vec![1], vec![1])
+            .ins(vec![2], vec![2])
+            .ins(vec![3], vec![3]);
+        assert_eq!(error(&mutate(actions), &limits), Error::TooManyEntries);
+        let actions = MDataUnseqEntryActions::new().del(vec![1; 5]);
+        assert_eq!(error(&mutate(actions), &limits), Error::ExceededKeyLength);
+        let actions = MDataUnseqEntryActions::new().update(vec![1], vec![1; 9]);
+        assert_eq!(error(&mutate(actions), &limits), Error::ExceededValueLength);
+
+        let owner = *ClientFullId::new_ed25519(&mut rand::thread_rng())
+            .public_id()
+            .public_key();
+        let data = unwrap!(UnseqMutableData::new_with_data(
+            rand::random(),
+            100,
+            (0..3).map(|i| (vec![i], vec![i])).collect(),
+            Default::default(),
+            owner,
+        ));
+        assert_eq!(
+            error(&request(Request::PutMData(data.into())).to_bytes(), &limits),
+            Error::TooManyEntries
+        );
+
+        let append = ADataAppendOperation {
+            address: ADataAddress::PubUnseq {
+                name: rand::random(),
+                tag: 100,
+            },
+            values: vec![ADataEntry {
+                key: vec![1; 5],
+                value: vec![1],
+            }],
+        };
+        assert_eq!(
+            error(&request(Request::AppendUnseq(append)).to_bytes(), &limits),
+            Error::ExceededKeyLength
+        );
+    }
+
+    #[test]
+    fn login_packet() {
+        let limits = MessageLimits {
+            max_login_packet_size: 16,
+            ..Default::default()
+        };
+        let client = ClientFullId::new_ed25519(&mut rand::thread_rng());
+        let login_packet = |size| {
+            let data = vec![0; size];
+            let signature = client.sign(&data);
+            let login_packet = unwrap!(LoginPacket::new(
+                rand::random(),
+                *client.public_id().public_key(),
+                data,
+                signature
+            ));
+            request(Request::CreateLoginPacket(login_packet)).to_bytes()
+        };
+
+        let _ = unwrap!(Message::from_bytes(&login_packet(16), &limits));
+        assert_eq!(
+            error(&login_packet(17), &limits),
+            Error::ExceededLoginPacketSize
+        );
+    }
+}
@@ -0,0 +1,450 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Diffs and three-way merges of MutableData, for syncing client-side copies with the network.
+//!
+//! Entry diffs are returned as entry actions which can be sent unchanged in a
+//! `Request::MutateMDataEntries` to the data they were computed against.
+
+use crate::{
+    Group, GroupName, MDataPermissionSet, MDataSeqEntryAction, MDataSeqEntryActions, MDataSeqValue,
+    MDataUnseqEntryAction, MDataUnseqEntryActions, MDataUser, PublicKey, SeqMutableData,
+    UnseqMutableData,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Outcome of a three-way merge of MutableData entries.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Merge<A> {
+    /// Actions applying the changes of "ours" to "theirs", to be sent to the data "theirs" was
+    /// taken from.
+    pub actions: A,
+    /// Keys changed differently in "ours" and "theirs", which are left out of `actions`.
+    pub conflicts: BTreeSet<Vec<u8>>,
+}
+
+/// Differences between the shells of two copies of a MutableData.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShellDiff {
+    /// Users whose permissions were added or changed, with their new permissions.
+    pub set_permissions: BTreeMap<MDataUser, MDataPermissionSet>,
+    /// Users whose permissions were deleted.
+    pub deleted_permissions: BTreeSet<MDataUser>,
+    /// Entry key prefixes and users whose entry permissions were added or changed, with their new
+    /// permissions.
+    pub set_entry_permissions: BTreeMap<(Vec<u8>, MDataUser), MDataPermissionSet>,
+    /// Entry key prefixes and users whose entry permissions were deleted.
+    pub deleted_entry_permissions: BTreeSet<(Vec<u8>, MDataUser)>,
+    /// Members added to each group.
+    pub added_group_members: BTreeMap<GroupName, BTreeSet<PublicKey>>,
+    /// Members removed from each group.
+    pub removed_group_members: BTreeMap<GroupName, BTreeSet<PublicKey>>,
+    /// The new owner, if it changed.
+    pub owner: Option<PublicKey>,
+    /// The new version of the shell, if it changed.
+    pub version: Option<u64>,
+}
+
+impl ShellDiff {
+    /// Returns true if the shells are the same.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+macro_rules! impl_shell_diff {
+    ($flavour:ident) => {
+        impl $flavour {
+            /// Returns the changes to permissions, entry permissions, group members, owner and
+            /// version from `old` to `new`.
+            pub fn shell_diff(old: &Self, new: &Self) -> ShellDiff {
+                let old_permissions = old.permissions();
+                let new_permissions = new.permissions();
+                let old_entry_permissions = flatten(old.entry_permissions());
+                let new_entry_permissions = flatten(new.entry_permissions());
+                ShellDiff {
+                    set_permissions: new_permissions
+                        .iter()
+                        .filter(|(user, permissions)| {
+                            old_permissions.get(user) != Some(permissions)
+                        })
+                        .map(|(user, permissions)| (user.clone(), permissions.clone()))
+                        .collect(),
+                    deleted_permissions: old_permissions
+                        .keys()
+                        .filter(|user| !new_permissions.contains_key(user))
+                        .cloned()
+                        .collect(),
+                    set_entry_permissions: new_entry_permissions
+                        .iter()
+                        .filter(|(key, permissions)| {
+                            old_entry_permissions.get(key) != Some(permissions)
+                        })
+                        .map(|(key, permissions)| (key.clone(), (*permissions).clone()))
+                        .collect(),
+                    deleted_entry_permissions: old_entry_permissions
+                        .keys()
+                        .filter(|key| !new_entry_permissions.contains_key(key))
+                        .cloned()
+                        .collect(),
+                    added_group_members: member_changes(new.groups(), old.groups()),
+                    removed_group_members: member_changes(old.groups(), new.groups()),
+                    owner: Some(*new.owner()).filter(|owner| owner != old.owner()),
+                    version: Some(new.version()).filter(|version| *version != old.version()),
+                }
+            }
+        }
+    };
+}
+
+impl_shell_diff!(SeqMutableData);
+impl_shell_diff!(UnseqMutableData);
+
+impl SeqMutableData {
+    /// Returns the actions turning the entries of `old` into those of `new`.
+    ///
+    /// Updates and deletions succeed the versions in `old`. Insertions keep the version in `new`,
+    /// unless they replace a tombstone in `old`, which they succeed.
+    pub fn diff(old: &Self, new: &Self) -> MDataSeqEntryActions {
+        keys(old.entries(), new.entries())
+            .into_iter()
+            .filter_map(|key| {
+                seq_action(old, key, new.get(key)).map(|action| (key.clone(), action))
+            })
+            .collect::<BTreeMap<_, _>>()
+            .into()
+    }
+
+    /// Merges the changes from `base` to `ours` into `theirs`.
+    ///
+    /// Keys changed in `ours` but not in `theirs` are turned into actions against `theirs`. Keys
+    /// changed in both to different values are reported as conflicts.
+    pub fn merge(base: &Self, ours: &Self, theirs: &Self) -> Merge<MDataSeqEntryActions> {
+        let value = |data: &Self, key: &[u8]| data.get(key).map(|value| value.data.clone());
+        let mut actions = BTreeMap::new();
+        let mut conflicts = BTreeSet::new();
+        for key in keys(base.entries(), ours.entries()) {
+            let ours_value = value(ours, key);
+            let theirs_value = value(theirs, key);
+            if ours_value == value(base, key) || ours_value == theirs_value {
+                continue;
+            }
+            if theirs_value == value(base, key) {
+                if let Some(action) = seq_action(theirs, key, ours.get(key)) {
+                    let _ = actions.insert(key.clone(), action);
+                }
+            } else {
+                let _ = conflicts.insert(key.clone());
+            }
+        }
+        Merge {
+            actions: actions.into(),
+            conflicts,
+        }
+    }
+}
+
+impl UnseqMutableData {
+    /// Returns the actions turning the entries of `old` into those of `new`.
+    pub fn diff(old: &Self, new: &Self) -> MDataUnseqEntryActions {
+        keys(old.entries(), new.entries())
+            .into_iter()
+            .filter_map(|key| {
+                unseq_action(old.get(key), new.get(key)).map(|action| (key.clone(), action))
+            })
+            .collect::<BTreeMap<_, _>>()
+            .into()
+    }
+
+    /// Merges the changes from `base` to `ours` into `theirs`.
+    ///
+    /// Keys changed in `ours` but not in `theirs` are turned into actions against `theirs`. Keys
+    /// changed in both to different values are reported as conflicts.
+    pub fn merge(base: &Self, ours: &Self, theirs: &Self) -> Merge<MDataUnseqEntryActions> {
+        let mut actions = BTreeMap::new();
+        let mut conflicts = BTreeSet::new();
+        for key in keys(base.entries(), ours.entries()) {
+            let ours_value = ours.get(key);
+            let theirs_value = theirs.get(key);
+            if ours_value == base.get(key) || ours_value == theirs_value {
+                continue;
+            }
+            if theirs_value == base.get(key) {
+                if let Some(action) = unseq_action(theirs_value, ours_value) {
+                    let _ = actions.insert(key.clone(), action);
+                }
+            } else {
+                let _ = conflicts.insert(key.clone());
+            }
+        }
+        Merge {
+            actions: actions.into(),
+            conflicts,
+        }
+    }
+}
+
+// Entry permissions keyed by prefix and user.
+fn flatten(
+    entry_permissions: &BTreeMap<Vec<u8>, BTreeMap<MDataUser, MDataPermissionSet>>,
+) -> BTreeMap<(Vec<u8>, MDataUser), &MDataPermissionSet> {
+    entry_permissions
+        .iter()
+        .flat_map(|(prefix, permissions)| {
+            permissions
+                .iter()
+                .map(move |(user, permissions)| ((prefix.clone(), user.clone()), permissions))
+        })
+        .collect()
+}
+
+// Members of each group in `first` which aren't members of the same group in `second`.
+fn member_changes(
+    first: &BTreeMap<GroupName, Group>,
+    second: &BTreeMap<GroupName, Group>,
+) -> BTreeMap<GroupName, BTreeSet<PublicKey>> {
+    first
+        .iter()
+        .filter_map(|(name, group)| {
+            let members: BTreeSet<_> = group
+                .members()
+                .iter()
+                .filter(|member| {
+                    !second
+                        .get(name)
+                        .into_iter()
+                        .any(|group| group.is_member(member))
+                })
+                .cloned()
+                .collect();
+            if members.is_empty() {
+                None
+            } else {
+                Some((name.clone(), members))
+            }
+        })
+        .collect()
+}
+
+// Union of the keys of both maps.
+fn keys<'a, V>(
+    first: &'a BTreeMap<Vec<u8>, V>,
+    second: &'a BTreeMap<Vec<u8>, V>,
+) -> BTreeSet<&'a Vec<u8>> {
+    first.keys().chain(second.keys()).collect()
+}
+
+// Action changing the entry under `key` in `data` to `new`, with the version `data` expects.
+fn seq_action(
+    data: &SeqMutableData,
+    key: &[u8],
+    new: Option<&MDataSeqValue>,
+) -> Option<MDataSeqEntryAction> {
+    match (data.get(key), new) {
+        (None, Some(new)) => {
+            let version = match data.tombstones().get(key) {
+                Some(deleted_version) => deleted_version + 1,
+                None => new.version,
+            };
+            Some(MDataSeqEntryAction::Ins(MDataSeqValue {
+                data: new.data.clone(),
+                version,
+            }))
+        }
+        (Some(old), Some(new)) if old.data != new.data => {
+            Some(MDataSeqEntryAction::Update(MDataSeqValue {
+                data: new.data.clone(),
+                version: old.version + 1,
+            }))
+        }
+        (Some(old), None) => Some(MDataSeqEntryAction::Del(old.version + 1)),
+        _ => None,
+    }
+}
+
+fn unseq_action(old: Option<&Vec<u8>>, new: Option<&Vec<u8>>) -> Option<MDataUnseqEntryAction> {
+    match (old, new) {
+        (None, Some(new)) => Some(MDataUnseqEntryAction::Ins(new.clone())),
+        (Some(old), Some(new)) if old != new => Some(MDataUnseqEntryAction::Update(new.clone())),
+        (Some(_), None) => Some(MDataUnseqEntryAction::Del),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MDataAction, XorName};
+    use threshold_crypto::SecretKey;
+    use unwrap::unwrap;
+
+    fn seq_data(owner: PublicKey) -> SeqMutableData {
+        let mut data = SeqMutableData::new(XorName([1; 32]), 10_000, owner);
+        let actions = MDataSeqEntryActions::new()
+            .ins(b"a".to_vec(), b"a".to_vec(), 0)
+            .ins(b"b".to_vec(), b"b".to_vec(), 0)
+            .ins(b"c".to_vec(), b"c".to_vec(), 0);
+        unwrap!(data.mutate_entries(actions, owner));
+        data
+    }
+
+    #[test]
+    fn seq_diff() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let old = seq_data(owner);
+        let mut new = old.clone();
+        let actions = MDataSeqEntryActions::new()
+            .update(b"a".to_vec(), b"A".to_vec(), 1)
+            .del(b"b".to_vec(), 1)
+            .ins(b"d".to_vec(), b"d".to_vec(), 0);
+        unwrap!(new.mutate_entries(actions.clone(), owner));
+
+        let diff = SeqMutableData::diff(&old, &new);
+        assert_eq!(diff, actions);
+        assert!(SeqMutableData::diff(&new, &new).actions().is_empty());
+
+        // the diff applies to `old`, and reinserting over a tombstone succeeds its version
+        let mut synced = old.clone();
+        unwrap!(synced.mutate_entries(diff, owner));
+        assert_eq!(synced.entries(), new.entries());
+        let mut reinserted = new.clone();
+        unwrap!(reinserted.mutate_entries(
+            MDataSeqEntryActions::new().ins(b"b".to_vec(), b"B".to_vec(), 2),
+            owner
+        ));
+        unwrap!(new.mutate_entries(SeqMutableData::diff(&new, &reinserted), owner));
+        assert_eq!(new.entries(), reinserted.entries());
+    }
+
+    #[test]
+    fn seq_merge() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let base = seq_data(owner);
+        let mut ours = base.clone();
+        unwrap!(ours.mutate_entries(
+            MDataSeqEntryActions::new()
+                .update(b"a".to_vec(), b"ours".to_vec(), 1)
+                .update(b"b".to_vec(), b"ours".to_vec(), 1)
+                .update(b"c".to_vec(), b"same".to_vec(), 1),
+            owner
+        ));
+        let mut theirs = base.clone();
+        unwrap!(theirs.mutate_entries(
+            MDataSeqEntryActions::new()
+                .update(b"b".to_vec(), b"theirs".to_vec(), 1)
+                .update(b"c".to_vec(), b"same".to_vec(), 1),
+            owner
+        ));
+        unwrap!(theirs.mutate_entries(
+            MDataSeqEntryActions::new().update(b"a".to_vec(), b"a".to_vec(), 1),
+            owner
+        ));
+        unwrap!(theirs.mutate_entries(
+            MDataSeqEntryActions::new().update(b"a".to_vec(), b"a".to_vec(), 2),
+            owner
+        ));
+
+        let merge = SeqMutableData::merge(&base, &ours, &theirs);
+        assert_eq!(
+            merge.actions,
+            MDataSeqEntryActions::new().update(b"a".to_vec(), b"ours".to_vec(), 3)
+        );
+        assert_eq!(merge.conflicts, vec![b"b".to_vec()].into_iter().collect());
+        unwrap!(theirs.mutate_entries(merge.actions, owner));
+    }
+
+    #[test]
+    fn unseq_diff_and_merge() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let mut base = UnseqMutableData::new(XorName([1; 32]), 10_000, owner);
+        unwrap!(base.mutate_entries(
+            MDataUnseqEntryActions::new()
+                .ins(b"a".to_vec(), b"a".to_vec())
+                .ins(b"b".to_vec(), b"b".to_vec()),
+            owner
+        ));
+        let mut ours = base.clone();
+        let actions = MDataUnseqEntryActions::new()
+            .del(b"a".to_vec())
+            .ins(b"c".to_vec(), b"c".to_vec());
+        unwrap!(ours.mutate_entries(actions.clone(), owner));
+        assert_eq!(UnseqMutableData::diff(&base, &ours), actions);
+
+        let mut theirs = base.clone();
+        unwrap!(theirs.mutate_entries(
+            MDataUnseqEntryActions::new()
+                .update(b"b".to_vec(), b"B".to_vec())
+                .ins(b"c".to_vec(), b"C".to_vec()),
+            owner
+        ));
+        let merge = UnseqMutableData::merge(&base, &ours, &theirs);
+        assert_eq!(
+            merge.actions,
+            MDataUnseqEntryActions::new().del(b"a".to_vec())
+        );
+        assert_eq!(merge.conflicts, vec![b"c".to_vec()].into_iter().collect());
+    }
+
+    #[test]
+    fn shell_diff() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let old = seq_data(owner);
+        assert!(SeqMutableData::shell_diff(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        let permissions = MDataPermissionSet::new().allow(MDataAction::Read);
+        unwrap!(new.set_user_permissions(user, permissions.clone(), 1));
+        unwrap!(new.change_owner(user, 2));
+        let diff = SeqMutableData::shell_diff(&old, &new);
+        assert_eq!(
+            diff.set_permissions,
+            vec![(MDataUser::Key(user), permissions.clone())]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(diff.owner, Some(user));
+        assert_eq!(diff.version, Some(2));
+
+        let diff = SeqMutableData::shell_diff(&new, &old);
+        assert_eq!(
+            diff.deleted_permissions,
+            vec![MDataUser::Key(user)].into_iter().collect()
+        );
+        assert_eq!(diff.owner, Some(owner));
+        assert_eq!(diff.version, Some(0));
+
+        // entry permissions and group members are part of the shell too
+        let mut new = old.clone();
+        unwrap!(new.set_entry_permissions(b"a".to_vec(), user, permissions.clone(), 1));
+        unwrap!(new.add_group_member("readers".to_string(), user, 1));
+        let diff = SeqMutableData::shell_diff(&old, &new);
+        let entry_user = (b"a".to_vec(), MDataUser::Key(user));
+        assert_eq!(
+            diff.set_entry_permissions,
+            vec![(entry_user.clone(), permissions)]
+                .into_iter()
+                .collect()
+        );
+        let members = vec![("readers".to_string(), vec![user].into_iter().collect())];
+        assert_eq!(
+            diff.added_group_members,
+            members.clone().into_iter().collect()
+        );
+        assert!(diff.removed_group_members.is_empty());
+
+        let diff = SeqMutableData::shell_diff(&new, &old);
+        assert_eq!(
+            diff.deleted_entry_permissions,
+            vec![entry_user].into_iter().collect()
+        );
+        assert_eq!(diff.removed_group_members, members.into_iter().collect());
+        assert_eq!(diff.version, Some(0));
+    }
+}
@@ -0,0 +1,2555 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! In-memory reference vault.
+//!
+//! `MockVault` executes every `Request` variant against locally stored data, using the same
+//! validation rules as the data types themselves. It is intended for testing client code offline;
+//! it does not attempt to model routing, replication or any of the network's timing behaviour.
+//!
+//! Permission checks are performed using the public key of the requester, so apps need to be
+//! granted permissions explicitly. Operations reserved for the owner of the data (storing,
+//! deleting, changing ownership) are performed on behalf of the app's owner instead.
+//!
+//! A `Capability` attached to the request is accepted in place of a permission entry when reading
+//! data, mutating MutableData entries, appending to AppendOnlyData and deleting unpublished
+//! ImmutableData. Capabilities are checked against the network ID of the vault, the system clock
+//! and the grants revoked by the owner of the data.
+//!
+//! Entry permissions of MutableData are checked for every key read or mutated. Requests listing
+//! or fetching the whole data only return the entries the requester is allowed to read, and the
+//! paginated listing requests page through those entries only.
+//!
+//! Notifications for subscribed data are queued and can be collected with
+//! `MockVault::take_notifications`.
+
+use crate::{
+    AData, ADataAction, ADataAddress, ADataAppendOperation, ADataIndex, ADataOwner,
+    ADataPermissions, ADataPubPermissions, ADataUnpubPermissions, AppPermissions, AppendOnlyData,
+    Capability, Coins, DataAddress, Error, GenericAction, GroupName, IData, IDataAddress,
+    LoginPacket, MData, MDataAction, MDataAddress, MDataEntries, MDataEntryActions, MDataKeyFilter,
+    MDataPermissionSet, MDataUser, MDataValue, Message, NetworkId, Notification, PublicId,
+    PublicKey, Request, Response, Result, SeqAppendOnly, Signature, SigningContext, Transaction,
+    TransactionId, UnseqAppendOnly, XorName,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    mem,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// In-memory store which executes `Request`s and produces the corresponding `Response`s.
+#[derive(Default)]
+pub struct MockVault {
+    idata: HashMap<IDataAddress, IData>,
+    mdata: HashMap<MDataAddress, MData>,
+    adata: HashMap<ADataAddress, AData>,
+    balances: HashMap<XorName, Coins>,
+    login_packets: HashMap<XorName, LoginPacket>,
+    auth_keys: HashMap<XorName, (BTreeMap<PublicKey, AppPermissions>, u64)>,
+    subscriptions: HashMap<DataAddress, BTreeSet<PublicId>>,
+    notifications: Vec<(PublicId, Notification)>,
+    revoked_grants: HashMap<DataAddress, BTreeSet<u64>>,
+    // Network requests and capabilities must have been signed for.
+    network_id: NetworkId,
+    // Capability attached to the request being executed.
+    capability: Option<Capability>,
+}
+
+impl MockVault {
+    /// Constructs an empty vault, accepting requests and capabilities signed for
+    /// `NetworkId::default()`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Constructs an empty vault, accepting requests and capabilities signed for the network with
+    /// the given ID.
+    pub fn with_network_id(network_id: NetworkId) -> Self {
+        Self {
+            network_id,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a coin balance for `owner` without requiring a source balance.
+    ///
+    /// This is the only way of introducing coins into the vault, and is intended for setting up
+    /// test fixtures. Returns `Err(BalanceExists)` if `owner` already has a balance.
+    pub fn create_balance(&mut self, owner: PublicKey, amount: Coins) -> Result<()> {
+        let name = XorName::from(owner);
+        if self.balances.contains_key(&name) {
+            return Err(Error::BalanceExists);
+        }
+        let _ = self.balances.insert(name, amount);
+        Ok(())
+    }
+
+    /// Returns the coin balance of `owner`, if it exists.
+    pub fn balance(&self, owner: PublicKey) -> Option<Coins> {
+        self.balances.get(&XorName::from(owner)).cloned()
+    }
+
+    /// Returns the notifications generated since the last call, along with their recipients.
+    pub fn take_notifications(&mut self) -> Vec<(PublicId, Message)> {
+        mem::replace(&mut self.notifications, Vec::new())
+            .into_iter()
+            .map(|(recipient, notification)| (recipient, Message::Notification { notification }))
+            .collect()
+    }
+
+    /// Executes the request contained in `message` on behalf of `requester`.
+    ///
+    /// Returns `Err(InvalidOperation)` if `message` isn't a `Message::Request`. Otherwise, returns
+    /// a `Message::Response` with the same message ID, containing either the result of the request
+    /// or the error which caused it to fail.
+    pub fn process_request(&mut self, message: Message, requester: &PublicId) -> Result<Message> {
+        let verification = SigningContext::new(self.network_id).verify_request(&message, requester);
+        let (request, message_id, capability) = match message {
+            Message::Request {
+                request,
+                message_id,
+                capability,
+                ..
+            } => (request, message_id, capability),
+            Message::Response { .. } | Message::Notification { .. } => {
+                return Err(Error::InvalidOperation)
+            }
+        };
+
+        let response = match verification.and_then(|()| self.authorise(requester)) {
+            Ok(()) => {
+                self.capability = capability;
+                let response = self.execute(request, requester);
+                self.capability = None;
+                response
+            }
+            Err(error) => request.error_response(error),
+        };
+
+        Ok(Message::Response {
+            response,
+            message_id,
+        })
+    }
+
+    fn authorise(&self, requester: &PublicId) -> Result<()> {
+        // Apps can only act on behalf of their owner once they've been authorised by it.
+        if let PublicId::App(app_id) = requester {
+            let is_authorised = self
+                .auth_keys
+                .get(app_id.owner_name())
+                .and_then(|(keys, _)| keys.get(app_id.public_key()))
+                .is_some();
+            if !is_authorised {
+                return Err(Error::AccessDenied);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, request: Request, requester: &PublicId) -> Response {
+        use Request::*;
+
+        match request {
+            // IData
+            PutIData(data) => Response::Mutation(self.put_idata(data, requester)),
+            GetIData(address) => Response::GetIData(self.get_idata(address, requester)),
+            DeleteUnpubIData(address) => {
+                Response::Mutation(self.delete_unpub_idata(address, requester))
+            }
+            // MData
+            PutMData(data) => Response::Mutation(self.put_mdata(data, requester)),
+            GetMData(address) => {
+                Response::GetMData(self.readable_mdata_entries(address, requester))
+            }
+            GetMDataValue { address, key } => {
+                Response::GetMDataValue(self.get_mdata_value(address, &key, requester))
+            }
+            DeleteMData(address) => Response::Mutation(self.delete_mdata(address, requester)),
+            GetMDataShell(address) => Response::GetMDataShell(
+                self.readable_mdata(address, requester)
+                    .map(|data| data.shell()),
+            ),
+            GetMDataVersion(address) => Response::GetMDataVersion(
+                self.readable_mdata(address, requester)
+                    .map(|data| data.version()),
+            ),
+            ListMDataEntries(address) => Response::ListMDataEntries(
+                self.readable_mdata_entries(address, requester)
+                    .map(|data| match data {
+                        MData::Seq(data) => data.entries().clone().into(),
+                        MData::Unseq(data) => data.entries().clone().into(),
+                    }),
+            ),
+            ListMDataKeys(address) => Response::ListMDataKeys(
+                self.readable_mdata_entries(address, requester)
+                    .map(|data| data.keys()),
+            ),
+            ListMDataValues(address) => Response::ListMDataValues(
+                self.readable_mdata_entries(address, requester)
+                    .map(|data| match data {
+                        MData::Seq(data) => data.values().into(),
+                        MData::Unseq(data) => data.values().into(),
+                    }),
+            ),
+            SetMDataUserPermissions {
+                address,
+                user,
+                permissions,
+                version,
+            } => Response::Mutation(self.set_mdata_user_permissions(
+                address,
+                user,
+                permissions,
+                version,
+                requester,
+            )),
+            DelMDataUserPermissions {
+                address,
+                user,
+                version,
+            } => Response::Mutation(
+                self.del_mdata_user_permissions(address, user, version, requester),
+            ),
+            ListMDataPermissions(address) => Response::ListMDataPermissions(
+                self.readable_mdata(address, requester)
+                    .map(|data| data.permissions()),
+            ),
+            ListMDataUserPermissions { address, user } => Response::ListMDataUserPermissions(
+                self.readable_mdata(address, requester)
+                    .and_then(|data| data.user_permissions(user).cloned()),
+            ),
+            MutateMDataEntries { address, actions } => {
+                Response::Mutation(self.mutate_mdata_entries(address, actions, requester))
+            }
+            // AData
+            PutAData(data) => Response::Mutation(self.put_adata(data, requester)),
+            GetAData(address) => {
+                Response::GetAData(self.readable_adata(address, requester).cloned())
+            }
+            GetADataShell {
+                address,
+                data_index,
+            } => Response::GetADataShell(
+                self.readable_adata(address, requester)
+                    .and_then(|data| data.shell(data_index)),
+            ),
+            DeleteAData(address) => Response::Mutation(self.delete_adata(address, requester)),
+            GetADataRange { address, range } => Response::GetADataRange(
+                self.readable_adata(address, requester)
+                    .and_then(|data| data.in_range(range.0, range.1).ok_or(Error::NoSuchEntry)),
+            ),
+            GetADataValue { address, key } => Response::GetADataValue(
+                self.readable_adata(address, requester)
+                    .and_then(|data| data.get(&key).cloned().ok_or(Error::NoSuchEntry)),
+            ),
+            GetADataIndices(address) => Response::GetADataIndices(
+                self.readable_adata(address, requester)
+                    .and_then(|data| data.indices()),
+            ),
+            GetADataLastEntry(address) => Response::GetADataLastEntry(
+                self.readable_adata(address, requester)
+                    .and_then(|data| data.last_entry().cloned().ok_or(Error::NoSuchEntry)),
+            ),
+            GetADataPermissions {
+                address,
+                permissions_index,
+            } => Response::GetADataPermissions(self.get_adata_permissions(
+                address,
+                permissions_index,
+                requester,
+            )),
+            GetPubADataUserPermissions {
+                address,
+                permissions_index,
+                user,
+            } => Response::GetPubADataUserPermissions(
+                self.readable_adata(address, requester)
+                    .and_then(|data| data.pub_user_permissions(user, permissions_index)),
+            ),
+            GetUnpubADataUserPermissions {
+                address,
+                permissions_index,
+                public_key,
+            } => Response::GetUnpubADataUserPermissions(
+                self.readable_adata(address, requester)
+                    .and_then(|data| data.unpub_user_permissions(public_key, permissions_index)),
+            ),
+            GetADataOwners {
+                address,
+                owners_index,
+            } => {
+                Response::GetADataOwners(self.readable_adata(address, requester).and_then(|data| {
+                    data.owner(owners_index)
+                        .cloned()
+                        .ok_or(Error::InvalidOwners)
+                }))
+            }
+            AddPubADataPermissions {
+                address,
+                permissions,
+                permissions_index,
+            } => Response::Mutation(self.add_pub_adata_permissions(
+                address,
+                permissions,
+                permissions_index,
+                requester,
+            )),
+            AddUnpubADataPermissions {
+                address,
+                permissions,
+                permissions_index,
+            } => Response::Mutation(self.add_unpub_adata_permissions(
+                address,
+                permissions,
+                permissions_index,
+                requester,
+            )),
+            SetADataOwner {
+                address,
+                owner,
+                owners_index,
+            } => Response::Mutation(self.set_adata_owner(address, owner, owners_index, requester)),
+            AppendSeq { append, index } => {
+                Response::Mutation(self.append_seq(append, index, requester))
+            }
+            AppendUnseq(append) => Response::Mutation(self.append_unseq(append, requester)),
+            // Coins
+            TransferCoins {
+                destination,
+                amount,
+                transaction_id,
+            } => Response::Transaction(self.transfer_coins(
+                destination,
+                amount,
+                transaction_id,
+                requester,
+            )),
+            GetBalance => Response::GetBalance(
+                self.balances
+                    .get(requester.name())
+                    .cloned()
+                    .ok_or(Error::NoSuchBalance),
+            ),
+            CreateBalance {
+                new_balance_owner,
+                amount,
+                transaction_id,
+            } => Response::Transaction(self.transfer_to_new_balance(
+                new_balance_owner,
+                amount,
+                transaction_id,
+                requester,
+            )),
+            // Login Packet
+            CreateLoginPacket(login_packet) => {
+                Response::Mutation(self.create_login_packet(login_packet, requester))
+            }
+            CreateLoginPacketFor {
+                new_owner,
+                amount,
+                transaction_id,
+                new_login_packet,
+            } => Response::Mutation(self.create_login_packet_for(
+                new_owner,
+                amount,
+                transaction_id,
+                new_login_packet,
+                requester,
+            )),
+            UpdateLoginPacket(login_packet) => {
+                Response::Mutation(self.update_login_packet(login_packet, requester))
+            }
+            GetLoginPacket(name) => {
+                Response::GetLoginPacket(self.get_login_packet(&name, requester))
+            }
+            // Client (Owner) to SrcElders
+            ListAuthKeysAndVersion => Response::ListAuthKeysAndVersion(
+                client_name(requester)
+                    .map(|name| self.auth_keys.get(name).cloned().unwrap_or_default()),
+            ),
+            InsAuthKey {
+                key,
+                version,
+                permissions,
+            } => Response::Mutation(self.ins_auth_key(key, version, permissions, requester)),
+            DelAuthKey { key, version } => {
+                Response::Mutation(self.del_auth_key(key, version, requester))
+            }
+            // Subscriptions
+            SubscribeMData(address) => Response::Mutation(self.subscribe_mdata(address, requester)),
+            UnsubscribeMData(address) => {
+                Response::Mutation(self.unsubscribe(address.into(), requester))
+            }
+            SubscribeAData(address) => Response::Mutation(self.subscribe_adata(address, requester)),
+            UnsubscribeAData(address) => {
+                Response::Mutation(self.unsubscribe(address.into(), requester))
+            }
+            // Permission Groups
+            AddMDataGroupMember {
+                address,
+                group,
+                member,
+                version,
+            } => Response::Mutation(
+                self.add_mdata_group_member(address, group, member, version, requester),
+            ),
+            RemoveMDataGroupMember {
+                address,
+                group,
+                member,
+                version,
+            } => Response::Mutation(
+                self.remove_mdata_group_member(address, &group, &member, version, requester),
+            ),
+            AddADataGroupMember {
+                address,
+                group,
+                member,
+                version,
+            } => Response::Mutation(
+                self.add_adata_group_member(address, group, member, version, requester),
+            ),
+            RemoveADataGroupMember {
+                address,
+                group,
+                member,
+                version,
+            } => Response::Mutation(
+                self.remove_adata_group_member(address, &group, &member, version, requester),
+            ),
+            // Capabilities
+            RevokeCapability { address, nonce } => {
+                Response::Mutation(self.revoke_capability(address, nonce, requester))
+            }
+            // Entry Permissions
+            SetMDataEntryPermissions {
+                address,
+                prefix,
+                user,
+                permissions,
+                version,
+            } => Response::Mutation(self.set_mdata_entry_permissions(
+                address,
+                prefix,
+                user,
+                permissions,
+                version,
+                requester,
+            )),
+            DelMDataEntryPermissions {
+                address,
+                prefix,
+                user,
+                version,
+            } => Response::Mutation(
+                self.del_mdata_entry_permissions(address, &prefix, user, version, requester),
+            ),
+            // Tombstones
+            PurgeMDataTombstones(address) => {
+                Response::Mutation(self.purge_mdata_tombstones(address, requester))
+            }
+            // Paginated Listing
+            ListMDataEntriesPage {
+                address,
+                filter,
+                limit,
+                cursor,
+            } => Response::ListMDataEntriesPage(self.mdata_entries_page(
+                address,
+                &filter,
+                limit,
+                cursor.as_deref(),
+                requester,
+            )),
+            ListMDataKeysPage {
+                address,
+                filter,
+                limit,
+                cursor,
+            } => Response::ListMDataKeysPage(
+                self.mdata_entries_page(address, &filter, limit, cursor.as_deref(), requester)
+                    .map(|(entries, next)| {
+                        let keys = match entries {
+                            MDataEntries::Seq(entries) => entries.keys().cloned().collect(),
+                            MDataEntries::Unseq(entries) => entries.keys().cloned().collect(),
+                        };
+                        (keys, next)
+                    }),
+            ),
+            ListMDataValuesPage {
+                address,
+                filter,
+                limit,
+                cursor,
+            } => Response::ListMDataValuesPage(
+                self.mdata_entries_page(address, &filter, limit, cursor.as_deref(), requester)
+                    .map(|(entries, next)| {
+                        let values = match entries {
+                            MDataEntries::Seq(entries) => {
+                                entries.into_values().collect::<Vec<_>>().into()
+                            }
+                            MDataEntries::Unseq(entries) => {
+                                entries.into_values().collect::<Vec<_>>().into()
+                            }
+                        };
+                        (values, next)
+                    }),
+            ),
+        }
+    }
+
+    //
+    // ===== Immutable Data =====
+    //
+    fn put_idata(&mut self, data: IData, requester: &PublicId) -> Result<()> {
+        if let IData::Unpub(ref unpub_data) = data {
+            if Some(*unpub_data.owner()) != owner_key(requester) {
+                return Err(Error::InvalidOwners);
+            }
+        }
+        if !data.validate_size() {
+            return Err(Error::ExceededSize);
+        }
+        if self.idata.contains_key(data.address()) {
+            return Err(Error::DataExists);
+        }
+        let _ = self.idata.insert(*data.address(), data);
+        Ok(())
+    }
+
+    fn get_idata(&self, address: IDataAddress, requester: &PublicId) -> Result<IData> {
+        self.accessible_idata(address, GenericAction::Read, requester)
+            .cloned()
+    }
+
+    fn delete_unpub_idata(&mut self, address: IDataAddress, requester: &PublicId) -> Result<()> {
+        if address.is_pub() {
+            return Err(Error::InvalidOperation);
+        }
+        let _ = self.accessible_idata(address, GenericAction::Delete, requester)?;
+        let _ = self.idata.remove(&address);
+        Ok(())
+    }
+
+    fn accessible_idata(
+        &self,
+        address: IDataAddress,
+        action: GenericAction,
+        requester: &PublicId,
+    ) -> Result<&IData> {
+        let data = self.idata.get(&address).ok_or(Error::NoSuchData)?;
+        if let IData::Unpub(ref unpub_data) = data {
+            let result = if Some(*unpub_data.owner()) == owner_key(requester) {
+                Ok(())
+            } else {
+                Err(Error::AccessDenied)
+            };
+            self.or_capability(address.into(), result, |context, capability, now| {
+                unpub_data.check_capability(
+                    context,
+                    capability,
+                    action,
+                    requester.signing_key(),
+                    now,
+                )
+            })?;
+        }
+        Ok(data)
+    }
+
+    //
+    // ===== Mutable Data =====
+    //
+    fn readable_mdata(&self, address: MDataAddress, requester: &PublicId) -> Result<&MData> {
+        let data = self.mdata.get(&address).ok_or(Error::NoSuchData)?;
+        let key = requester.signing_key();
+        let result = data.check_permissions(MDataAction::Read, key);
+        self.or_capability(address.into(), result, |context, capability, now| {
+            data.check_capability(context, capability, MDataAction::Read, key, now)
+        })?;
+        Ok(data)
+    }
+
+    // Returns the data if the requester may read at least some of its entries, along with the
+    // key whose permissions decide which ones. A capability gives access to every entry.
+    fn mdata_entries_reader(
+        &self,
+        address: MDataAddress,
+        requester: &PublicId,
+    ) -> Result<(&MData, Option<PublicKey>)> {
+        let data = self.mdata.get(&address).ok_or(Error::NoSuchData)?;
+        let key = requester.signing_key();
+        let result = data.check_any_entry_permissions(MDataAction::Read, key);
+        if result.is_ok() {
+            return Ok((data, Some(key)));
+        }
+        self.or_capability(address.into(), result, |context, capability, now| {
+            data.check_capability(context, capability, MDataAction::Read, key, now)
+        })?;
+        Ok((data, None))
+    }
+
+    // Returns the data holding only the entries the requester is allowed to read.
+    fn readable_mdata_entries(&self, address: MDataAddress, requester: &PublicId) -> Result<MData> {
+        let (data, reader) = self.mdata_entries_reader(address, requester)?;
+        match reader {
+            Some(key) => Ok(data.readable_by(key)),
+            None => Ok(data.clone()),
+        }
+    }
+
+    fn mdata_mut(&mut self, address: MDataAddress) -> Result<&mut MData> {
+        self.mdata.get_mut(&address).ok_or(Error::NoSuchData)
+    }
+
+    fn put_mdata(&mut self, data: MData, requester: &PublicId) -> Result<()> {
+        if Some(data.owner()) != owner_key(requester) {
+            return Err(Error::InvalidOwners);
+        }
+        if self.mdata.contains_key(data.address()) {
+            return Err(Error::DataExists);
+        }
+        data.validate_limits()?;
+        let _ = self.mdata.insert(*data.address(), data);
+        Ok(())
+    }
+
+    fn get_mdata_value(
+        &self,
+        address: MDataAddress,
+        key: &[u8],
+        requester: &PublicId,
+    ) -> Result<MDataValue> {
+        let data = self.mdata.get(&address).ok_or(Error::NoSuchData)?;
+        let requester = requester.signing_key();
+        let result = data.check_entry_permissions(key, MDataAction::Read, requester);
+        self.or_capability(address.into(), result, |context, capability, now| {
+            data.check_capability(context, capability, MDataAction::Read, requester, now)
+        })?;
+        let value = match data {
+            MData::Seq(data) => data.get(key).cloned().map(MDataValue::from),
+            MData::Unseq(data) => data.get(key).cloned().map(MDataValue::from),
+        };
+        value.ok_or(Error::NoSuchEntry)
+    }
+
+    fn delete_mdata(&mut self, address: MDataAddress, requester: &PublicId) -> Result<()> {
+        let owner = owner_key(requester).ok_or(Error::AccessDenied)?;
+        self.mdata_mut(address)?.check_is_owner(owner)?;
+        let _ = self.mdata.remove(&address);
+        let _ = self.subscriptions.remove(&address.into());
+        Ok(())
+    }
+
+    fn set_mdata_user_permissions(
+        &mut self,
+        address: MDataAddress,
+        user: MDataUser,
+        permissions: MDataPermissionSet,
+        version: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        self.mdata_mut(address)?.set_user_permissions_checked(
+            user,
+            permissions,
+            version,
+            requester.signing_key(),
+        )?;
+        self.notify_shell_changed(address.into());
+        Ok(())
+    }
+
+    fn del_mdata_user_permissions(
+        &mut self,
+        address: MDataAddress,
+        user: MDataUser,
+        version: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        self.mdata_mut(address)?.del_user_permissions_checked(
+            user,
+            version,
+            requester.signing_key(),
+        )?;
+        self.notify_shell_changed(address.into());
+        Ok(())
+    }
+
+    fn set_mdata_entry_permissions(
+        &mut self,
+        address: MDataAddress,
+        prefix: Vec<u8>,
+        user: MDataUser,
+        permissions: MDataPermissionSet,
+        version: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        self.mdata_mut(address)?.set_entry_permissions_checked(
+            prefix,
+            user,
+            permissions,
+            version,
+            requester.signing_key(),
+        )?;
+        self.notify_shell_changed(address.into());
+        Ok(())
+    }
+
+    fn del_mdata_entry_permissions(
+        &mut self,
+        address: MDataAddress,
+        prefix: &[u8],
+        user: MDataUser,
+        version: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        self.mdata_mut(address)?.del_entry_permissions_checked(
+            prefix,
+            user,
+            version,
+            requester.signing_key(),
+        )?;
+        self.notify_shell_changed(address.into());
+        Ok(())
+    }
+
+    fn mdata_entries_page(
+        &self,
+        address: MDataAddress,
+        filter: &MDataKeyFilter,
+        limit: u64,
+        cursor: Option<&[u8]>,
+        requester: &PublicId,
+    ) -> Result<(MDataEntries, Option<Vec<u8>>)> {
+        if limit == 0 {
+            return Err(Error::InvalidOperation);
+        }
+        let (data, reader) = self.mdata_entries_reader(address, requester)?;
+        Ok(match reader {
+            Some(key) => data.readable_entries_page(filter, limit, cursor, key),
+            None => data.entries_page(filter, limit, cursor),
+        })
+    }
+
+    fn purge_mdata_tombstones(
+        &mut self,
+        address: MDataAddress,
+        requester: &PublicId,
+    ) -> Result<()> {
+        let owner = owner_key(requester).ok_or(Error::AccessDenied)?;
+        match self.mdata_mut(address)? {
+            MData::Seq(data) => data.purge_tombstones(owner),
+            MData::Unseq(_) => Err(Error::InvalidOperation),
+        }
+    }
+
+    fn add_mdata_group_member(
+        &mut self,
+        address: MDataAddress,
+        group: GroupName,
+        member: PublicKey,
+        version: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        self.mdata_mut(address)?.add_group_member_checked(
+            group,
+            member,
+            version,
+            requester.signing_key(),
+        )?;
+        self.notify_shell_changed(address.into());
+        Ok(())
+    }
+
+    fn remove_mdata_group_member(
+        &mut self,
+        address: MDataAddress,
+        group: &str,
+        member: &PublicKey,
+        version: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        self.mdata_mut(address)?.remove_group_member_checked(
+            group,
+            member,
+            version,
+            requester.signing_key(),
+        )?;
+        self.notify_shell_changed(address.into());
+        Ok(())
+    }
+
+    fn mutate_mdata_entries(
+        &mut self,
+        address: MDataAddress,
+        actions: MDataEntryActions,
+        requester: &PublicId,
+    ) -> Result<()> {
+        let key = requester.signing_key();
+        let keys = actions.keys();
+        // Permissions are checked before any entry is changed, so a denied mutation leaves the
+        // data untouched. A capability allowing every required action then stands in for the
+        // permissions, in which case the entries are mutated with the owner's authority.
+        match self
+            .mdata_mut(address)?
+            .mutate_entries(actions.clone(), key)
+        {
+            Err(Error::AccessDenied) => {
+                let data = self.mdata.get(&address).ok_or(Error::NoSuchData)?;
+                for action in actions.required_actions() {
+                    let result = Err(Error::AccessDenied);
+                    self.or_capability(address.into(), result, |context, capability, now| {
+                        data.check_capability(context, capability, action, key, now)
+                    })?;
+                }
+                let owner = data.owner();
+                self.mdata_mut(address)?.mutate_entries(actions, owner)?;
+            }
+            result => result?,
+        }
+        self.notify_mdata(address, keys);
+        Ok(())
+    }
+
+    //
+    // ===== Append Only Data =====
+    //
+    fn readable_adata(&self, address: ADataAddress, requester: &PublicId) -> Result<&AData> {
+        self.accessible_adata(address, ADataAction::Read, requester)
+    }
+
+    fn accessible_adata(
+        &self,
+        address: ADataAddress,
+        action: ADataAction,
+        requester: &PublicId,
+    ) -> Result<&AData> {
+        let data = self.adata.get(&address).ok_or(Error::NoSuchData)?;
+        let key = requester.signing_key();
+        let result = data.check_permission(action, key);
+        self.or_capability(address.into(), result, |context, capability, now| {
+            data.check_capability(context, capability, action, key, now)
+        })?;
+        Ok(data)
+    }
+
+    fn adata_mut(&mut self, address: ADataAddress) -> Result<&mut AData> {
+        self.adata.get_mut(&address).ok_or(Error::NoSuchData)
+    }
+
+    fn put_adata(&mut self, data: AData, requester: &PublicId) -> Result<()> {
+        let owner = owner_key(requester).ok_or(Error::InvalidOwners)?;
+        data.check_is_last_owner(owner)
+            .map_err(|_| Error::InvalidOwners)?;
+        if self.adata.contains_key(data.address()) {
+            return Err(Error::DataExists);
+        }
+        let _ = self.adata.insert(*data.address(), data);
+        Ok(())
+    }
+
+    fn delete_adata(&mut self, address: ADataAddress, requester: &PublicId) -> Result<()> {
+        if address.is_pub() {
+            return Err(Error::InvalidOperation);
+        }
+        let owner = owner_key(requester).ok_or(Error::AccessDenied)?;
+        self.adata_mut(address)?.check_is_last_owner(owner)?;
+        let _ = self.adata.remove(&address);
+        let _ = self.subscriptions.remove(&address.into());
+        Ok(())
+    }
+
+    fn get_adata_permissions(
+        &self,
+        address: ADataAddress,
+        permissions_index: ADataIndex,
+        requester: &PublicId,
+    ) -> Result<ADataPermissions> {
+        let data = self.readable_adata(address, requester)?;
+        if data.is_pub() {
+            data.pub_permissions(permissions_index)
+                .map(|perms| perms.clone().into())
+        } else {
+            data.unpub_permissions(permissions_index)
+                .map(|perms| perms.clone().into())
+        }
+    }
+
+    fn add_pub_adata_permissions(
+        &mut self,
+        address: ADataAddress,
+        permissions: ADataPubPermissions,
+        permissions_index: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        let data = self.adata_mut(address)?;
+        data.check_permissions_grant(&permissions, requester.signing_key())?;
+        match data {
+            AData::PubSeq(adata) => adata.append_permissions(permissions, permissions_index),
+            AData::PubUnseq(adata) => adata.append_permissions(permissions, permissions_index),
+            AData::UnpubSeq(_) | AData::UnpubUnseq(_) => Err(Error::InvalidOperation),
+        }
+    }
+
+    fn add_unpub_adata_permissions(
+        &mut self,
+        address: ADataAddress,
+        permissions: ADataUnpubPermissions,
+        permissions_index: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        let data = self.adata_mut(address)?;
+        data.check_permissions_grant(&permissions, requester.signing_key())?;
+        match data {
+            AData::UnpubSeq(adata) => adata.append_permissions(permissions, permissions_index),
+            AData::UnpubUnseq(adata) => adata.append_permissions(permissions, permissions_index),
+            AData::PubSeq(_) | AData::PubUnseq(_) => Err(Error::InvalidOperation),
+        }
+    }
+
+    fn add_adata_group_member(
+        &mut self,
+        address: ADataAddress,
+        group: GroupName,
+        member: PublicKey,
+        version: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        self.adata_mut(address)?.add_group_member_checked(
+            group,
+            member,
+            version,
+            requester.signing_key(),
+        )?;
+        self.notify_shell_changed(address.into());
+        Ok(())
+    }
+
+    fn remove_adata_group_member(
+        &mut self,
+        address: ADataAddress,
+        group: &str,
+        member: &PublicKey,
+        version: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        self.adata_mut(address)?.remove_group_member_checked(
+            group,
+            member,
+            version,
+            requester.signing_key(),
+        )?;
+        self.notify_shell_changed(address.into());
+        Ok(())
+    }
+
+    fn set_adata_owner(
+        &mut self,
+        address: ADataAddress,
+        owner: ADataOwner,
+        owners_index: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        let current_owner = owner_key(requester).ok_or(Error::AccessDenied)?;
+        let data = self.adata_mut(address)?;
+        data.check_is_last_owner(current_owner)?;
+        match data {
+            AData::PubSeq(adata) => adata.append_owner(owner, owners_index),
+            AData::PubUnseq(adata) => adata.append_owner(owner, owners_index),
+            AData::UnpubSeq(adata) => adata.append_owner(owner, owners_index),
+            AData::UnpubUnseq(adata) => adata.append_owner(owner, owners_index),
+        }
+    }
+
+    fn append_seq(
+        &mut self,
+        append: ADataAppendOperation,
+        index: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        let address = append.address;
+        let keys = append_keys(&append);
+        let _ = self.accessible_adata(address, ADataAction::Append, requester)?;
+        match self.adata_mut(address)? {
+            AData::PubSeq(adata) => adata.append(append.values, index)?,
+            AData::UnpubSeq(adata) => adata.append(append.values, index)?,
+            AData::PubUnseq(_) | AData::UnpubUnseq(_) => return Err(Error::InvalidOperation),
+        }
+        self.notify_adata(address, keys);
+        Ok(())
+    }
+
+    fn append_unseq(&mut self, append: ADataAppendOperation, requester: &PublicId) -> Result<()> {
+        let address = append.address;
+        let keys = append_keys(&append);
+        let _ = self.accessible_adata(address, ADataAction::Append, requester)?;
+        match self.adata_mut(address)? {
+            AData::PubUnseq(adata) => adata.append(append.values)?,
+            AData::UnpubUnseq(adata) => adata.append(append.values)?,
+            AData::PubSeq(_) | AData::UnpubSeq(_) => return Err(Error::InvalidOperation),
+        }
+        self.notify_adata(address, keys);
+        Ok(())
+    }
+
+    //
+    // ===== Coins =====
+    //
+    fn transfer_coins(
+        &mut self,
+        destination: XorName,
+        amount: Coins,
+        transaction_id: TransactionId,
+        requester: &PublicId,
+    ) -> Result<Transaction> {
+        let source = *self.coin_source(requester)?;
+        // Debit first, so a transfer to the source balance itself leaves it unchanged.
+        self.debit(&source, amount)?;
+        if let Err(error) = self.credit(&destination, amount) {
+            // Crediting back the amount just debited can't overflow.
+            let _ = self.credit(&source, amount);
+            return Err(error);
+        }
+
+        Ok(Transaction {
+            id: transaction_id,
+            amount,
+        })
+    }
+
+    fn transfer_to_new_balance(
+        &mut self,
+        new_balance_owner: PublicKey,
+        amount: Coins,
+        transaction_id: TransactionId,
+        requester: &PublicId,
+    ) -> Result<Transaction> {
+        let source = *self.coin_source(requester)?;
+        let destination = XorName::from(new_balance_owner);
+        if self.balances.contains_key(&destination) {
+            return Err(Error::BalanceExists);
+        }
+        self.debit(&source, amount)?;
+        let _ = self.balances.insert(destination, amount);
+
+        Ok(Transaction {
+            id: transaction_id,
+            amount,
+        })
+    }
+
+    /// Returns the name of the balance to be debited by `requester`.
+    fn coin_source<'a>(&self, requester: &'a PublicId) -> Result<&'a XorName> {
+        match requester {
+            PublicId::Client(client_id) => Ok(client_id.name()),
+            PublicId::App(app_id) => {
+                let can_transfer = self
+                    .auth_keys
+                    .get(app_id.owner_name())
+                    .and_then(|(keys, _)| keys.get(app_id.public_key()))
+                    .filter(|permissions| permissions.transfer_coins)
+                    .is_some();
+                if can_transfer {
+                    Ok(app_id.owner_name())
+                } else {
+                    Err(Error::AccessDenied)
+                }
+            }
+            PublicId::Node(_) => Err(Error::AccessDenied),
+        }
+    }
+
+    fn debit(&mut self, name: &XorName, amount: Coins) -> Result<()> {
+        let balance = self.balances.get_mut(name).ok_or(Error::NoSuchBalance)?;
+        *balance = balance
+            .checked_sub(amount)
+            .ok_or(Error::InsufficientBalance)?;
+        Ok(())
+    }
+
+    fn credit(&mut self, name: &XorName, amount: Coins) -> Result<()> {
+        let balance = self.balances.get_mut(name).ok_or(Error::NoSuchBalance)?;
+        *balance = balance.checked_add(amount).ok_or(Error::ExcessiveValue)?;
+        Ok(())
+    }
+
+    //
+    // ===== Login Packet =====
+    //
+    fn create_login_packet(
+        &mut self,
+        login_packet: LoginPacket,
+        requester: &PublicId,
+    ) -> Result<()> {
+        if *login_packet.authorised_getter() != requester.signing_key() {
+            return Err(Error::AccessDenied);
+        }
+        self.store_login_packet(login_packet)
+    }
+
+    fn create_login_packet_for(
+        &mut self,
+        new_owner: PublicKey,
+        amount: Coins,
+        transaction_id: TransactionId,
+        new_login_packet: LoginPacket,
+        requester: &PublicId,
+    ) -> Result<()> {
+        // Validate the packet before any coins move, so a rejected packet leaves no new balance.
+        self.validate_new_login_packet(&new_login_packet)?;
+        let _ = self.transfer_to_new_balance(new_owner, amount, transaction_id, requester)?;
+        self.store_login_packet(new_login_packet)
+    }
+
+    fn validate_new_login_packet(&self, login_packet: &LoginPacket) -> Result<()> {
+        if !login_packet.size_is_valid() {
+            return Err(Error::ExceededSize);
+        }
+        if self.login_packets.contains_key(login_packet.destination()) {
+            return Err(Error::LoginPacketExists);
+        }
+        Ok(())
+    }
+
+    fn store_login_packet(&mut self, login_packet: LoginPacket) -> Result<()> {
+        self.validate_new_login_packet(&login_packet)?;
+        let _ = self
+            .login_packets
+            .insert(*login_packet.destination(), login_packet);
+        Ok(())
+    }
+
+    fn update_login_packet(
+        &mut self,
+        login_packet: LoginPacket,
+        requester: &PublicId,
+    ) -> Result<()> {
+        let _ = self.get_login_packet(login_packet.destination(), requester)?;
+        if !login_packet.size_is_valid() {
+            return Err(Error::ExceededSize);
+        }
+        let _ = self
+            .login_packets
+            .insert(*login_packet.destination(), login_packet);
+        Ok(())
+    }
+
+    fn get_login_packet(
+        &self,
+        name: &XorName,
+        requester: &PublicId,
+    ) -> Result<(Vec<u8>, Signature)> {
+        let login_packet = self
+            .login_packets
+            .get(name)
+            .ok_or(Error::NoSuchLoginPacket)?;
+        if *login_packet.authorised_getter() != requester.signing_key() {
+            return Err(Error::AccessDenied);
+        }
+        Ok(login_packet.clone().into_data_and_signature())
+    }
+
+    //
+    // ===== Client (Owner) to SrcElders =====
+    //
+    fn ins_auth_key(
+        &mut self,
+        key: PublicKey,
+        version: u64,
+        permissions: AppPermissions,
+        requester: &PublicId,
+    ) -> Result<()> {
+        let name = client_name(requester)?;
+        let (keys, current_version) = self.auth_keys.entry(*name).or_default();
+        if version != *current_version + 1 {
+            return Err(Error::InvalidSuccessor(*current_version));
+        }
+        let _ = keys.insert(key, permissions);
+        *current_version = version;
+        Ok(())
+    }
+
+    fn del_auth_key(&mut self, key: PublicKey, version: u64, requester: &PublicId) -> Result<()> {
+        let name = client_name(requester)?;
+        let (keys, current_version) = self.auth_keys.entry(*name).or_default();
+        if version != *current_version + 1 {
+            return Err(Error::InvalidSuccessor(*current_version));
+        }
+        if keys.remove(&key).is_none() {
+            return Err(Error::NoSuchKey);
+        }
+        *current_version = version;
+        Ok(())
+    }
+
+    //
+    // ===== Subscriptions =====
+    //
+    fn subscribe_mdata(&mut self, address: MDataAddress, requester: &PublicId) -> Result<()> {
+        let _ = self.mdata_entries_reader(address, requester)?;
+        self.subscribe(address.into(), requester);
+        Ok(())
+    }
+
+    fn subscribe_adata(&mut self, address: ADataAddress, requester: &PublicId) -> Result<()> {
+        let _ = self.readable_adata(address, requester)?;
+        self.subscribe(address.into(), requester);
+        Ok(())
+    }
+
+    fn subscribe(&mut self, address: DataAddress, requester: &PublicId) {
+        let _ = self
+            .subscriptions
+            .entry(address)
+            .or_default()
+            .insert(requester.clone());
+    }
+
+    fn unsubscribe(&mut self, address: DataAddress, requester: &PublicId) -> Result<()> {
+        let is_subscribed = self
+            .subscriptions
+            .get_mut(&address)
+            .map(|subscribers| subscribers.remove(requester))
+            .unwrap_or(false);
+        if is_subscribed {
+            Ok(())
+        } else {
+            Err(Error::NoSuchEntry)
+        }
+    }
+
+    /// Notifies the subscribers of `address` which are still allowed to read some of the data,
+    /// of the mutated keys they are allowed to read.
+    fn notify_mdata(&mut self, address: MDataAddress, keys: BTreeSet<Vec<u8>>) {
+        let (data, subscribers) = match (
+            self.mdata.get(&address),
+            self.subscriptions.get(&address.into()),
+        ) {
+            (Some(data), Some(subscribers)) => (data, subscribers),
+            _ => return,
+        };
+        for subscriber in subscribers {
+            let key = subscriber.signing_key();
+            if data
+                .check_any_entry_permissions(MDataAction::Read, key)
+                .is_err()
+            {
+                continue;
+            }
+            let notification = Notification::MDataMutated {
+                address,
+                version: data.version(),
+                keys: keys
+                    .iter()
+                    .filter(|entry_key| {
+                        data.check_entry_permissions(entry_key, MDataAction::Read, key)
+                            .is_ok()
+                    })
+                    .cloned()
+                    .collect(),
+            };
+            self.notifications.push((subscriber.clone(), notification));
+        }
+    }
+
+    /// Notifies the subscribers of `address` which are still allowed to read the data.
+    fn notify_adata(&mut self, address: ADataAddress, keys: Vec<Vec<u8>>) {
+        let (data, subscribers) = match (
+            self.adata.get(&address),
+            self.subscriptions.get(&address.into()),
+        ) {
+            (Some(data), Some(subscribers)) => (data, subscribers),
+            _ => return,
+        };
+        let notification = Notification::ADataAppended {
+            address,
+            index: data.entries_index(),
+            keys,
+        };
+        for subscriber in subscribers {
+            if data
+                .check_permission(ADataAction::Read, subscriber.signing_key())
+                .is_ok()
+            {
+                self.notifications
+                    .push((subscriber.clone(), notification.clone()));
+            }
+        }
+    }
+
+    /// Notifies the subscribers of `address` which are still allowed to read the data that its
+    /// shell has changed.
+    fn notify_shell_changed(&mut self, address: DataAddress) {
+        let subscribers = match self.subscriptions.get(&address) {
+            Some(subscribers) => subscribers,
+            None => return,
+        };
+        for subscriber in subscribers {
+            let key = subscriber.signing_key();
+            let allowed = match address {
+                DataAddress::Mutable(address) => match self.mdata.get(&address) {
+                    Some(data) => data
+                        .check_any_entry_permissions(MDataAction::Read, key)
+                        .is_ok(),
+                    None => false,
+                },
+                DataAddress::AppendOnly(address) => match self.adata.get(&address) {
+                    Some(data) => data.check_permission(ADataAction::Read, key).is_ok(),
+                    None => false,
+                },
+                DataAddress::Immutable(_) => false,
+            };
+            if allowed {
+                self.notifications
+                    .push((subscriber.clone(), Notification::ShellChanged { address }));
+            }
+        }
+    }
+
+    //
+    // ===== Capabilities =====
+    //
+    fn revoke_capability(
+        &mut self,
+        address: DataAddress,
+        nonce: u64,
+        requester: &PublicId,
+    ) -> Result<()> {
+        let owner = owner_key(requester).ok_or(Error::AccessDenied)?;
+        match address {
+            DataAddress::Immutable(address) => {
+                match self.idata.get(&address).ok_or(Error::NoSuchData)? {
+                    IData::Unpub(data) if *data.owner() == owner => (),
+                    IData::Unpub(_) => return Err(Error::AccessDenied),
+                    IData::Pub(_) => return Err(Error::InvalidOperation),
+                }
+            }
+            DataAddress::Mutable(address) => self.mdata_mut(address)?.check_is_owner(owner)?,
+            DataAddress::AppendOnly(address) => {
+                self.adata_mut(address)?.check_is_last_owner(owner)?
+            }
+        }
+        let _ = self
+            .revoked_grants
+            .entry(address)
+            .or_default()
+            .insert(nonce);
+        Ok(())
+    }
+
+    /// Returns `result` if it's a success or no capability is attached to the current request.
+    /// Otherwise returns the result of `check` on the capability and the signing context of the
+    /// vault's network, unless one of its grants has been revoked.
+    fn or_capability<F>(&self, address: DataAddress, result: Result<()>, check: F) -> Result<()>
+    where
+        F: FnOnce(&SigningContext, &Capability, u64) -> Result<()>,
+    {
+        let capability = match (result, &self.capability) {
+            (Ok(()), _) => return Ok(()),
+            (Err(error), None) => return Err(error),
+            (Err(_), Some(capability)) => capability,
+        };
+        if let Some(revoked) = self.revoked_grants.get(&address) {
+            if capability
+                .grants()
+                .any(|grant| revoked.contains(&grant.nonce()))
+            {
+                return Err(Error::CapabilityRevoked);
+            }
+        }
+        check(&SigningContext::new(self.network_id), capability, now())
+    }
+}
+
+/// Returns the keys of the entries appended by `append`, in order.
+fn append_keys(append: &ADataAppendOperation) -> Vec<Vec<u8>> {
+    append
+        .values
+        .iter()
+        .map(|entry| entry.key.clone())
+        .collect()
+}
+
+/// Returns the key which newly stored data must be owned by, if `requester` can own data.
+fn owner_key(requester: &PublicId) -> Option<PublicKey> {
+    match requester {
+        PublicId::Node(_) => None,
+        PublicId::Client(client_id) => Some(*client_id.public_key()),
+        PublicId::App(app_id) => Some(*app_id.owner().public_key()),
+    }
+}
+
+/// Returns the current time in seconds since the UNIX epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// Returns the name of `requester` if it's a client. Only clients can manage their auth keys.
+fn client_name(requester: &PublicId) -> Result<&XorName> {
+    match requester {
+        PublicId::Client(client_id) => Ok(client_id.name()),
+        PublicId::App(_) | PublicId::Node(_) => Err(Error::AccessDenied),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ADataPubPermissionSet, ADataUser, AppFullId, CapabilityGrantee, ClientFullId,
+        MDataSeqEntryActions, MDataSeqValue, MDataValues, MessageId, PubImmutableData,
+        PubSeqAppendOnlyData, SeqMutableData, UnpubImmutableData, MAX_LOGIN_PACKET_BYTES,
+    };
+    use unwrap::unwrap;
+
+    fn send(vault: &mut MockVault, full_id: &ClientFullId, request: Request) -> Response {
+        send_with(vault, full_id, request, None)
+    }
+
+    fn send_with(
+        vault: &mut MockVault,
+        full_id: &ClientFullId,
+        request: Request,
+        capability: Option<Capability>,
+    ) -> Response {
+        let context = SigningContext::new(vault.network_id);
+        let mut message = Message::new_signed_request(&context, full_id, request);
+        if let Some(capability) = capability {
+            unwrap!(message.attach_capability(capability));
+        }
+        let requester = PublicId::Client(full_id.public_id().clone());
+        match unwrap!(vault.process_request(message, &requester)) {
+            Message::Response { response, .. } => response,
+            message => panic!("Unexpected message: {:?}", message.message_id()),
+        }
+    }
+
+    #[test]
+    fn put_and_get_idata() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let other = ClientFullId::new_ed25519(&mut rng);
+
+        let pub_data = IData::from(PubImmutableData::new(vec![1, 2, 3]));
+        let unpub_data = IData::from(UnpubImmutableData::new(
+            vec![4, 5, 6],
+            *owner.public_id().public_key(),
+        ));
+
+        let response = send(&mut vault, &owner, Request::PutIData(pub_data.clone()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let response = send(&mut vault, &owner, Request::PutIData(pub_data.clone()));
+        assert_eq!(response, Response::Mutation(Err(Error::DataExists)));
+        let response = send(&mut vault, &owner, Request::PutIData(unpub_data.clone()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        // Anyone can fetch published data, but only the owner can fetch unpublished data.
+        let response = send(&mut vault, &other, Request::GetIData(*pub_data.address()));
+        assert_eq!(response, Response::GetIData(Ok(pub_data)));
+        let response = send(&mut vault, &other, Request::GetIData(*unpub_data.address()));
+        assert_eq!(response, Response::GetIData(Err(Error::AccessDenied)));
+        let response = send(&mut vault, &owner, Request::GetIData(*unpub_data.address()));
+        assert_eq!(response, Response::GetIData(Ok(unpub_data.clone())));
+
+        let response = send(
+            &mut vault,
+            &owner,
+            Request::DeleteUnpubIData(*unpub_data.address()),
+        );
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let response = send(&mut vault, &owner, Request::GetIData(*unpub_data.address()));
+        assert_eq!(response, Response::GetIData(Err(Error::NoSuchData)));
+    }
+
+    #[test]
+    fn capabilities() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let user = ClientFullId::new_ed25519(&mut rng);
+        let bearer = ClientFullId::new_bls(&mut rng);
+
+        let data = IData::from(UnpubImmutableData::new(
+            vec![1, 2, 3],
+            *owner.public_id().public_key(),
+        ));
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutIData(data.clone()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        let read: BTreeSet<_> = vec![GenericAction::Read].into_iter().collect();
+        let expiry = now() + 3600;
+        let context = SigningContext::new(NetworkId::default());
+        let capability = unwrap!(Capability::new(
+            &context,
+            &owner,
+            address.into(),
+            read.clone(),
+            CapabilityGrantee::Key(*user.public_id().public_key()),
+            expiry,
+        ));
+
+        // The capability replaces a permission entry, for the granted actions only.
+        let response = send(&mut vault, &user, Request::GetIData(address));
+        assert_eq!(response, Response::GetIData(Err(Error::AccessDenied)));
+        let response = send_with(
+            &mut vault,
+            &user,
+            Request::GetIData(address),
+            Some(capability.clone()),
+        );
+        assert_eq!(response, Response::GetIData(Ok(data.clone())));
+        let response = send_with(
+            &mut vault,
+            &user,
+            Request::DeleteUnpubIData(address),
+            Some(capability.clone()),
+        );
+        assert_eq!(response, Response::Mutation(Err(Error::AccessDenied)));
+
+        let expired = unwrap!(Capability::new(
+            &context,
+            &owner,
+            address.into(),
+            read.clone(),
+            CapabilityGrantee::Key(*user.public_id().public_key()),
+            1,
+        ));
+        let response = send_with(&mut vault, &user, Request::GetIData(address), Some(expired));
+        assert_eq!(response, Response::GetIData(Err(Error::CapabilityExpired)));
+
+        // Capabilities issued for another network are rejected.
+        let foreign = unwrap!(Capability::new(
+            &SigningContext::new(NetworkId([1; 32])),
+            &owner,
+            address.into(),
+            read.clone(),
+            CapabilityGrantee::Key(*user.public_id().public_key()),
+            expiry,
+        ));
+        let response = send_with(&mut vault, &user, Request::GetIData(address), Some(foreign));
+        assert_eq!(response, Response::GetIData(Err(Error::InvalidSignature)));
+
+        // Delegated capabilities stop working once the grant they're based on is revoked.
+        let delegated =
+            unwrap!(capability.delegate(&context, &user, read, CapabilityGrantee::Bearer, expiry));
+        let response = send_with(
+            &mut vault,
+            &bearer,
+            Request::GetIData(address),
+            Some(delegated.clone()),
+        );
+        assert_eq!(response, Response::GetIData(Ok(data)));
+
+        let revoke = Request::RevokeCapability {
+            address: address.into(),
+            nonce: unwrap!(capability.grants().next()).nonce(),
+        };
+        let response = send(&mut vault, &user, revoke.clone());
+        assert_eq!(response, Response::Mutation(Err(Error::AccessDenied)));
+        let response = send(&mut vault, &owner, revoke);
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let response = send_with(
+            &mut vault,
+            &bearer,
+            Request::GetIData(address),
+            Some(delegated),
+        );
+        assert_eq!(response, Response::GetIData(Err(Error::CapabilityRevoked)));
+    }
+
+    #[test]
+    fn mdata_entry_capabilities() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let user = ClientFullId::new_ed25519(&mut rng);
+
+        let data = SeqMutableData::new(rand::random(), 1000, *owner.public_id().public_key());
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutMData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        let insert: BTreeSet<_> = vec![GenericAction::Insert].into_iter().collect();
+        let capability = unwrap!(Capability::new(
+            &SigningContext::new(NetworkId::default()),
+            &owner,
+            address.into(),
+            insert,
+            CapabilityGrantee::Key(*user.public_id().public_key()),
+            now() + 3600,
+        ));
+
+        // The capability replaces a permission entry, for the granted actions only.
+        let insert = Request::MutateMDataEntries {
+            address,
+            actions: MDataSeqEntryActions::new()
+                .ins(b"key".to_vec(), b"value".to_vec(), 0)
+                .into(),
+        };
+        let response = send(&mut vault, &user, insert.clone());
+        assert_eq!(response, Response::Mutation(Err(Error::AccessDenied)));
+        let response = send_with(&mut vault, &user, insert, Some(capability.clone()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        let update = Request::MutateMDataEntries {
+            address,
+            actions: MDataSeqEntryActions::new()
+                .update(b"key".to_vec(), b"new".to_vec(), 1)
+                .into(),
+        };
+        let response = send_with(&mut vault, &user, update, Some(capability));
+        assert_eq!(response, Response::Mutation(Err(Error::AccessDenied)));
+    }
+
+    #[test]
+    fn mutate_mdata_entries() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let other = ClientFullId::new_ed25519(&mut rng);
+
+        let data = SeqMutableData::new(rand::random(), 1000, *owner.public_id().public_key());
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutMData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        let actions = MDataSeqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec(), 0);
+        let request = Request::MutateMDataEntries {
+            address,
+            actions: actions.into(),
+        };
+        let response = send(&mut vault, &other, request.clone());
+        assert_eq!(response, Response::Mutation(Err(Error::AccessDenied)));
+        let response = send(&mut vault, &owner, request);
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        let request = Request::GetMDataValue {
+            address,
+            key: b"key".to_vec(),
+        };
+        let response = send(&mut vault, &other, request.clone());
+        assert_eq!(response, Response::GetMDataValue(Err(Error::AccessDenied)));
+
+        let request_permissions = Request::SetMDataUserPermissions {
+            address,
+            user: MDataUser::Key(*other.public_id().public_key()),
+            permissions: MDataPermissionSet::new().allow(MDataAction::Read),
+            version: 1,
+        };
+        let response = send(&mut vault, &owner, request_permissions);
+        assert_eq!(response, Response::Mutation(Ok(())));
+        match send(&mut vault, &other, request) {
+            Response::GetMDataValue(Ok(MDataValue::Seq(value))) => {
+                assert_eq!(value.data, b"value".to_vec());
+                assert_eq!(value.version, 0);
+            }
+            response => panic!("Unexpected response: {:?}", response),
+        }
+    }
+
+    #[test]
+    fn purge_mdata_tombstones() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let other = ClientFullId::new_ed25519(&mut rng);
+
+        let data = SeqMutableData::new(rand::random(), 1000, *owner.public_id().public_key());
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutMData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        for actions in &[
+            MDataSeqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec(), 0),
+            MDataSeqEntryActions::new().del(b"key".to_vec(), 1),
+        ] {
+            let request = Request::MutateMDataEntries {
+                address,
+                actions: actions.clone().into(),
+            };
+            assert_eq!(
+                send(&mut vault, &owner, request),
+                Response::Mutation(Ok(()))
+            );
+        }
+
+        let request = Request::PurgeMDataTombstones(address);
+        assert_eq!(
+            send(&mut vault, &other, request.clone()),
+            Response::Mutation(Err(Error::AccessDenied))
+        );
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        match send(&mut vault, &owner, Request::GetMData(address)) {
+            Response::GetMData(Ok(MData::Seq(data))) => assert!(data.tombstones().is_empty()),
+            response => panic!("Unexpected response: {:?}", response),
+        }
+    }
+
+    #[test]
+    fn mdata_entry_permissions() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let other = ClientFullId::new_ed25519(&mut rng);
+        let other_key = *other.public_id().public_key();
+
+        let data = SeqMutableData::new(rand::random(), 1000, *owner.public_id().public_key());
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutMData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let actions = MDataSeqEntryActions::new()
+            .ins(b"public/1".to_vec(), b"value".to_vec(), 0)
+            .ins(b"private/1".to_vec(), b"value".to_vec(), 0);
+        let request = Request::MutateMDataEntries {
+            address,
+            actions: actions.into(),
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+
+        let request = Request::SetMDataUserPermissions {
+            address,
+            user: MDataUser::Key(other_key),
+            permissions: MDataPermissionSet::new().allow(MDataAction::Read),
+            version: 1,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        let request = Request::SetMDataEntryPermissions {
+            address,
+            prefix: b"private/".to_vec(),
+            user: MDataUser::Key(other_key),
+            permissions: MDataPermissionSet::new().deny(MDataAction::Read),
+            version: 2,
+        };
+        assert_eq!(
+            send(&mut vault, &other, request.clone()),
+            Response::Mutation(Err(Error::AccessDenied))
+        );
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+
+        // overridden entries can't be read, and are left out of listings
+        let request = Request::GetMDataValue {
+            address,
+            key: b"private/1".to_vec(),
+        };
+        assert_eq!(
+            send(&mut vault, &other, request),
+            Response::GetMDataValue(Err(Error::AccessDenied))
+        );
+        let mut keys = BTreeSet::new();
+        let _ = keys.insert(b"public/1".to_vec());
+        assert_eq!(
+            send(&mut vault, &other, Request::ListMDataKeys(address)),
+            Response::ListMDataKeys(Ok(keys))
+        );
+        match send(&mut vault, &owner, Request::ListMDataKeys(address)) {
+            Response::ListMDataKeys(Ok(keys)) => assert_eq!(keys.len(), 2),
+            response => panic!("Unexpected response: {:?}", response),
+        }
+
+        // managers can't lift an override restricting themselves
+        let request = Request::SetMDataUserPermissions {
+            address,
+            user: MDataUser::Key(other_key),
+            permissions: MDataPermissionSet::new()
+                .allow(MDataAction::Read)
+                .allow(MDataAction::ManagePermissions),
+            version: 3,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        let request = Request::DelMDataEntryPermissions {
+            address,
+            prefix: b"private/".to_vec(),
+            user: MDataUser::Key(other_key),
+            version: 4,
+        };
+        assert_eq!(
+            send(&mut vault, &other, request.clone()),
+            Response::Mutation(Err(Error::PrivilegeEscalation))
+        );
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        let request = Request::GetMDataValue {
+            address,
+            key: b"private/1".to_vec(),
+        };
+        match send(&mut vault, &other, request) {
+            Response::GetMDataValue(Ok(_)) => (),
+            response => panic!("Unexpected response: {:?}", response),
+        }
+    }
+
+    #[test]
+    fn mdata_entry_permissions_on_read_paths() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let other = ClientFullId::new_ed25519(&mut rng);
+        let other_id = PublicId::Client(other.public_id().clone());
+
+        let data = SeqMutableData::new(rand::random(), 1000, *owner.public_id().public_key());
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutMData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let actions = MDataSeqEntryActions::new()
+            .ins(b"shared/1".to_vec(), b"value".to_vec(), 0)
+            .ins(b"private/1".to_vec(), b"value".to_vec(), 0);
+        let request = Request::MutateMDataEntries {
+            address,
+            actions: actions.into(),
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+
+        // without any permissions, nothing can be listed
+        assert_eq!(
+            send(&mut vault, &other, Request::ListMDataKeys(address)),
+            Response::ListMDataKeys(Err(Error::AccessDenied))
+        );
+
+        // an entry override is enough to list and subscribe to the entries it covers
+        let request = Request::SetMDataEntryPermissions {
+            address,
+            prefix: b"shared/".to_vec(),
+            user: MDataUser::Key(*other.public_id().public_key()),
+            permissions: MDataPermissionSet::new().allow(MDataAction::Read),
+            version: 1,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        let mut keys = BTreeSet::new();
+        let _ = keys.insert(b"shared/1".to_vec());
+        assert_eq!(
+            send(&mut vault, &other, Request::ListMDataKeys(address)),
+            Response::ListMDataKeys(Ok(keys.clone()))
+        );
+        let request = Request::ListMDataKeysPage {
+            address,
+            filter: MDataKeyFilter::All,
+            limit: 10,
+            cursor: None,
+        };
+        assert_eq!(
+            send(&mut vault, &other, request),
+            Response::ListMDataKeysPage(Ok((keys.clone(), None)))
+        );
+        let response = send(&mut vault, &other, Request::SubscribeMData(address));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        // and notifications leave out the keys of the other entries
+        let actions = MDataSeqEntryActions::new()
+            .update(b"shared/1".to_vec(), b"new".to_vec(), 1)
+            .update(b"private/1".to_vec(), b"new".to_vec(), 1);
+        let request = Request::MutateMDataEntries {
+            address,
+            actions: actions.into(),
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        let entries_mutated = Message::Notification {
+            notification: Notification::MDataMutated {
+                address,
+                version: 1,
+                keys,
+            },
+        };
+        assert!(vault.take_notifications() == vec![(other_id, entries_mutated)]);
+    }
+
+    #[test]
+    fn list_mdata_pages() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let other = ClientFullId::new_ed25519(&mut rng);
+        let other_key = *other.public_id().public_key();
+
+        let data = SeqMutableData::new(rand::random(), 1000, *owner.public_id().public_key());
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutMData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let actions = MDataSeqEntryActions::new()
+            .ins(b"public/1".to_vec(), b"value".to_vec(), 0)
+            .ins(b"public/2".to_vec(), b"value".to_vec(), 0)
+            .ins(b"private/1".to_vec(), b"value".to_vec(), 0);
+        let request = Request::MutateMDataEntries {
+            address,
+            actions: actions.into(),
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        let request = Request::SetMDataUserPermissions {
+            address,
+            user: MDataUser::Key(other_key),
+            permissions: MDataPermissionSet::new().allow(MDataAction::Read),
+            version: 1,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        let request = Request::SetMDataEntryPermissions {
+            address,
+            prefix: b"private/".to_vec(),
+            user: MDataUser::Key(other_key),
+            permissions: MDataPermissionSet::new().deny(MDataAction::Read),
+            version: 2,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+
+        // page through the keys one at a time, skipping the entries which can't be read
+        let mut keys = Vec::new();
+        let mut cursor = None;
+        loop {
+            let request = Request::ListMDataKeysPage {
+                address,
+                filter: MDataKeyFilter::All,
+                limit: 1,
+                cursor,
+            };
+            match send(&mut vault, &other, request) {
+                Response::ListMDataKeysPage(Ok((page, next))) => {
+                    assert_eq!(page.len(), 1);
+                    keys.extend(page);
+                    cursor = next;
+                }
+                response => panic!("Unexpected response: {:?}", response),
+            }
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(keys, vec![b"public/1".to_vec(), b"public/2".to_vec()]);
+
+        let request = Request::ListMDataEntriesPage {
+            address,
+            filter: MDataKeyFilter::Prefix(b"private/".to_vec()),
+            limit: 10,
+            cursor: None,
+        };
+        match send(&mut vault, &owner, request) {
+            Response::ListMDataEntriesPage(Ok((MDataEntries::Seq(entries), None))) => {
+                assert!(entries.contains_key(&b"private/1"[..]))
+            }
+            response => panic!("Unexpected response: {:?}", response),
+        }
+        let request = Request::ListMDataValuesPage {
+            address,
+            filter: MDataKeyFilter::Range {
+                start: Some(b"public/2".to_vec()),
+                end: None,
+            },
+            limit: 10,
+            cursor: None,
+        };
+        match send(&mut vault, &other, request) {
+            Response::ListMDataValuesPage(Ok((values, None))) => {
+                assert_eq!(
+                    values,
+                    MDataValues::from(vec![MDataSeqValue {
+                        data: b"value".to_vec(),
+                        version: 0,
+                    }])
+                )
+            }
+            response => panic!("Unexpected response: {:?}", response),
+        }
+
+        let request = Request::ListMDataKeysPage {
+            address,
+            filter: MDataKeyFilter::All,
+            limit: 0,
+            cursor: None,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::ListMDataKeysPage(Err(Error::InvalidOperation))
+        );
+    }
+
+    #[test]
+    fn subscriptions() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let owner_id = PublicId::Client(owner.public_id().clone());
+        let other = ClientFullId::new_ed25519(&mut rng);
+        let other_id = PublicId::Client(other.public_id().clone());
+
+        let data = SeqMutableData::new(rand::random(), 1000, *owner.public_id().public_key());
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutMData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        // Only readers can subscribe.
+        let response = send(&mut vault, &other, Request::SubscribeMData(address));
+        assert_eq!(response, Response::Mutation(Err(Error::AccessDenied)));
+        let response = send(&mut vault, &owner, Request::SubscribeMData(address));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        let request = Request::SetMDataUserPermissions {
+            address,
+            user: MDataUser::Key(*other.public_id().public_key()),
+            permissions: MDataPermissionSet::new().allow(MDataAction::Read),
+            version: 1,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        let response = send(&mut vault, &other, Request::SubscribeMData(address));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        let actions = MDataSeqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec(), 0);
+        let request = Request::MutateMDataEntries {
+            address,
+            actions: actions.into(),
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+
+        let permissions_changed = Message::Notification {
+            notification: Notification::ShellChanged {
+                address: address.into(),
+            },
+        };
+        let entries_mutated = Message::Notification {
+            notification: Notification::MDataMutated {
+                address,
+                version: 1,
+                keys: vec![b"key".to_vec()].into_iter().collect(),
+            },
+        };
+        let notifications = vault.take_notifications();
+        assert_eq!(notifications.len(), 3);
+        assert!(notifications.contains(&(owner_id.clone(), permissions_changed)));
+        assert!(notifications.contains(&(owner_id, entries_mutated.clone())));
+        assert!(notifications.contains(&(other_id, entries_mutated)));
+        assert!(vault.take_notifications().is_empty());
+
+        let response = send(&mut vault, &other, Request::UnsubscribeMData(address));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let response = send(&mut vault, &other, Request::UnsubscribeMData(address));
+        assert_eq!(response, Response::Mutation(Err(Error::NoSuchEntry)));
+    }
+
+    #[test]
+    fn mdata_group_member_escalation() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let manager = ClientFullId::new_ed25519(&mut rng);
+        let manager_key = *manager.public_id().public_key();
+
+        let data = SeqMutableData::new(rand::random(), 1000, *owner.public_id().public_key());
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutMData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        for (user, permissions, version) in &[
+            (
+                MDataUser::Key(manager_key),
+                MDataPermissionSet::new()
+                    .allow(MDataAction::Read)
+                    .allow(MDataAction::ManagePermissions),
+                1,
+            ),
+            (
+                MDataUser::Group("editors".to_string()),
+                MDataPermissionSet::new()
+                    .allow(MDataAction::Insert)
+                    .allow(MDataAction::Update)
+                    .allow(MDataAction::Delete),
+                2,
+            ),
+        ] {
+            let request = Request::SetMDataUserPermissions {
+                address,
+                user: user.clone(),
+                permissions: permissions.clone(),
+                version: *version,
+            };
+            assert_eq!(
+                send(&mut vault, &owner, request),
+                Response::Mutation(Ok(()))
+            );
+        }
+
+        // the manager can't get around `SetMDataUserPermissions` by joining the group
+        let request = Request::AddMDataGroupMember {
+            address,
+            group: "editors".to_string(),
+            member: manager_key,
+            version: 1,
+        };
+        assert_eq!(
+            send(&mut vault, &manager, request.clone()),
+            Response::Mutation(Err(Error::PrivilegeEscalation))
+        );
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+    }
+
+    #[test]
+    fn mdata_permissions_removal_escalation() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let manager = ClientFullId::new_ed25519(&mut rng);
+        let manager_key = *manager.public_id().public_key();
+
+        let data = SeqMutableData::new(rand::random(), 1000, *owner.public_id().public_key());
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutMData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let requests = vec![
+            Request::SetMDataUserPermissions {
+                address,
+                user: MDataUser::Key(manager_key),
+                permissions: MDataPermissionSet::new()
+                    .allow(MDataAction::Read)
+                    .allow(MDataAction::ManagePermissions),
+                version: 1,
+            },
+            Request::SetMDataUserPermissions {
+                address,
+                user: MDataUser::Group("editors".to_string()),
+                permissions: MDataPermissionSet::new()
+                    .allow(MDataAction::Read)
+                    .allow(MDataAction::Insert)
+                    .allow(MDataAction::Update)
+                    .allow(MDataAction::Delete),
+                version: 2,
+            },
+            Request::SetMDataEntryPermissions {
+                address,
+                prefix: b"private/".to_vec(),
+                user: MDataUser::Group("restricted".to_string()),
+                permissions: MDataPermissionSet::new().deny(MDataAction::Read),
+                version: 3,
+            },
+            Request::AddMDataGroupMember {
+                address,
+                group: "editors".to_string(),
+                member: manager_key,
+                version: 1,
+            },
+            Request::AddMDataGroupMember {
+                address,
+                group: "restricted".to_string(),
+                member: manager_key,
+                version: 1,
+            },
+        ];
+        for request in requests {
+            assert_eq!(
+                send(&mut vault, &owner, request),
+                Response::Mutation(Ok(()))
+            );
+        }
+
+        // the manager's own entry overrides the broader one of its group, so deleting it would
+        // grant the manager the group's actions
+        let request = Request::DelMDataUserPermissions {
+            address,
+            user: MDataUser::Key(manager_key),
+            version: 4,
+        };
+        assert_eq!(
+            send(&mut vault, &manager, request.clone()),
+            Response::Mutation(Err(Error::PrivilegeEscalation))
+        );
+
+        // leaving the group restricted on some entries would lift the restriction
+        let leave = Request::RemoveMDataGroupMember {
+            address,
+            group: "restricted".to_string(),
+            member: manager_key,
+            version: 2,
+        };
+        assert_eq!(
+            send(&mut vault, &manager, leave.clone()),
+            Response::Mutation(Err(Error::PrivilegeEscalation))
+        );
+
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        assert_eq!(send(&mut vault, &owner, leave), Response::Mutation(Ok(())));
+    }
+
+    #[test]
+    fn adata_group_member_removal_escalation() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let manager = ClientFullId::new_ed25519(&mut rng);
+        let owner_key = *owner.public_id().public_key();
+        let manager_key = *manager.public_id().public_key();
+        let member = *ClientFullId::new_ed25519(&mut rng).public_id().public_key();
+
+        let mut data = PubSeqAppendOnlyData::new(rand::random(), 1000);
+        unwrap!(data.append_owner(
+            ADataOwner {
+                public_key: owner_key,
+                entries_index: 0,
+                permissions_index: 0,
+            },
+            0
+        ));
+        let mut permissions = ADataPubPermissions {
+            permissions: BTreeMap::new(),
+            entries_index: 0,
+            owners_index: 1,
+        };
+        let _ = permissions
+            .permissions
+            .insert(ADataUser::Anyone, ADataPubPermissionSet::new(true, false));
+        let _ = permissions.permissions.insert(
+            ADataUser::Key(manager_key),
+            ADataPubPermissionSet::new(false, true),
+        );
+        let _ = permissions.permissions.insert(
+            ADataUser::Group("muted".to_string()),
+            ADataPubPermissionSet::new(false, None),
+        );
+        unwrap!(data.append_permissions(permissions, 0));
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutAData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let request = Request::AddADataGroupMember {
+            address,
+            group: "muted".to_string(),
+            member,
+            version: 1,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+
+        // the member would fall back to `Anyone`, which allows appending
+        let request = Request::RemoveADataGroupMember {
+            address,
+            group: "muted".to_string(),
+            member,
+            version: 2,
+        };
+        assert_eq!(
+            send(&mut vault, &manager, request.clone()),
+            Response::Mutation(Err(Error::PrivilegeEscalation))
+        );
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+    }
+
+    #[test]
+    fn adata_group_member_notifications() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let owner = ClientFullId::new_ed25519(&mut rng);
+        let owner_id = PublicId::Client(owner.public_id().clone());
+        let owner_key = *owner.public_id().public_key();
+        let member = *ClientFullId::new_ed25519(&mut rng).public_id().public_key();
+
+        let mut data = PubSeqAppendOnlyData::new(rand::random(), 1000);
+        unwrap!(data.append_owner(
+            ADataOwner {
+                public_key: owner_key,
+                entries_index: 0,
+                permissions_index: 0,
+            },
+            0
+        ));
+        let address = *data.address();
+        let response = send(&mut vault, &owner, Request::PutAData(data.into()));
+        assert_eq!(response, Response::Mutation(Ok(())));
+        let response = send(&mut vault, &owner, Request::SubscribeAData(address));
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        let membership_changed = (
+            owner_id,
+            Message::Notification {
+                notification: Notification::ShellChanged {
+                    address: address.into(),
+                },
+            },
+        );
+        let request = Request::AddADataGroupMember {
+            address,
+            group: "editors".to_string(),
+            member,
+            version: 1,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        assert!(vault.take_notifications() == vec![membership_changed.clone()]);
+        let request = Request::RemoveADataGroupMember {
+            address,
+            group: "editors".to_string(),
+            member,
+            version: 2,
+        };
+        assert_eq!(
+            send(&mut vault, &owner, request),
+            Response::Mutation(Ok(()))
+        );
+        assert!(vault.take_notifications() == vec![membership_changed]);
+    }
+
+    #[test]
+    fn transfer_coins() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let sender = ClientFullId::new_ed25519(&mut rng);
+        let receiver = ClientFullId::new_ed25519(&mut rng);
+        let sender_key = *sender.public_id().public_key();
+        let receiver_key = *receiver.public_id().public_key();
+
+        unwrap!(vault.create_balance(sender_key, unwrap!(Coins::from_nano(10))));
+
+        let response = send(
+            &mut vault,
+            &sender,
+            Request::CreateBalance {
+                new_balance_owner: receiver_key,
+                amount: unwrap!(Coins::from_nano(4)),
+                transaction_id: 1,
+            },
+        );
+        assert_eq!(
+            response,
+            Response::Transaction(Ok(Transaction {
+                id: 1,
+                amount: unwrap!(Coins::from_nano(4)),
+            }))
+        );
+
+        let request = Request::TransferCoins {
+            destination: *receiver.public_id().name(),
+            amount: unwrap!(Coins::from_nano(7)),
+            transaction_id: 2,
+        };
+        let response = send(&mut vault, &sender, request);
+        assert_eq!(
+            response,
+            Response::Transaction(Err(Error::InsufficientBalance))
+        );
+
+        let response = send(&mut vault, &receiver, Request::GetBalance);
+        assert_eq!(
+            response,
+            Response::GetBalance(Ok(unwrap!(Coins::from_nano(4))))
+        );
+        assert_eq!(
+            vault.balance(sender_key),
+            Some(unwrap!(Coins::from_nano(6)))
+        );
+
+        // a transfer to yourself leaves the balance unchanged
+        let request = Request::TransferCoins {
+            destination: *sender.public_id().name(),
+            amount: unwrap!(Coins::from_nano(5)),
+            transaction_id: 3,
+        };
+        assert_eq!(
+            send(&mut vault, &sender, request),
+            Response::Transaction(Ok(Transaction {
+                id: 3,
+                amount: unwrap!(Coins::from_nano(5)),
+            }))
+        );
+        assert_eq!(
+            vault.balance(sender_key),
+            Some(unwrap!(Coins::from_nano(6)))
+        );
+
+        // a failed credit is rolled back
+        let request = Request::TransferCoins {
+            destination: rand::random(),
+            amount: unwrap!(Coins::from_nano(1)),
+            transaction_id: 4,
+        };
+        assert_eq!(
+            send(&mut vault, &sender, request),
+            Response::Transaction(Err(Error::NoSuchBalance))
+        );
+        assert_eq!(
+            vault.balance(sender_key),
+            Some(unwrap!(Coins::from_nano(6)))
+        );
+    }
+
+    #[test]
+    fn create_login_packet_for() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let sender = ClientFullId::new_ed25519(&mut rng);
+        let sender_key = *sender.public_id().public_key();
+        let new_owner = *ClientFullId::new_ed25519(&mut rng).public_id().public_key();
+        unwrap!(vault.create_balance(sender_key, unwrap!(Coins::from_nano(10))));
+
+        // `LoginPacket::new` rejects oversized data, so build the packet from its serialised form.
+        let data = vec![0; MAX_LOGIN_PACKET_BYTES + 1];
+        let signature = sender.sign(&data);
+        let destination: XorName = rand::random();
+        let serialised = unwrap!(bincode::serialize(&(
+            destination,
+            new_owner,
+            data,
+            signature
+        )));
+        let login_packet: LoginPacket = unwrap!(bincode::deserialize(&serialised));
+        let request = Request::CreateLoginPacketFor {
+            new_owner,
+            amount: unwrap!(Coins::from_nano(4)),
+            transaction_id: 1,
+            new_login_packet: login_packet,
+        };
+        assert_eq!(
+            send(&mut vault, &sender, request),
+            Response::Mutation(Err(Error::ExceededSize))
+        );
+        assert_eq!(
+            vault.balance(sender_key),
+            Some(unwrap!(Coins::from_nano(10)))
+        );
+        assert_eq!(vault.balance(new_owner), None);
+    }
+
+    #[test]
+    fn unsigned_mutation() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let client = ClientFullId::new_ed25519(&mut rng);
+        let requester = PublicId::Client(client.public_id().clone());
+
+        let message = Message::Request {
+            request: Request::PutIData(PubImmutableData::new(vec![1]).into()),
+            message_id: MessageId::new(),
+            signature: None,
+            capability: None,
+        };
+        match unwrap!(vault.process_request(message, &requester)) {
+            Message::Response { response, .. } => {
+                assert_eq!(response, Response::Mutation(Err(Error::InvalidSignature)))
+            }
+            message => panic!("Unexpected message: {:?}", message.message_id()),
+        }
+    }
+
+    #[test]
+    fn foreign_network_requests() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::with_network_id(NetworkId([2; 32]));
+        let client = ClientFullId::new_ed25519(&mut rng);
+        let requester = PublicId::Client(client.public_id().clone());
+        let data = IData::from(UnpubImmutableData::new(
+            vec![1, 2, 3],
+            *client.public_id().public_key(),
+        ));
+
+        // A request signed for another network is rejected, even though the signature is valid
+        // there.
+        let foreign = SigningContext::new(NetworkId([1; 32]));
+        let message = foreign.new_signed_request(&client, Request::PutIData(data.clone()));
+        unwrap!(foreign.verify_request(&message, &requester));
+        match unwrap!(vault.process_request(message, &requester)) {
+            Message::Response { response, .. } => {
+                assert_eq!(response, Response::Mutation(Err(Error::InvalidSignature)))
+            }
+            message => panic!("Unexpected message: {:?}", message.message_id()),
+        }
+
+        let response = send(&mut vault, &client, Request::PutIData(data));
+        assert_eq!(response, Response::Mutation(Ok(())));
+    }
+
+    #[test]
+    fn unauthorised_app() {
+        let mut rng = rand::thread_rng();
+        let mut vault = MockVault::new();
+        let client = ClientFullId::new_ed25519(&mut rng);
+        let app = AppFullId::new_ed25519(&mut rng, client.public_id().clone());
+        let app_key = *app.public_id().public_key();
+        let requester = PublicId::App(app.public_id().clone());
+
+        let context = SigningContext::new(NetworkId::default());
+        let get_balance = || Message::new_signed_request(&context, &app, Request::GetBalance);
+
+        let response = unwrap!(vault.process_request(get_balance(), &requester));
+        match response {
+            Message::Response { response, .. } => {
+                assert_eq!(response, Response::GetBalance(Err(Error::AccessDenied)))
+            }
+            message => panic!("Unexpected message: {:?}", message.message_id()),
+        }
+
+        let response = send(
+            &mut vault,
+            &client,
+            Request::InsAuthKey {
+                key: app_key,
+                version: 1,
+                permissions: AppPermissions::default(),
+            },
+        );
+        assert_eq!(response, Response::Mutation(Ok(())));
+
+        let response = unwrap!(vault.process_request(get_balance(), &requester));
+        match response {
+            Message::Response { response, .. } => {
+                assert_eq!(response, Response::GetBalance(Err(Error::NoSuchBalance)))
+            }
+            message => panic!("Unexpected message: {:?}", message.message_id()),
+        }
+    }
+}
@@ -27,16 +27,34 @@
 //! does not have to pass version numbers for keys, but it still must pass the next version number
 //! while modifying the MutableData shell.
 
-use crate::{utils, EntryError, Error, PublicKey, Result, XorName};
+use crate::{
+    group::resolve_groups, utils, Capability, DataAddress, EntryError, Error, Group, GroupName,
+    PublicKey, Result, SigningContext, XorName,
+};
+use bincode::serialized_size;
 use hex_fmt::HexFmt;
 use multibase::Decodable;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    borrow::Borrow,
+    collections::{
+        btree_map::{self, Entry},
+        BTreeMap, BTreeSet,
+    },
     fmt::{self, Debug, Formatter},
-    mem,
+    iter, mem,
+    ops::{Bound, RangeBounds},
 };
 
+/// Maximum number of entries in a MutableData.
+pub const MAX_MDATA_ENTRIES: u64 = 1000;
+/// Maximum size of a MutableData entry key in bytes.
+pub const MAX_MDATA_KEY_SIZE_IN_BYTES: u64 = 1024;
+/// Maximum size of a MutableData entry value in bytes.
+pub const MAX_MDATA_VALUE_SIZE_IN_BYTES: u64 = 1024 * 1024;
+/// Maximum allowed size for a serialised MutableData to grow to.
+pub const MAX_MDATA_SIZE_IN_BYTES: u64 = 1024 * 1024 + 10 * 1024;
+
 /// MutableData that is unpublished on the network. This data can only be fetched by the owner or
 /// those in the permissions fields with `Permission::Read` access.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
@@ -44,9 +62,18 @@ pub struct SeqMutableData {
     /// Network address.
     address: Address,
     /// Key-Value semantics.
+    #[serde(with = "crate::human_readable::byte_keys")]
     data: SeqEntries,
-    /// Maps an application key to a list of allowed or forbidden actions.
-    permissions: BTreeMap<PublicKey, PermissionSet>,
+    /// Versions of the deleted entries, which new entries under the same key have to succeed.
+    #[serde(with = "crate::human_readable::byte_keys")]
+    tombstones: BTreeMap<Vec<u8>, u64>,
+    /// Maps an application key or a group to a list of allowed or forbidden actions.
+    permissions: BTreeMap<User, PermissionSet>,
+    /// Named groups of users, which can be given permissions as a whole.
+    groups: BTreeMap<GroupName, Group>,
+    /// Permissions overriding `permissions` for the entries whose key starts with a given prefix.
+    #[serde(with = "crate::human_readable::byte_keys")]
+    entry_permissions: BTreeMap<Vec<u8>, BTreeMap<User, PermissionSet>>,
     /// Version should be increased for any changes to MutableData fields except for data.
     version: u64,
     /// Contains the public key of an owner or owners of this data.
@@ -68,9 +95,15 @@ pub struct UnseqMutableData {
     /// Network address.
     address: Address,
     /// Key-Value semantics.
+    #[serde(with = "crate::human_readable::byte_map")]
     data: UnseqEntries,
-    /// Maps an application key to a list of allowed or forbidden actions.
-    permissions: BTreeMap<PublicKey, PermissionSet>,
+    /// Maps an application key or a group to a list of allowed or forbidden actions.
+    permissions: BTreeMap<User, PermissionSet>,
+    /// Named groups of users, which can be given permissions as a whole.
+    groups: BTreeMap<GroupName, Group>,
+    /// Permissions overriding `permissions` for the entries whose key starts with a given prefix.
+    #[serde(with = "crate::human_readable::byte_keys")]
+    entry_permissions: BTreeMap<Vec<u8>, BTreeMap<User, PermissionSet>>,
     /// Version should be increased for any changes to MutableData fields except for data.
     version: u64,
     /// Contains the public key of an owner or owners of this data.
@@ -89,6 +122,7 @@ impl Debug for UnseqMutableData {
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct SeqValue {
     /// Actual data.
+    #[serde(with = "crate::human_readable::bytes")]
     pub data: Vec<u8>,
     /// Version, incremented sequentially for any change to `data`.
     pub version: u64,
@@ -100,13 +134,120 @@ impl Debug for SeqValue {
     }
 }
 
+// Size of an entry value, as limited by `MAX_MDATA_VALUE_SIZE_IN_BYTES`.
+trait ValueSize {
+    fn value_size(&self) -> usize;
+}
+
+impl ValueSize for Vec<u8> {
+    fn value_size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ValueSize for SeqValue {
+    fn value_size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+// Returns `EntryError::ExceededSize` for each of `entries` whose key or value is too large.
+fn entry_size_errors<'a, V: ValueSize + 'a>(
+    entries: impl Iterator<Item = (&'a Vec<u8>, &'a V)>,
+) -> BTreeMap<Vec<u8>, EntryError> {
+    entries
+        .filter(|(key, value)| {
+            key.len() as u64 > MAX_MDATA_KEY_SIZE_IN_BYTES
+                || value.value_size() as u64 > MAX_MDATA_VALUE_SIZE_IN_BYTES
+        })
+        .map(|(key, _)| (key.clone(), EntryError::ExceededSize))
+        .collect()
+}
+
+// Effect of a batch of entry actions on the number of entries and the serialised size of a
+// MutableData.
+struct LimitCheck {
+    len: usize,
+    size: u64,
+    added: u64,
+    removed: u64,
+    inserted: Vec<Vec<u8>>,
+    written: Vec<Vec<u8>>,
+}
+
+impl LimitCheck {
+    fn new(len: usize, size: u64) -> Self {
+        Self {
+            len,
+            size,
+            added: 0,
+            removed: 0,
+            inserted: Vec::new(),
+            written: Vec::new(),
+        }
+    }
+
+    fn insert<V: Serialize>(&mut self, key: &[u8], value: &V) {
+        self.len += 1;
+        self.add(key, value);
+        self.inserted.push(key.to_vec());
+        self.written.push(key.to_vec());
+    }
+
+    fn update<V: Serialize>(&mut self, key: &[u8], current: &V, value: &V) {
+        self.remove(key, current);
+        self.add(key, value);
+        self.written.push(key.to_vec());
+    }
+
+    fn delete<V: Serialize>(&mut self, key: &[u8], current: &V) {
+        self.len -= 1;
+        self.remove(key, current);
+    }
+
+    // Accounts for a serialised key-value pair, of an entry or a tombstone, being added.
+    fn add<V: Serialize>(&mut self, key: &[u8], value: &V) {
+        self.added = self
+            .added
+            .saturating_add(serialized_size(&(key, value)).unwrap_or(u64::MAX));
+    }
+
+    // Accounts for a serialised key-value pair, of an entry or a tombstone, being removed.
+    fn remove<V: Serialize>(&mut self, key: &[u8], value: &V) {
+        self.removed += serialized_size(&(key, value)).unwrap_or(0);
+    }
+
+    // Adds the limit errors to the per-key `errors`, and returns them if there are any.
+    fn check(self, mut errors: BTreeMap<Vec<u8>, EntryError>) -> Result<()> {
+        if self.len as u64 > MAX_MDATA_ENTRIES {
+            for key in self.inserted {
+                let _ = errors.entry(key).or_insert(EntryError::TooManyEntries);
+            }
+        }
+        if !errors.is_empty() {
+            return Err(Error::InvalidEntryActions(errors));
+        }
+        if (self.size.saturating_add(self.added)).saturating_sub(self.removed)
+            > MAX_MDATA_SIZE_IN_BYTES
+        {
+            return Err(Error::InvalidEntryActions(
+                self.written
+                    .into_iter()
+                    .map(|key| (key, EntryError::ExceededSize))
+                    .collect(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Wrapper type for values, which can be sequenced or unsequenced.
 #[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub enum Value {
     /// Sequenced value.
     Seq(SeqValue),
     /// Unsequenced value.
-    Unseq(Vec<u8>),
+    Unseq(#[serde(with = "crate::human_readable::bytes")] Vec<u8>),
 }
 
 impl From<SeqValue> for Value {
@@ -127,7 +268,7 @@ pub enum Values {
     /// List of sequenced values.
     Seq(Vec<SeqValue>),
     /// List of unsequenced values.
-    Unseq(Vec<Vec<u8>>),
+    Unseq(#[serde(with = "crate::human_readable::byte_list")] Vec<Vec<u8>>),
 }
 
 impl From<Vec<SeqValue>> for Values {
@@ -189,8 +330,178 @@ pub enum Action {
     ManagePermissions,
 }
 
+/// The outcome of a permission check, and the reason for it.
+///
+/// Returned by `explain_access`, which runs the same logic as `check_permissions`, and by
+/// `explain_entry_access`, which runs the same logic as `check_entry_permissions`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessDecision {
+    /// The requester is the owner.
+    Owner,
+    /// The permission set of `user` allows the action. `user` is either the requester's key or a
+    /// group the requester belongs to.
+    Granted {
+        /// The user whose permission set decided the outcome.
+        user: User,
+        /// The prefix of the entry permissions which decided the outcome, or `None` if the
+        /// permissions for the whole data did.
+        prefix: Option<Vec<u8>>,
+    },
+    /// The permission set of `user` doesn't allow the action. `user` is either the requester's
+    /// key or a group the requester belongs to.
+    Denied {
+        /// The user whose permission set decided the outcome.
+        user: User,
+        /// The prefix of the entry permissions which decided the outcome, or `None` if the
+        /// permissions for the whole data did.
+        prefix: Option<Vec<u8>>,
+    },
+    /// The requester isn't the owner and neither they nor any of their groups has a permission
+    /// set.
+    NoPermissionSet,
+}
+
+impl AccessDecision {
+    /// Returns true if the action is allowed.
+    pub fn is_allowed(&self) -> bool {
+        match self {
+            AccessDecision::Owner | AccessDecision::Granted { .. } => true,
+            AccessDecision::Denied { .. } | AccessDecision::NoPermissionSet => false,
+        }
+    }
+
+    fn from_resolved(resolved: Option<(User, bool)>, prefix: Option<Vec<u8>>) -> Self {
+        match resolved {
+            Some((user, true)) => AccessDecision::Granted { user, prefix },
+            Some((user, false)) => AccessDecision::Denied { user, prefix },
+            None => AccessDecision::NoPermissionSet,
+        }
+    }
+
+    /// Converts the decision into the result returned by `check_permissions`.
+    pub fn into_result(self) -> Result<()> {
+        if self.is_allowed() {
+            Ok(())
+        } else {
+            Err(Error::AccessDenied)
+        }
+    }
+}
+
+/// A user, or group of users, which can be given permissions.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum User {
+    /// User identified by its public key.
+    Key(PublicKey),
+    /// Every member of the named group.
+    Group(GroupName),
+}
+
+impl From<PublicKey> for User {
+    fn from(key: PublicKey) -> Self {
+        User::Key(key)
+    }
+}
+
+// Start and end bounds of a range of keys.
+type KeyBounds<'a> = (Bound<&'a [u8]>, Bound<&'a [u8]>);
+
+/// Selection of entries by key, used when listing the entries a page at a time.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum KeyFilter {
+    /// All entries.
+    All,
+    /// Entries whose key is in the range from `start` (inclusive) to `end` (exclusive).
+    Range {
+        /// First key of the range, or unbounded if `None`.
+        start: Option<Vec<u8>>,
+        /// Key ending the range, or unbounded if `None`.
+        end: Option<Vec<u8>>,
+    },
+    /// Entries whose key starts with the given prefix.
+    Prefix(Vec<u8>),
+}
+
+impl KeyFilter {
+    /// Returns true if `key` is selected by this filter.
+    pub fn matches(&self, key: &[u8]) -> bool {
+        match self {
+            KeyFilter::All => true,
+            KeyFilter::Range { start, end } => {
+                start.iter().all(|start| key >= start.as_slice())
+                    && end.iter().all(|end| key < end.as_slice())
+            }
+            KeyFilter::Prefix(prefix) => key.starts_with(prefix),
+        }
+    }
+
+    // Returns the bounds of the keys selected by this filter which come after `cursor`, or `None`
+    // if there can't be any such keys.
+    fn bounds<'a>(&'a self, cursor: Option<&'a [u8]>) -> Option<KeyBounds<'a>> {
+        let (start, end) = match self {
+            KeyFilter::All => (Bound::Unbounded, Bound::Unbounded),
+            KeyFilter::Range { start, end } => (
+                start
+                    .as_ref()
+                    .map_or(Bound::Unbounded, |start| Bound::Included(start.as_slice())),
+                end.as_ref()
+                    .map_or(Bound::Unbounded, |end| Bound::Excluded(end.as_slice())),
+            ),
+            KeyFilter::Prefix(prefix) => (Bound::Included(prefix.as_slice()), Bound::Unbounded),
+        };
+        let start = match (start, cursor) {
+            (Bound::Included(start), Some(cursor)) if cursor < start => Bound::Included(start),
+            (_, Some(cursor)) => Bound::Excluded(cursor),
+            (start, None) => start,
+        };
+        match (start, end) {
+            (Bound::Included(start), Bound::Excluded(end))
+            | (Bound::Excluded(start), Bound::Excluded(end))
+                if start >= end =>
+            {
+                None
+            }
+            bounds => Some(bounds),
+        }
+    }
+}
+
+// Returns the entries of `data` selected by `filter` which come after `cursor`.
+fn filter_entries<'a, V>(
+    data: &'a BTreeMap<Vec<u8>, V>,
+    filter: &'a KeyFilter,
+    cursor: Option<&'a [u8]>,
+) -> impl Iterator<Item = (&'a Vec<u8>, &'a V)> + 'a {
+    filter
+        .bounds(cursor)
+        .map(|bounds| data.range::<[u8], _>(bounds))
+        .into_iter()
+        .flatten()
+        .take_while(move |(key, _)| filter.matches(key))
+}
+
+// Collects up to `limit` of the `entries`, along with the key to continue after if any remain.
+fn page<'a, V: Clone + 'a>(
+    mut entries: impl Iterator<Item = (&'a Vec<u8>, &'a V)>,
+    limit: u64,
+) -> (BTreeMap<Vec<u8>, V>, Option<Vec<u8>>) {
+    let page: BTreeMap<_, _> = entries
+        .by_ref()
+        .take(limit as usize)
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let next = if entries.next().is_some() {
+        page.keys().next_back().cloned()
+    } else {
+        None
+    };
+    (page, next)
+}
+
 macro_rules! impl_mutable_data {
-    ($flavour:ident) => {
+    // `$value` is the type of the entry values, and `$keyed` are the fields other than `data`
+    // which are keyed by the entry key.
+    ($flavour:ident, $value:ty $(, $keyed:ident)*) => {
         impl $flavour {
             /// Returns the address.
             pub fn address(&self) -> &Address {
@@ -227,25 +538,173 @@ macro_rules! impl_mutable_data {
                 self.data.keys().cloned().collect()
             }
 
+            /// Returns size of this data after serialisation.
+            pub fn serialised_size(&self) -> u64 {
+                serialized_size(self).unwrap_or(u64::MAX)
+            }
+
+            /// Returns true if the size is valid.
+            pub fn validate_size(&self) -> bool {
+                self.serialised_size() <= MAX_MDATA_SIZE_IN_BYTES
+            }
+
+            /// Checks the entries against the MutableData limits.
+            ///
+            /// Returns `Err(Error::InvalidEntryActions)` with `EntryError::TooManyEntries` for the
+            /// entries beyond `MAX_MDATA_ENTRIES` and `EntryError::ExceededSize` for the entries
+            /// which are too large, or `Err(Error::ExceededSize)` if the whole data is too large.
+            pub fn validate_limits(&self) -> Result<()> {
+                let mut errors = entry_size_errors(self.data.iter());
+                for key in self.data.keys().skip(MAX_MDATA_ENTRIES as usize) {
+                    let _ = errors
+                        .entry(key.clone())
+                        .or_insert(EntryError::TooManyEntries);
+                }
+                if !errors.is_empty() {
+                    return Err(Error::InvalidEntryActions(errors));
+                }
+                if !self.validate_size() {
+                    return Err(Error::ExceededSize);
+                }
+                Ok(())
+            }
+
             /// Returns the shell of this MutableData (the fields without the data).
             pub fn shell(&self) -> Self {
                 Self {
                     address: self.address.clone(),
                     data: BTreeMap::new(),
+                    $($keyed: BTreeMap::new(),)*
                     permissions: self.permissions.clone(),
+                    groups: self.groups.clone(),
+                    entry_permissions: self.entry_permissions.clone(),
                     version: self.version,
                     owner: self.owner,
                 }
             }
 
             /// Gets a complete list of permissions.
-            pub fn permissions(&self) -> BTreeMap<PublicKey, PermissionSet> {
+            pub fn permissions(&self) -> BTreeMap<User, PermissionSet> {
                 self.permissions.clone()
             }
 
             /// Gets the permissions for the provided user.
-            pub fn user_permissions(&self, user: PublicKey) -> Result<&PermissionSet> {
-                self.permissions.get(&user).ok_or(Error::NoSuchKey)
+            pub fn user_permissions(&self, user: impl Into<User>) -> Result<&PermissionSet> {
+                self.permissions.get(&user.into()).ok_or(Error::NoSuchKey)
+            }
+
+            /// Returns the per-entry permissions, keyed by the key prefix they apply to.
+            pub fn entry_permissions(&self) -> &BTreeMap<Vec<u8>, BTreeMap<User, PermissionSet>> {
+                &self.entry_permissions
+            }
+
+            /// Returns the groups.
+            pub fn groups(&self) -> &BTreeMap<GroupName, Group> {
+                &self.groups
+            }
+
+            /// Returns the group with the given name.
+            pub fn group(&self, name: &str) -> Result<&Group> {
+                self.groups.get(name).ok_or(Error::NoSuchKey)
+            }
+
+            /// Adds a member to a group, creating the group if it doesn't exist.
+            ///
+            /// Requires the new `version` of the group, which is independent of the version of
+            /// the MutableData fields. If it does not match the current version of the group + 1,
+            /// an error will be returned.
+            pub fn add_group_member(
+                &mut self,
+                group: GroupName,
+                member: PublicKey,
+                version: u64,
+            ) -> Result<()> {
+                match self.groups.entry(group) {
+                    Entry::Occupied(mut entry) => entry.get_mut().add_member(member, version),
+                    Entry::Vacant(entry) => {
+                        let mut group = Group::new();
+                        group.add_member(member, version)?;
+                        let _ = entry.insert(group);
+                        Ok(())
+                    }
+                }
+            }
+
+            /// Adds a member to a group on behalf of `requester`. Other users than the owner need
+            /// `ManagePermissions` and must themselves hold every action the group is allowed,
+            /// map-wide and on the entries of each prefix, since the new member gains them all.
+            ///
+            /// Requires the new `version` of the group, which is independent of the version of
+            /// the MutableData fields. If it does not match the current version of the group + 1,
+            /// an error will be returned.
+            pub fn add_group_member_checked(
+                &mut self,
+                group: GroupName,
+                member: PublicKey,
+                version: u64,
+                requester: PublicKey,
+            ) -> Result<()> {
+                if self.owner != requester {
+                    self.check_permissions(Action::ManagePermissions, requester)?;
+                    let user = User::Group(group.clone());
+                    let escalates = self
+                        .permissions
+                        .get(&user)
+                        .into_iter()
+                        .flat_map(|permissions| &permissions.permissions)
+                        .any(|action| !self.is_action_allowed(&requester, *action))
+                        || self.entry_permissions.iter().any(|(prefix, permissions)| {
+                            permissions
+                                .get(&user)
+                                .into_iter()
+                                .flat_map(|permissions| &permissions.permissions)
+                                .any(|action| {
+                                    !self.is_entry_action_allowed(&requester, prefix, *action)
+                                })
+                        });
+                    if escalates {
+                        return Err(Error::PrivilegeEscalation);
+                    }
+                }
+                self.add_group_member(group, member, version)
+            }
+
+            /// Removes a member from a group.
+            ///
+            /// Requires the new `version` of the group, which is independent of the version of
+            /// the MutableData fields. If it does not match the current version of the group + 1,
+            /// an error will be returned.
+            pub fn remove_group_member(
+                &mut self,
+                group: &str,
+                member: &PublicKey,
+                version: u64,
+            ) -> Result<()> {
+                self.groups
+                    .get_mut(group)
+                    .ok_or(Error::NoSuchKey)?
+                    .remove_member(member, version)
+            }
+
+            /// Removes a member from a group on behalf of `requester`, after checking the removal
+            /// with `check_permissions_removal`, as the member may fall back to broader
+            /// permissions.
+            ///
+            /// Requires the new `version` of the group, which is independent of the version of
+            /// the MutableData fields. If it does not match the current version of the group + 1,
+            /// an error will be returned.
+            pub fn remove_group_member_checked(
+                &mut self,
+                group: &str,
+                member: &PublicKey,
+                version: u64,
+                requester: PublicKey,
+            ) -> Result<()> {
+                let keys = iter::once(*member).collect();
+                self.check_permissions_removal(keys, requester, |data| {
+                    data.remove_group_member(group, member, version)
+                })?;
+                self.remove_group_member(group, member, version)
             }
 
             /// Checks if the provided user is an owner.
@@ -264,17 +723,211 @@ macro_rules! impl_mutable_data {
             ///
             /// Returns `Err(Error::AccessDenied)` if the permission check has failed.
             pub fn check_permissions(&self, action: Action, requester: PublicKey) -> Result<()> {
+                self.explain_access(requester, action).into_result()
+            }
+
+            /// Checks that `capability` allows the provided user to perform `action` at time
+            /// `now`, in seconds since the UNIX epoch, on the network of `context`. A valid
+            /// capability is an alternative to a permission entry.
+            pub fn check_capability(
+                &self,
+                context: &SigningContext,
+                capability: &Capability,
+                action: Action,
+                requester: PublicKey,
+                now: u64,
+            ) -> Result<()> {
+                capability.verify(
+                    context,
+                    &self.owner,
+                    &DataAddress::Mutable(self.address),
+                    &requester,
+                    action.into(),
+                    now,
+                )
+            }
+
+            /// Explains the outcome of `check_permissions` for given `action` and the provided
+            /// user.
+            pub fn explain_access(&self, requester: PublicKey, action: Action) -> AccessDecision {
+                if self.owner == requester {
+                    return AccessDecision::Owner;
+                }
+
+                AccessDecision::from_resolved(
+                    self.resolve_permissions(&self.permissions, requester, action),
+                    None,
+                )
+            }
+
+            /// Explains the outcome of `check_entry_permissions` for given `action` on the entry
+            /// with the given `key` and the provided user.
+            ///
+            /// The entry permissions with the longest prefix of `key` that apply to the user
+            /// override the permissions for the whole data.
+            pub fn explain_entry_access(
+                &self,
+                requester: PublicKey,
+                key: &[u8],
+                action: Action,
+            ) -> AccessDecision {
                 if self.owner == requester {
+                    return AccessDecision::Owner;
+                }
+
+                // Prefixes of `key` sort before it, longer ones after shorter ones.
+                let resolved = self
+                    .entry_permissions
+                    .range::<[u8], _>((Bound::Unbounded, Bound::Included(key)))
+                    .rev()
+                    .filter(|(prefix, _)| key.starts_with(prefix))
+                    .find_map(|(prefix, permissions)| {
+                        self.resolve_permissions(permissions, requester, action)
+                            .map(|resolved| (prefix.clone(), resolved))
+                    });
+                match resolved {
+                    Some((prefix, resolved)) => {
+                        AccessDecision::from_resolved(Some(resolved), Some(prefix))
+                    }
+                    None => self.explain_access(requester, action),
+                }
+            }
+
+            /// Checks permissions for given `action` on the entry with the given `key` for the
+            /// provided user.
+            ///
+            /// Returns `Err(Error::AccessDenied)` if the permission check has failed.
+            pub fn check_entry_permissions(
+                &self,
+                key: &[u8],
+                action: Action,
+                requester: PublicKey,
+            ) -> Result<()> {
+                self.explain_entry_access(requester, key, action)
+                    .into_result()
+            }
+
+            /// Checks that the provided user is allowed `action` on the whole data, or on the
+            /// entries of at least one prefix.
+            ///
+            /// Returns `Err(Error::AccessDenied)` if the permission check has failed.
+            pub fn check_any_entry_permissions(
+                &self,
+                action: Action,
+                requester: PublicKey,
+            ) -> Result<()> {
+                let allowed_on_prefix = self.entry_permissions.values().any(|permissions| {
+                    self.resolve_permissions(permissions, requester, action)
+                        .map(|(_, allowed)| allowed)
+                        == Some(true)
+                });
+                if allowed_on_prefix || self.is_action_allowed(&requester, action) {
                     Ok(())
                 } else {
-                    let permissions = self
-                        .user_permissions(requester)
-                        .map_err(|_| Error::AccessDenied)?;
-                    if permissions.is_allowed(action) {
-                        Ok(())
-                    } else {
-                        Err(Error::AccessDenied)
-                    }
+                    Err(Error::AccessDenied)
+                }
+            }
+
+            /// Returns the entries with keys in the given range.
+            ///
+            /// Panics if the range start is greater than the range end, like `BTreeMap::range`.
+            pub fn range<T, R>(&self, range: R) -> btree_map::Range<'_, Vec<u8>, $value>
+            where
+                T: Ord + ?Sized,
+                Vec<u8>: Borrow<T>,
+                R: RangeBounds<T>,
+            {
+                self.data.range(range)
+            }
+
+            /// Returns the entries whose key starts with `prefix`.
+            pub fn entries_with_prefix<'a>(
+                &'a self,
+                prefix: &'a [u8],
+            ) -> impl Iterator<Item = (&'a Vec<u8>, &'a $value)> + 'a {
+                self.data
+                    .range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+                    .take_while(move |(key, _)| key.starts_with(prefix))
+            }
+
+            /// Returns the entries selected by `filter` whose key comes after `cursor`.
+            pub fn filter_entries<'a>(
+                &'a self,
+                filter: &'a KeyFilter,
+                cursor: Option<&'a [u8]>,
+            ) -> impl Iterator<Item = (&'a Vec<u8>, &'a $value)> + 'a {
+                filter_entries(&self.data, filter, cursor)
+            }
+
+            /// Returns up to `limit` of the entries selected by `filter` whose key comes after
+            /// `cursor`, along with the cursor for the next page, or `None` if this is the last
+            /// page.
+            pub fn entries_page(
+                &self,
+                filter: &KeyFilter,
+                limit: u64,
+                cursor: Option<&[u8]>,
+            ) -> (BTreeMap<Vec<u8>, $value>, Option<Vec<u8>>) {
+                page(filter_entries(&self.data, filter, cursor), limit)
+            }
+
+            /// Like `entries_page`, but skips the entries the provided user isn't allowed to
+            /// read.
+            pub fn readable_entries_page(
+                &self,
+                filter: &KeyFilter,
+                limit: u64,
+                cursor: Option<&[u8]>,
+                requester: PublicKey,
+            ) -> (BTreeMap<Vec<u8>, $value>, Option<Vec<u8>>) {
+                page(
+                    filter_entries(&self.data, filter, cursor).filter(|(key, _)| {
+                        self.is_entry_action_allowed(&requester, key, Action::Read)
+                    }),
+                    limit,
+                )
+            }
+
+            /// Returns a copy of this MutableData holding only the entries the provided user is
+            /// allowed to read.
+            pub fn readable_by(&self, requester: PublicKey) -> Self {
+                let mut data = self.shell();
+                data.data = self
+                    .data
+                    .iter()
+                    .filter(|(key, _)| self.is_entry_action_allowed(&requester, key, Action::Read))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                $(
+                    data.$keyed = self
+                        .$keyed
+                        .iter()
+                        .filter(|(key, _)| {
+                            self.is_entry_action_allowed(&requester, key, Action::Read)
+                        })
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect();
+                )*
+                data
+            }
+
+            /// Finds the entry in `permissions` which applies to the provided user, either
+            /// directly or through a group.
+            fn resolve_permissions(
+                &self,
+                permissions: &BTreeMap<User, PermissionSet>,
+                requester: PublicKey,
+                action: Action,
+            ) -> Option<(User, bool)> {
+                let user = User::Key(requester);
+                match permissions.get(&user) {
+                    Some(permissions) => Some((user, permissions.is_allowed(action))),
+                    None => resolve_groups(&self.groups, &requester, |name| {
+                        permissions
+                            .get(&User::Group(name.clone()))
+                            .map(|permissions| permissions.is_allowed(action))
+                    })
+                    .map(|(name, allowed)| (User::Group(name), allowed)),
                 }
             }
 
@@ -284,7 +937,7 @@ macro_rules! impl_mutable_data {
             /// current version + 1, an error will be returned.
             pub fn set_user_permissions(
                 &mut self,
-                user: PublicKey,
+                user: impl Into<User>,
                 permissions: PermissionSet,
                 version: u64,
             ) -> Result<()> {
@@ -292,124 +945,371 @@ macro_rules! impl_mutable_data {
                     return Err(Error::InvalidSuccessor(self.version));
                 }
 
-                let _prev = self.permissions.insert(user, permissions);
+                let _prev = self.permissions.insert(user.into(), permissions);
                 self.version = version;
 
                 Ok(())
             }
 
-            /// Deletes permissions for the provided user.
+            /// Checks that `requester` may give `permissions` to a user: the owner may grant
+            /// anything, other users need `ManagePermissions` and may only grant actions they are
+            /// allowed themselves.
+            ///
+            /// Returns `Err(Error::AccessDenied)` if the requester can't manage permissions and
+            /// `Err(Error::PrivilegeEscalation)` if the grant exceeds the requester's own rights.
+            pub fn check_permissions_grant(
+                &self,
+                permissions: &PermissionSet,
+                requester: PublicKey,
+            ) -> Result<()> {
+                if self.owner == requester {
+                    return Ok(());
+                }
+                self.check_permissions(Action::ManagePermissions, requester)?;
+                if permissions
+                    .permissions
+                    .iter()
+                    .all(|action| self.is_action_allowed(&requester, *action))
+                {
+                    Ok(())
+                } else {
+                    Err(Error::PrivilegeEscalation)
+                }
+            }
+
+            /// Inserts or updates permissions for the provided user on behalf of `requester`,
+            /// after checking them with `check_permissions_grant`.
+            ///
+            /// Requires the new `version` of the MutableData fields. If it does not match the
+            /// current version + 1, an error will be returned.
+            pub fn set_user_permissions_checked(
+                &mut self,
+                user: impl Into<User>,
+                permissions: PermissionSet,
+                version: u64,
+                requester: PublicKey,
+            ) -> Result<()> {
+                self.check_permissions_grant(&permissions, requester)?;
+                self.set_user_permissions(user, permissions, version)
+            }
+
+            /// Inserts or updates permissions for the provided user on the entries whose key
+            /// starts with `prefix`.
             ///
             /// Requires the new `version` of the MutableData fields. If it does not match the
             /// current version + 1, an error will be returned.
-            pub fn del_user_permissions(&mut self, user: PublicKey, version: u64) -> Result<()> {
+            pub fn set_entry_permissions(
+                &mut self,
+                prefix: Vec<u8>,
+                user: impl Into<User>,
+                permissions: PermissionSet,
+                version: u64,
+            ) -> Result<()> {
                 if version != self.version + 1 {
                     return Err(Error::InvalidSuccessor(self.version));
                 }
-                if !self.permissions.contains_key(&user) {
-                    return Err(Error::NoSuchKey);
-                }
 
-                let _ = self.permissions.remove(&user);
+                let _prev = self
+                    .entry_permissions
+                    .entry(prefix)
+                    .or_insert_with(BTreeMap::new)
+                    .insert(user.into(), permissions);
                 self.version = version;
 
                 Ok(())
             }
 
-            /// Deletes user permissions without performing any validation.
+            /// Inserts or updates permissions for the provided user on the entries whose key
+            /// starts with `prefix` on behalf of `requester`. Other users than the owner need
+            /// `ManagePermissions` and may only grant actions they are allowed on those entries.
             ///
             /// Requires the new `version` of the MutableData fields. If it does not match the
             /// current version + 1, an error will be returned.
-            pub fn del_user_permissions_without_validation(
+            pub fn set_entry_permissions_checked(
                 &mut self,
-                user: PublicKey,
+                prefix: Vec<u8>,
+                user: impl Into<User>,
+                permissions: PermissionSet,
                 version: u64,
-            ) -> bool {
-                if version <= self.version {
-                    return false;
+                requester: PublicKey,
+            ) -> Result<()> {
+                if self.owner != requester {
+                    self.check_permissions(Action::ManagePermissions, requester)?;
+                    if !permissions
+                        .permissions
+                        .iter()
+                        .all(|action| self.is_entry_action_allowed(&requester, &prefix, *action))
+                    {
+                        return Err(Error::PrivilegeEscalation);
+                    }
                 }
-
-                let _ = self.permissions.remove(&user);
-                self.version = version;
-
-                true
+                self.set_entry_permissions(prefix, user, permissions, version)
             }
 
-            /// Changes the owner.
+            /// Deletes permissions for the provided user on the entries whose key starts with
+            /// `prefix`.
             ///
             /// Requires the new `version` of the MutableData fields. If it does not match the
             /// current version + 1, an error will be returned.
-            pub fn change_owner(&mut self, new_owner: PublicKey, version: u64) -> Result<()> {
+            pub fn del_entry_permissions(
+                &mut self,
+                prefix: &[u8],
+                user: impl Into<User>,
+                version: u64,
+            ) -> Result<()> {
                 if version != self.version + 1 {
                     return Err(Error::InvalidSuccessor(self.version));
                 }
+                let permissions = self
+                    .entry_permissions
+                    .get_mut(prefix)
+                    .ok_or(Error::NoSuchKey)?;
+                if permissions.remove(&user.into()).is_none() {
+                    return Err(Error::NoSuchKey);
+                }
+                if permissions.is_empty() {
+                    let _ = self.entry_permissions.remove(prefix);
+                }
 
-                self.owner = new_owner;
                 self.version = version;
 
                 Ok(())
             }
 
-            /// Changes the owner without performing any validation.
+            /// Deletes permissions for the provided user on the entries whose key starts with
+            /// `prefix` on behalf of `requester`, after checking the deletion with
+            /// `check_permissions_removal`.
             ///
             /// Requires the new `version` of the MutableData fields. If it does not match the
             /// current version + 1, an error will be returned.
-            pub fn change_owner_without_validation(
+            pub fn del_entry_permissions_checked(
                 &mut self,
-                new_owner: PublicKey,
+                prefix: &[u8],
+                user: impl Into<User>,
                 version: u64,
-            ) -> bool {
-                if version <= self.version {
-                    return false;
-                }
-
-                self.owner = new_owner;
-                self.version = version;
+                requester: PublicKey,
+            ) -> Result<()> {
+                let user = user.into();
+                self.check_permissions_removal(self.keys_of(&user), requester, |data| {
+                    data.del_entry_permissions(prefix, user.clone(), version)
+                })?;
+                self.del_entry_permissions(prefix, user, version)
+            }
 
-                true
+            /// Checks that `requester` may make a change to the permissions or groups which lifts
+            /// restrictions on the users with the given keys, applying it to a copy of the shell
+            /// with `change`. The owner may make any change, other users need `ManagePermissions`
+            /// and the change mustn't give any of the users an action, on the whole data or on the
+            /// entries of a prefix, which the requester isn't allowed itself. In particular, users
+            /// can't lift restrictions on themselves, directly or through a group.
+            ///
+            /// Returns `Err(Error::AccessDenied)` if the requester can't manage permissions,
+            /// `Err(Error::PrivilegeEscalation)` if the change exceeds the requester's own rights
+            /// and otherwise the error returned by `change`, if any.
+            fn check_permissions_removal<F>(
+                &self,
+                keys: BTreeSet<PublicKey>,
+                requester: PublicKey,
+                change: F,
+            ) -> Result<()>
+            where
+                F: FnOnce(&mut Self) -> Result<()>,
+            {
+                if self.owner == requester {
+                    return Ok(());
+                }
+                self.check_permissions(Action::ManagePermissions, requester)?;
+                let mut changed = self.shell();
+                change(&mut changed)?;
+
+                let actions = [
+                    Action::Read,
+                    Action::Insert,
+                    Action::Update,
+                    Action::Delete,
+                    Action::ManagePermissions,
+                ];
+                let escalates = keys.iter().any(|key| {
+                    actions.iter().any(|&action| {
+                        (changed.is_action_allowed(key, action)
+                            && !self.is_action_allowed(key, action)
+                            && !self.is_action_allowed(&requester, action))
+                            || self.entry_permissions.keys().any(|prefix| {
+                                changed.is_entry_action_allowed(key, prefix, action)
+                                    && !self.is_entry_action_allowed(key, prefix, action)
+                                    && !self.is_entry_action_allowed(&requester, prefix, action)
+                            })
+                    })
+                });
+                if escalates {
+                    Err(Error::PrivilegeEscalation)
+                } else {
+                    Ok(())
+                }
             }
 
-            /// Returns true if `action` is allowed for the provided user.
-            pub fn is_action_allowed(&self, requester: &PublicKey, action: Action) -> bool {
-                match self.permissions.get(requester) {
-                    Some(perms) => perms.is_allowed(action),
-                    None => false,
+            // Returns the keys the permissions of `user` apply to.
+            fn keys_of(&self, user: &User) -> BTreeSet<PublicKey> {
+                match user {
+                    User::Key(key) => iter::once(*key).collect(),
+                    User::Group(name) => self
+                        .groups
+                        .get(name)
+                        .map(|group| group.members().clone())
+                        .unwrap_or_default(),
                 }
             }
-        }
-    };
-}
 
-impl_mutable_data!(SeqMutableData);
-impl_mutable_data!(UnseqMutableData);
+            /// Deletes permissions for the provided user.
+            ///
+            /// Requires the new `version` of the MutableData fields. If it does not match the
+            /// current version + 1, an error will be returned.
+            pub fn del_user_permissions(
+                &mut self,
+                user: impl Into<User>,
+                version: u64,
+            ) -> Result<()> {
+                if version != self.version + 1 {
+                    return Err(Error::InvalidSuccessor(self.version));
+                }
+                if self.permissions.remove(&user.into()).is_none() {
+                    return Err(Error::NoSuchKey);
+                }
 
-impl UnseqMutableData {
-    /// Creates a new unsequenced MutableData.
-    pub fn new(name: XorName, tag: u64, owner: PublicKey) -> Self {
+                self.version = version;
+
+                Ok(())
+            }
+
+            /// Deletes permissions for the provided user on behalf of `requester`, after checking
+            /// the deletion with `check_permissions_removal`.
+            ///
+            /// Requires the new `version` of the MutableData fields. If it does not match the
+            /// current version + 1, an error will be returned.
+            pub fn del_user_permissions_checked(
+                &mut self,
+                user: impl Into<User>,
+                version: u64,
+                requester: PublicKey,
+            ) -> Result<()> {
+                let user = user.into();
+                self.check_permissions_removal(self.keys_of(&user), requester, |data| {
+                    data.del_user_permissions(user.clone(), version)
+                })?;
+                self.del_user_permissions(user, version)
+            }
+
+            /// Deletes user permissions without performing any validation.
+            ///
+            /// Requires the new `version` of the MutableData fields. If it does not match the
+            /// current version + 1, an error will be returned.
+            pub fn del_user_permissions_without_validation(
+                &mut self,
+                user: impl Into<User>,
+                version: u64,
+            ) -> bool {
+                if version <= self.version {
+                    return false;
+                }
+
+                let _ = self.permissions.remove(&user.into());
+                self.version = version;
+
+                true
+            }
+
+            /// Changes the owner.
+            ///
+            /// Requires the new `version` of the MutableData fields. If it does not match the
+            /// current version + 1, an error will be returned.
+            pub fn change_owner(&mut self, new_owner: PublicKey, version: u64) -> Result<()> {
+                if version != self.version + 1 {
+                    return Err(Error::InvalidSuccessor(self.version));
+                }
+
+                self.owner = new_owner;
+                self.version = version;
+
+                Ok(())
+            }
+
+            /// Changes the owner without performing any validation.
+            ///
+            /// Requires the new `version` of the MutableData fields. If it does not match the
+            /// current version + 1, an error will be returned.
+            pub fn change_owner_without_validation(
+                &mut self,
+                new_owner: PublicKey,
+                version: u64,
+            ) -> bool {
+                if version <= self.version {
+                    return false;
+                }
+
+                self.owner = new_owner;
+                self.version = version;
+
+                true
+            }
+
+            /// Returns true if `action` is allowed for the provided user.
+            pub fn is_action_allowed(&self, requester: &PublicKey, action: Action) -> bool {
+                self.explain_access(*requester, action).is_allowed()
+            }
+
+            /// Returns true if `action` is allowed on the entry with the given `key` for the
+            /// provided user.
+            pub fn is_entry_action_allowed(
+                &self,
+                requester: &PublicKey,
+                key: &[u8],
+                action: Action,
+            ) -> bool {
+                self.explain_entry_access(*requester, key, action)
+                    .is_allowed()
+            }
+        }
+    };
+}
+
+impl_mutable_data!(SeqMutableData, SeqValue, tombstones);
+impl_mutable_data!(UnseqMutableData, Vec<u8>);
+
+impl UnseqMutableData {
+    /// Creates a new unsequenced MutableData.
+    pub fn new(name: XorName, tag: u64, owner: PublicKey) -> Self {
         Self {
             address: Address::Unseq { name, tag },
             data: Default::default(),
             permissions: Default::default(),
+            groups: Default::default(),
+            entry_permissions: Default::default(),
             version: 0,
             owner,
         }
     }
 
     /// Creates a new unsequenced MutableData with entries and permissions.
+    ///
+    /// Returns an error if the entries exceed the MutableData limits, see `validate_limits`.
     pub fn new_with_data(
         name: XorName,
         tag: u64,
         data: UnseqEntries,
-        permissions: BTreeMap<PublicKey, PermissionSet>,
+        permissions: BTreeMap<User, PermissionSet>,
         owner: PublicKey,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        let data = Self {
             address: Address::Unseq { name, tag },
             data,
             permissions,
+            groups: Default::default(),
+            entry_permissions: Default::default(),
             version: 0,
             owner,
-        }
+        };
+        data.validate_limits()?;
+        Ok(data)
     }
 
     /// Returns a value for the given key.
@@ -432,86 +1332,87 @@ impl UnseqMutableData {
         mem::replace(&mut self.data, BTreeMap::new())
     }
 
-    /// Mutates entries based on `actions` for the provided user.
+    /// Checks that `actions` can be applied by the provided user, without applying them.
     ///
-    /// Returns `Err(InvalidEntryActions)` if the mutation parameters are invalid.
-    pub fn mutate_entries(
-        &mut self,
-        actions: UnseqEntryActions,
+    /// Returns `Err(AccessDenied)` if any of the actions isn't allowed and
+    /// `Err(InvalidEntryActions)` if the mutation parameters are invalid.
+    pub fn validate_entries(
+        &self,
+        actions: &UnseqEntryActions,
         requester: PublicKey,
     ) -> Result<()> {
-        let (insert, update, delete) = actions.actions.into_iter().fold(
-            (
-                BTreeMap::<Vec<u8>, Vec<u8>>::new(),
-                BTreeMap::<Vec<u8>, Vec<u8>>::new(),
-                BTreeSet::<Vec<u8>>::new(),
-            ),
-            |(mut insert, mut update, mut delete), (key, item)| {
-                match item {
-                    UnseqEntryAction::Ins(value) => {
-                        let _ = insert.insert(key, value);
-                    }
-                    UnseqEntryAction::Update(value) => {
-                        let _ = update.insert(key, value);
-                    }
-                    UnseqEntryAction::Del => {
-                        let _ = delete.insert(key);
-                    }
-                };
-                (insert, update, delete)
-            },
-        );
-
-        if *self.owner() != requester
-            && ((!insert.is_empty() && !self.is_action_allowed(&requester, Action::Insert))
-                || (!update.is_empty() && !self.is_action_allowed(&requester, Action::Update))
-                || (!delete.is_empty() && !self.is_action_allowed(&requester, Action::Delete)))
-        {
+        let allowed = actions.actions.iter().all(|(key, action)| {
+            let action = match action {
+                UnseqEntryAction::Ins(_) => Action::Insert,
+                UnseqEntryAction::Update(_) => Action::Update,
+                UnseqEntryAction::Del => Action::Delete,
+            };
+            self.is_entry_action_allowed(&requester, key, action)
+        });
+        if !allowed {
             return Err(Error::AccessDenied);
         }
 
-        let mut new_data = self.data.clone();
-        let mut errors = BTreeMap::new();
-
-        for (key, val) in insert {
-            match new_data.entry(key) {
-                Entry::Occupied(entry) => {
-                    let _ = errors.insert(entry.key().clone(), EntryError::EntryExists(0));
+        let mut errors =
+            entry_size_errors(
+                actions
+                    .actions
+                    .iter()
+                    .filter_map(|(key, action)| match action {
+                        UnseqEntryAction::Ins(value) | UnseqEntryAction::Update(value) => {
+                            Some((key, value))
+                        }
+                        UnseqEntryAction::Del => None,
+                    }),
+            );
+        let mut limits = LimitCheck::new(self.data.len(), self.serialised_size());
+
+        for (key, action) in &actions.actions {
+            let error = match (action, self.data.get(key)) {
+                (UnseqEntryAction::Ins(_), Some(_)) => EntryError::EntryExists(0),
+                (UnseqEntryAction::Update(_), None) | (UnseqEntryAction::Del, None) => {
+                    EntryError::NoSuchEntry
                 }
-                Entry::Vacant(entry) => {
-                    let _ = entry.insert(val);
+                (UnseqEntryAction::Ins(value), None) => {
+                    limits.insert(key, value);
+                    continue;
                 }
-            }
-        }
-
-        for (key, val) in update {
-            match new_data.entry(key) {
-                Entry::Occupied(mut entry) => {
-                    let _ = entry.insert(val);
+                (UnseqEntryAction::Update(value), Some(current)) => {
+                    limits.update(key, current, value);
+                    continue;
                 }
-                Entry::Vacant(entry) => {
-                    let _ = errors.insert(entry.key().clone(), EntryError::NoSuchEntry);
+                (UnseqEntryAction::Del, Some(current)) => {
+                    limits.delete(key, current);
+                    continue;
                 }
-            }
+            };
+            let _ = errors.insert(key.clone(), error);
         }
 
-        for key in delete {
-            match new_data.entry(key.clone()) {
-                Entry::Occupied(_) => {
-                    let _ = new_data.remove(&key);
+        limits.check(errors)
+    }
+
+    /// Mutates entries based on `actions` for the provided user.
+    ///
+    /// Returns `Err(InvalidEntryActions)` if the mutation parameters are invalid.
+    pub fn mutate_entries(
+        &mut self,
+        actions: UnseqEntryActions,
+        requester: PublicKey,
+    ) -> Result<()> {
+        self.validate_entries(&actions, requester)?;
+
+        for (key, action) in actions.actions {
+            match action {
+                UnseqEntryAction::Ins(value) | UnseqEntryAction::Update(value) => {
+                    let _ = self.data.insert(key, value);
                 }
-                Entry::Vacant(entry) => {
-                    let _ = errors.insert(entry.key().clone(), EntryError::NoSuchEntry);
+                UnseqEntryAction::Del => {
+                    let _ = self.data.remove(&key);
                 }
             }
         }
 
-        if !errors.is_empty() {
-            return Err(Error::InvalidEntryActions(errors));
-        }
-
-        let _old_data = mem::replace(&mut self.data, new_data);
-
         Ok(())
     }
 }
@@ -523,27 +1424,37 @@ impl SeqMutableData {
         Self {
             address: Address::Seq { name, tag },
             data: Default::default(),
+            tombstones: Default::default(),
             permissions: Default::default(),
+            groups: Default::default(),
+            entry_permissions: Default::default(),
             version: 0,
             owner,
         }
     }
 
     /// Creates a new sequenced MutableData with entries and permissions.
+    ///
+    /// Returns an error if the entries exceed the MutableData limits, see `validate_limits`.
     pub fn new_with_data(
         name: XorName,
         tag: u64,
         data: SeqEntries,
-        permissions: BTreeMap<PublicKey, PermissionSet>,
+        permissions: BTreeMap<User, PermissionSet>,
         owner: PublicKey,
-    ) -> Self {
-        Self {
+    ) -> Result<Self> {
+        let data = Self {
             address: Address::Seq { name, tag },
             data,
+            tombstones: Default::default(),
             permissions,
+            groups: Default::default(),
+            entry_permissions: Default::default(),
             version: 0,
             owner,
-        }
+        };
+        data.validate_limits()?;
+        Ok(data)
     }
 
     /// Returns a value by the given key
@@ -566,98 +1477,117 @@ impl SeqMutableData {
         mem::replace(&mut self.data, BTreeMap::new())
     }
 
-    /// Mutates entries (key + value pairs) in bulk.
+    /// Returns the keys of the deleted entries, with the version they were deleted at.
+    pub fn tombstones(&self) -> &BTreeMap<Vec<u8>, u64> {
+        &self.tombstones
+    }
+
+    /// Removes all tombstones, allowing new entries under their keys to start from any version.
     ///
-    /// Returns `Err(InvalidEntryActions)` if the mutation parameters are invalid.
-    pub fn mutate_entries(&mut self, actions: SeqEntryActions, requester: PublicKey) -> Result<()> {
-        // Deconstruct actions into inserts, updates, and deletes
-        let (insert, update, delete) = actions.actions.into_iter().fold(
-            (BTreeMap::new(), BTreeMap::new(), BTreeMap::new()),
-            |(mut insert, mut update, mut delete), (key, item)| {
-                match item {
-                    SeqEntryAction::Ins(value) => {
-                        let _ = insert.insert(key, value);
-                    }
-                    SeqEntryAction::Update(value) => {
-                        let _ = update.insert(key, value);
-                    }
-                    SeqEntryAction::Del(version) => {
-                        let _ = delete.insert(key, version);
-                    }
-                };
-                (insert, update, delete)
-            },
-        );
+    /// Returns `Err(Error::AccessDenied)` if the provided user is not the owner.
+    pub fn purge_tombstones(&mut self, requester: PublicKey) -> Result<()> {
+        self.check_is_owner(requester)?;
+        self.tombstones.clear();
+        Ok(())
+    }
 
-        if *self.owner() != requester
-            && ((!insert.is_empty() && !self.is_action_allowed(&requester, Action::Insert))
-                || (!update.is_empty() && !self.is_action_allowed(&requester, Action::Update))
-                || (!delete.is_empty() && !self.is_action_allowed(&requester, Action::Delete)))
-        {
+    /// Checks that `actions` can be applied by the provided user, without applying them.
+    ///
+    /// Returns `Err(AccessDenied)` if any of the actions isn't allowed and
+    /// `Err(InvalidEntryActions)` if the mutation parameters are invalid.
+    pub fn validate_entries(&self, actions: &SeqEntryActions, requester: PublicKey) -> Result<()> {
+        let allowed = actions.actions.iter().all(|(key, action)| {
+            let action = match action {
+                SeqEntryAction::Ins(_) => Action::Insert,
+                SeqEntryAction::Update(_) => Action::Update,
+                SeqEntryAction::Del(_) => Action::Delete,
+            };
+            self.is_entry_action_allowed(&requester, key, action)
+        });
+        if !allowed {
             return Err(Error::AccessDenied);
         }
 
-        let mut new_data = self.data.clone();
-        let mut errors = BTreeMap::new();
-
-        for (key, val) in insert {
-            match new_data.entry(key) {
-                Entry::Occupied(entry) => {
-                    let _ = errors.insert(
-                        entry.key().clone(),
-                        EntryError::EntryExists(entry.get().version as u8),
-                    );
+        let mut errors =
+            entry_size_errors(
+                actions
+                    .actions
+                    .iter()
+                    .filter_map(|(key, action)| match action {
+                        SeqEntryAction::Ins(value) | SeqEntryAction::Update(value) => {
+                            Some((key, value))
+                        }
+                        SeqEntryAction::Del(_) => None,
+                    }),
+            );
+        let mut limits = LimitCheck::new(self.data.len(), self.serialised_size());
+
+        for (key, action) in &actions.actions {
+            let error = match (action, self.data.get(key)) {
+                (SeqEntryAction::Ins(_), Some(current)) => {
+                    EntryError::EntryExists(current.version as u8)
                 }
-                Entry::Vacant(entry) => {
-                    let _ = entry.insert(val);
+                (SeqEntryAction::Ins(value), None) => match self.tombstones.get(key) {
+                    Some(&deleted_version) if value.version != deleted_version + 1 => {
+                        EntryError::InvalidSuccessor(deleted_version as u8)
+                    }
+                    tombstone => {
+                        limits.insert(key, value);
+                        if let Some(deleted_version) = tombstone {
+                            limits.remove(key, deleted_version);
+                        }
+                        continue;
+                    }
+                },
+                (SeqEntryAction::Update(value), Some(current)) => {
+                    if value.version != current.version + 1 {
+                        EntryError::InvalidSuccessor(current.version as u8)
+                    } else {
+                        limits.update(key, current, value);
+                        continue;
+                    }
                 }
-            }
-        }
-
-        for (key, val) in update {
-            match new_data.entry(key) {
-                Entry::Occupied(mut entry) => {
-                    let current_version = entry.get().version;
-                    if val.version == current_version + 1 {
-                        let _ = entry.insert(val);
+                (SeqEntryAction::Del(version), Some(current)) => {
+                    if *version != current.version + 1 {
+                        EntryError::InvalidSuccessor(current.version as u8)
                     } else {
-                        let _ = errors.insert(
-                            entry.key().clone(),
-                            EntryError::InvalidSuccessor(current_version as u8),
-                        );
+                        limits.delete(key, current);
+                        limits.add(key, version);
+                        continue;
                     }
                 }
-                Entry::Vacant(entry) => {
-                    let _ = errors.insert(entry.key().clone(), EntryError::NoSuchEntry);
+                (SeqEntryAction::Update(_), None) | (SeqEntryAction::Del(_), None) => {
+                    EntryError::NoSuchEntry
                 }
-            }
+            };
+            let _ = errors.insert(key.clone(), error);
         }
 
-        for (key, version) in delete {
-            match new_data.entry(key.clone()) {
-                Entry::Occupied(entry) => {
-                    let current_version = entry.get().version;
-                    if version == current_version + 1 {
-                        let _ = new_data.remove(&key);
-                    } else {
-                        let _ = errors.insert(
-                            entry.key().clone(),
-                            EntryError::InvalidSuccessor(current_version as u8),
-                        );
-                    }
+        limits.check(errors)
+    }
+
+    /// Mutates entries (key + value pairs) in bulk.
+    ///
+    /// Returns `Err(InvalidEntryActions)` if the mutation parameters are invalid.
+    pub fn mutate_entries(&mut self, actions: SeqEntryActions, requester: PublicKey) -> Result<()> {
+        self.validate_entries(&actions, requester)?;
+
+        for (key, action) in actions.actions {
+            match action {
+                SeqEntryAction::Ins(value) => {
+                    let _ = self.tombstones.remove(&key);
+                    let _ = self.data.insert(key, value);
+                }
+                SeqEntryAction::Update(value) => {
+                    let _ = self.data.insert(key, value);
                 }
-                Entry::Vacant(entry) => {
-                    let _ = errors.insert(entry.key().clone(), EntryError::NoSuchEntry);
+                SeqEntryAction::Del(version) => {
+                    let _ = self.data.remove(&key);
+                    let _ = self.tombstones.insert(key, version);
                 }
             }
         }
 
-        if !errors.is_empty() {
-            return Err(Error::InvalidEntryActions(errors));
-        }
-
-        let _old_data = mem::replace(&mut self.data, new_data);
-
         Ok(())
     }
 }
@@ -822,6 +1752,70 @@ impl Data {
         }
     }
 
+    /// Returns size of this data after serialisation.
+    pub fn serialised_size(&self) -> u64 {
+        match self {
+            Data::Seq(data) => data.serialised_size(),
+            Data::Unseq(data) => data.serialised_size(),
+        }
+    }
+
+    /// Returns true if the size is valid.
+    pub fn validate_size(&self) -> bool {
+        match self {
+            Data::Seq(data) => data.validate_size(),
+            Data::Unseq(data) => data.validate_size(),
+        }
+    }
+
+    /// Checks the entries against the MutableData limits.
+    pub fn validate_limits(&self) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.validate_limits(),
+            Data::Unseq(data) => data.validate_limits(),
+        }
+    }
+
+    /// Returns up to `limit` of the entries selected by `filter` whose key comes after `cursor`,
+    /// along with the cursor for the next page, or `None` if this is the last page.
+    pub fn entries_page(
+        &self,
+        filter: &KeyFilter,
+        limit: u64,
+        cursor: Option<&[u8]>,
+    ) -> (Entries, Option<Vec<u8>>) {
+        match self {
+            Data::Seq(data) => {
+                let (entries, next) = data.entries_page(filter, limit, cursor);
+                (entries.into(), next)
+            }
+            Data::Unseq(data) => {
+                let (entries, next) = data.entries_page(filter, limit, cursor);
+                (entries.into(), next)
+            }
+        }
+    }
+
+    /// Like `entries_page`, but skips the entries the provided user isn't allowed to read.
+    pub fn readable_entries_page(
+        &self,
+        filter: &KeyFilter,
+        limit: u64,
+        cursor: Option<&[u8]>,
+        requester: PublicKey,
+    ) -> (Entries, Option<Vec<u8>>) {
+        match self {
+            Data::Seq(data) => {
+                let (entries, next) = data.readable_entries_page(filter, limit, cursor, requester);
+                (entries.into(), next)
+            }
+            Data::Unseq(data) => {
+                let (entries, next) = data.readable_entries_page(filter, limit, cursor, requester);
+                (entries.into(), next)
+            }
+        }
+    }
+
     /// Returns the shell of the data.
     pub fn shell(&self) -> Self {
         match self {
@@ -831,7 +1825,7 @@ impl Data {
     }
 
     /// Gets a complete list of permissions.
-    pub fn permissions(&self) -> BTreeMap<PublicKey, PermissionSet> {
+    pub fn permissions(&self) -> BTreeMap<User, PermissionSet> {
         match self {
             Data::Seq(data) => data.permissions(),
             Data::Unseq(data) => data.permissions(),
@@ -839,17 +1833,91 @@ impl Data {
     }
 
     /// Gets the permissions for the provided user.
-    pub fn user_permissions(&self, user: PublicKey) -> Result<&PermissionSet> {
+    pub fn user_permissions(&self, user: impl Into<User>) -> Result<&PermissionSet> {
         match self {
             Data::Seq(data) => data.user_permissions(user),
             Data::Unseq(data) => data.user_permissions(user),
         }
     }
 
+    /// Returns the per-entry permissions, keyed by the key prefix they apply to.
+    pub fn entry_permissions(&self) -> &BTreeMap<Vec<u8>, BTreeMap<User, PermissionSet>> {
+        match self {
+            Data::Seq(data) => data.entry_permissions(),
+            Data::Unseq(data) => data.entry_permissions(),
+        }
+    }
+
+    /// Returns the groups.
+    pub fn groups(&self) -> &BTreeMap<GroupName, Group> {
+        match self {
+            Data::Seq(data) => data.groups(),
+            Data::Unseq(data) => data.groups(),
+        }
+    }
+
+    /// Adds a member to a group, creating the group if it doesn't exist.
+    pub fn add_group_member(
+        &mut self,
+        group: GroupName,
+        member: PublicKey,
+        version: u64,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.add_group_member(group, member, version),
+            Data::Unseq(data) => data.add_group_member(group, member, version),
+        }
+    }
+
+    /// Adds a member to a group on behalf of `requester`, rejecting additions which would give
+    /// the member more rights than the requester holds.
+    pub fn add_group_member_checked(
+        &mut self,
+        group: GroupName,
+        member: PublicKey,
+        version: u64,
+        requester: PublicKey,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.add_group_member_checked(group, member, version, requester),
+            Data::Unseq(data) => data.add_group_member_checked(group, member, version, requester),
+        }
+    }
+
+    /// Removes a member from a group.
+    pub fn remove_group_member(
+        &mut self,
+        group: &str,
+        member: &PublicKey,
+        version: u64,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.remove_group_member(group, member, version),
+            Data::Unseq(data) => data.remove_group_member(group, member, version),
+        }
+    }
+
+    /// Removes a member from a group on behalf of `requester`, rejecting removals which would give
+    /// the member more than the requester holds.
+    pub fn remove_group_member_checked(
+        &mut self,
+        group: &str,
+        member: &PublicKey,
+        version: u64,
+        requester: PublicKey,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.remove_group_member_checked(group, member, version, requester),
+            Data::Unseq(data) => {
+                data.remove_group_member_checked(group, member, version, requester)
+            }
+        }
+    }
+
     /// Insert or update permissions for the provided user.
     pub fn set_user_permissions(
         &mut self,
-        user: PublicKey,
+        user: impl Into<User>,
         permissions: PermissionSet,
         version: u64,
     ) -> Result<()> {
@@ -859,14 +1927,158 @@ impl Data {
         }
     }
 
+    /// Insert or update permissions for the provided user on behalf of `requester`, rejecting
+    /// grants which exceed the requester's own rights.
+    pub fn set_user_permissions_checked(
+        &mut self,
+        user: impl Into<User>,
+        permissions: PermissionSet,
+        version: u64,
+        requester: PublicKey,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => {
+                data.set_user_permissions_checked(user, permissions, version, requester)
+            }
+            Data::Unseq(data) => {
+                data.set_user_permissions_checked(user, permissions, version, requester)
+            }
+        }
+    }
+
     /// Delete permissions for the provided user.
-    pub fn del_user_permissions(&mut self, user: PublicKey, version: u64) -> Result<()> {
+    pub fn del_user_permissions(&mut self, user: impl Into<User>, version: u64) -> Result<()> {
         match self {
             Data::Seq(data) => data.del_user_permissions(user, version),
             Data::Unseq(data) => data.del_user_permissions(user, version),
         }
     }
 
+    /// Delete permissions for the provided user on behalf of `requester`, rejecting deletions
+    /// which would give the users they applied to more than the requester holds.
+    pub fn del_user_permissions_checked(
+        &mut self,
+        user: impl Into<User>,
+        version: u64,
+        requester: PublicKey,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.del_user_permissions_checked(user, version, requester),
+            Data::Unseq(data) => data.del_user_permissions_checked(user, version, requester),
+        }
+    }
+
+    /// Insert or update permissions for the provided user on the entries whose key starts with
+    /// `prefix`.
+    pub fn set_entry_permissions(
+        &mut self,
+        prefix: Vec<u8>,
+        user: impl Into<User>,
+        permissions: PermissionSet,
+        version: u64,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.set_entry_permissions(prefix, user, permissions, version),
+            Data::Unseq(data) => data.set_entry_permissions(prefix, user, permissions, version),
+        }
+    }
+
+    /// Insert or update permissions for the provided user on the entries whose key starts with
+    /// `prefix` on behalf of `requester`, rejecting grants which exceed the requester's own rights.
+    pub fn set_entry_permissions_checked(
+        &mut self,
+        prefix: Vec<u8>,
+        user: impl Into<User>,
+        permissions: PermissionSet,
+        version: u64,
+        requester: PublicKey,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => {
+                data.set_entry_permissions_checked(prefix, user, permissions, version, requester)
+            }
+            Data::Unseq(data) => {
+                data.set_entry_permissions_checked(prefix, user, permissions, version, requester)
+            }
+        }
+    }
+
+    /// Delete permissions for the provided user on the entries whose key starts with `prefix`.
+    pub fn del_entry_permissions(
+        &mut self,
+        prefix: &[u8],
+        user: impl Into<User>,
+        version: u64,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.del_entry_permissions(prefix, user, version),
+            Data::Unseq(data) => data.del_entry_permissions(prefix, user, version),
+        }
+    }
+
+    /// Delete permissions for the provided user on the entries whose key starts with `prefix` on
+    /// behalf of `requester`, rejecting deletions which would give the users they applied to more
+    /// than the requester holds.
+    pub fn del_entry_permissions_checked(
+        &mut self,
+        prefix: &[u8],
+        user: impl Into<User>,
+        version: u64,
+        requester: PublicKey,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.del_entry_permissions_checked(prefix, user, version, requester),
+            Data::Unseq(data) => {
+                data.del_entry_permissions_checked(prefix, user, version, requester)
+            }
+        }
+    }
+
+    /// Check permissions for given `action` on the entry with the given `key` for the provided
+    /// user.
+    pub fn check_entry_permissions(
+        &self,
+        key: &[u8],
+        action: Action,
+        requester: PublicKey,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.check_entry_permissions(key, action, requester),
+            Data::Unseq(data) => data.check_entry_permissions(key, action, requester),
+        }
+    }
+
+    /// Check that the provided user is allowed `action` on the whole data, or on the entries of at
+    /// least one prefix.
+    pub fn check_any_entry_permissions(&self, action: Action, requester: PublicKey) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.check_any_entry_permissions(action, requester),
+            Data::Unseq(data) => data.check_any_entry_permissions(action, requester),
+        }
+    }
+
+    /// Explains the outcome of `check_entry_permissions` for given `action` on the entry with the
+    /// given `key` and the provided user.
+    pub fn explain_entry_access(
+        &self,
+        requester: PublicKey,
+        key: &[u8],
+        action: Action,
+    ) -> AccessDecision {
+        match self {
+            Data::Seq(data) => data.explain_entry_access(requester, key, action),
+            Data::Unseq(data) => data.explain_entry_access(requester, key, action),
+        }
+    }
+
+    /// Returns a copy of the data holding only the entries the provided user is allowed to read.
+    pub fn readable_by(&self, requester: PublicKey) -> Self {
+        match self {
+            Data::Seq(data) => Data::Seq(data.readable_by(requester)),
+            Data::Unseq(data) => Data::Unseq(data.readable_by(requester)),
+        }
+    }
+
     /// Check permissions for given `action` for the provided user.
     pub fn check_permissions(&self, action: Action, requester: PublicKey) -> Result<()> {
         match self {
@@ -875,6 +2087,30 @@ impl Data {
         }
     }
 
+    /// Check that `capability` allows the provided user to perform `action` at time `now`, on the
+    /// network of `context`.
+    pub fn check_capability(
+        &self,
+        context: &SigningContext,
+        capability: &Capability,
+        action: Action,
+        requester: PublicKey,
+        now: u64,
+    ) -> Result<()> {
+        match self {
+            Data::Seq(data) => data.check_capability(context, capability, action, requester, now),
+            Data::Unseq(data) => data.check_capability(context, capability, action, requester, now),
+        }
+    }
+
+    /// Explains the outcome of `check_permissions` for given `action` and the provided user.
+    pub fn explain_access(&self, requester: PublicKey, action: Action) -> AccessDecision {
+        match self {
+            Data::Seq(data) => data.explain_access(requester, action),
+            Data::Unseq(data) => data.explain_access(requester, action),
+        }
+    }
+
     /// Check if the provided user is an owner.
     pub fn check_is_owner(&self, requester: PublicKey) -> Result<()> {
         match self {
@@ -891,6 +2127,19 @@ impl Data {
         }
     }
 
+    /// Checks that `actions` can be applied by the provided user, without applying them.
+    pub fn validate_entries(&self, actions: &EntryActions, requester: PublicKey) -> Result<()> {
+        match (self, actions) {
+            (Data::Seq(data), EntryActions::Seq(actions)) => {
+                data.validate_entries(actions, requester)
+            }
+            (Data::Unseq(data), EntryActions::Unseq(actions)) => {
+                data.validate_entries(actions, requester)
+            }
+            _ => Err(Error::InvalidOperation),
+        }
+    }
+
     /// Mutates entries (key + value pairs) in bulk.
     pub fn mutate_entries(&mut self, actions: EntryActions, requester: PublicKey) -> Result<()> {
         match self {
@@ -937,9 +2186,9 @@ pub enum SeqEntryAction {
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
 pub enum UnseqEntryAction {
     /// Inserts a new Unsequenced entry.
-    Ins(Vec<u8>),
+    Ins(#[serde(with = "crate::human_readable::bytes")] Vec<u8>),
     /// Updates an entry with a new value.
-    Update(Vec<u8>),
+    Update(#[serde(with = "crate::human_readable::bytes")] Vec<u8>),
     /// Deletes an entry.
     Del,
 }
@@ -947,6 +2196,7 @@ pub enum UnseqEntryAction {
 /// Sequenced Entry Actions for given entry keys.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug, Default)]
 pub struct SeqEntryActions {
+    #[serde(with = "crate::human_readable::byte_keys")]
     actions: BTreeMap<Vec<u8>, SeqEntryAction>,
 }
 
@@ -1016,6 +2266,7 @@ impl From<BTreeMap<Vec<u8>, SeqEntryAction>> for SeqEntryActions {
 /// Unsequenced Entry Actions for given entry keys.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug, Default)]
 pub struct UnseqEntryActions {
+    #[serde(with = "crate::human_readable::byte_keys")]
     actions: BTreeMap<Vec<u8>, UnseqEntryAction>,
 }
 
@@ -1025,6 +2276,11 @@ impl UnseqEntryActions {
         Default::default()
     }
 
+    /// Gets the actions.
+    pub fn actions(&self) -> &BTreeMap<Vec<u8>, UnseqEntryAction> {
+        &self.actions
+    }
+
     /// Insert a new key-value pair
     pub fn ins(mut self, key: Vec<u8>, content: Vec<u8>) -> Self {
         let _ = self.actions.insert(key, UnseqEntryAction::Ins(content));
@@ -1050,6 +2306,12 @@ impl From<UnseqEntryActions> for BTreeMap<Vec<u8>, UnseqEntryAction> {
     }
 }
 
+impl From<BTreeMap<Vec<u8>, UnseqEntryAction>> for UnseqEntryActions {
+    fn from(actions: BTreeMap<Vec<u8>, UnseqEntryAction>) -> Self {
+        UnseqEntryActions { actions }
+    }
+}
+
 /// Wrapper type for entry actions, which can be sequenced or unsequenced.
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
 pub enum EntryActions {
@@ -1067,6 +2329,38 @@ impl EntryActions {
             EntryActions::Unseq(_) => Kind::Unseq,
         }
     }
+
+    /// Returns the keys of the entries affected by these actions.
+    pub fn keys(&self) -> BTreeSet<Vec<u8>> {
+        match self {
+            EntryActions::Seq(actions) => actions.actions().keys().cloned().collect(),
+            EntryActions::Unseq(actions) => actions.actions().keys().cloned().collect(),
+        }
+    }
+
+    /// Returns the permissions needed to apply these actions.
+    pub fn required_actions(&self) -> BTreeSet<Action> {
+        match self {
+            EntryActions::Seq(actions) => actions
+                .actions()
+                .values()
+                .map(|action| match action {
+                    SeqEntryAction::Ins(_) => Action::Insert,
+                    SeqEntryAction::Update(_) => Action::Update,
+                    SeqEntryAction::Del(_) => Action::Delete,
+                })
+                .collect(),
+            EntryActions::Unseq(actions) => actions
+                .actions()
+                .values()
+                .map(|action| match action {
+                    UnseqEntryAction::Ins(_) => Action::Insert,
+                    UnseqEntryAction::Update(_) => Action::Update,
+                    UnseqEntryAction::Del => Action::Delete,
+                })
+                .collect(),
+        }
+    }
 }
 
 impl From<SeqEntryActions> for EntryActions {
@@ -1090,9 +2384,9 @@ pub type UnseqEntries = BTreeMap<Vec<u8>, Vec<u8>>;
 #[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
 pub enum Entries {
     /// Sequenced entries.
-    Seq(SeqEntries),
+    Seq(#[serde(with = "crate::human_readable::byte_keys")] SeqEntries),
     /// Unsequenced entries.
-    Unseq(UnseqEntries),
+    Unseq(#[serde(with = "crate::human_readable::byte_map")] UnseqEntries),
 }
 
 impl From<SeqEntries> for Entries {
@@ -1109,7 +2403,14 @@ impl From<UnseqEntries> for Entries {
 
 #[cfg(test)]
 mod test {
-    use super::{Address, XorName};
+    use super::{
+        AccessDecision, Action, Address, Data, Entries, EntryError, Error, Group, KeyFilter,
+        PermissionSet, PublicKey, SeqEntryActions, SeqMutableData, UnseqEntryActions,
+        UnseqMutableData, User, XorName, MAX_MDATA_ENTRIES, MAX_MDATA_KEY_SIZE_IN_BYTES,
+        MAX_MDATA_SIZE_IN_BYTES,
+    };
+    use std::collections::BTreeMap;
+    use threshold_crypto::SecretKey;
     use unwrap::unwrap;
 
     #[test]
@@ -1120,4 +2421,799 @@ mod test {
         let decoded = unwrap!(self::Address::decode_from_zbase32(&encoded));
         assert_eq!(address, decoded);
     }
+
+    #[test]
+    fn explain_access() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let stranger = PublicKey::Bls(SecretKey::random().public_key());
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(User::Key(user), PermissionSet::new().allow(Action::Read));
+        let data = Data::from(unwrap!(UnseqMutableData::new_with_data(
+            XorName([1; 32]),
+            10_000,
+            BTreeMap::new(),
+            permissions,
+            owner,
+        )));
+
+        assert_eq!(
+            data.explain_access(owner, Action::ManagePermissions),
+            AccessDecision::Owner
+        );
+        assert_eq!(
+            data.explain_access(user, Action::Read),
+            AccessDecision::Granted {
+                user: User::Key(user),
+                prefix: None,
+            }
+        );
+        assert_eq!(
+            data.explain_access(user, Action::Insert),
+            AccessDecision::Denied {
+                user: User::Key(user),
+                prefix: None,
+            }
+        );
+        assert_eq!(
+            data.explain_access(stranger, Action::Read),
+            AccessDecision::NoPermissionSet
+        );
+
+        // the decision agrees with the permission check
+        for key in &[owner, user, stranger] {
+            for action in &[Action::Read, Action::Insert, Action::ManagePermissions] {
+                assert_eq!(
+                    data.explain_access(*key, *action).into_result(),
+                    data.check_permissions(*action, *key)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn group_permissions() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let member = PublicKey::Bls(SecretKey::random().public_key());
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            User::Group("editors".to_string()),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::Insert),
+        );
+        let _ = permissions.insert(
+            User::Group("readers".to_string()),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .deny(Action::Update),
+        );
+        let mut data = unwrap!(UnseqMutableData::new_with_data(
+            XorName([1; 32]),
+            10_000,
+            BTreeMap::new(),
+            permissions,
+            owner,
+        ));
+
+        // not a member yet
+        assert_eq!(
+            data.check_permissions(Action::Read, member),
+            Err(Error::AccessDenied)
+        );
+
+        unwrap!(data.add_group_member("readers".to_string(), member, 1));
+        assert_eq!(data.check_permissions(Action::Read, member), Ok(()));
+        assert_eq!(
+            data.explain_access(member, Action::Update),
+            AccessDecision::Denied {
+                user: User::Group("readers".to_string()),
+                prefix: None,
+            }
+        );
+        assert_eq!(
+            data.explain_access(member, Action::Insert),
+            AccessDecision::Denied {
+                user: User::Group("readers".to_string()),
+                prefix: None,
+            }
+        );
+
+        // a group allowing the action takes precedence over one denying it
+        unwrap!(data.add_group_member("editors".to_string(), member, 1));
+        assert_eq!(
+            data.explain_access(member, Action::Insert),
+            AccessDecision::Granted {
+                user: User::Group("editors".to_string()),
+                prefix: None,
+            }
+        );
+
+        // the member's own entry takes precedence over the groups
+        unwrap!(data.set_user_permissions(member, PermissionSet::new().deny(Action::Read), 1));
+        assert_eq!(
+            data.explain_access(member, Action::Read),
+            AccessDecision::Denied {
+                user: User::Key(member),
+                prefix: None,
+            }
+        );
+
+        // membership is versioned separately from the permissions
+        assert_eq!(
+            data.remove_group_member("editors", &member, 1),
+            Err(Error::InvalidSuccessor(1))
+        );
+        unwrap!(data.remove_group_member("editors", &member, 2));
+        assert_eq!(
+            data.check_permissions(Action::Insert, member),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(data.version(), 1);
+        assert_eq!(data.group("editors").map(Group::version), Ok(2));
+    }
+
+    #[test]
+    fn check_permissions_grant() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let manager = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            User::Key(manager),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::ManagePermissions),
+        );
+        let mut data = Data::from(unwrap!(UnseqMutableData::new_with_data(
+            XorName([1; 32]),
+            10_000,
+            BTreeMap::new(),
+            permissions,
+            owner,
+        )));
+
+        // only actions the manager holds can be granted
+        unwrap!(data.set_user_permissions_checked(
+            user,
+            PermissionSet::new().allow(Action::Read),
+            1,
+            manager,
+        ));
+        assert_eq!(
+            data.set_user_permissions_checked(
+                user,
+                PermissionSet::new().allow(Action::Insert),
+                2,
+                manager,
+            ),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(
+            data.set_user_permissions_checked(
+                manager,
+                PermissionSet::new()
+                    .allow(Action::ManagePermissions)
+                    .allow(Action::Delete),
+                2,
+                manager,
+            ),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(data.version(), 1);
+
+        // users without `ManagePermissions` can't grant anything
+        assert_eq!(
+            data.set_user_permissions_checked(
+                user,
+                PermissionSet::new().allow(Action::Read),
+                2,
+                user,
+            ),
+            Err(Error::AccessDenied)
+        );
+
+        // the owner can grant anything
+        unwrap!(data.set_user_permissions_checked(
+            user,
+            PermissionSet::new().allow(Action::Insert),
+            2,
+            owner,
+        ));
+        assert_eq!(
+            data.user_permissions(user),
+            Ok(&PermissionSet::new().allow(Action::Insert))
+        );
+
+        // joining a group grants its permissions, map-wide and on entries
+        unwrap!(data.set_user_permissions(
+            User::Group("editors".to_string()),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::Insert),
+            3,
+        ));
+        unwrap!(data.set_user_permissions(
+            User::Group("readers".to_string()),
+            PermissionSet::new().allow(Action::Read),
+            4,
+        ));
+        assert_eq!(
+            data.add_group_member_checked("editors".to_string(), manager, 1, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        unwrap!(data.add_group_member_checked("readers".to_string(), user, 1, manager));
+        unwrap!(data.set_entry_permissions(
+            b"drafts/".to_vec(),
+            User::Group("readers".to_string()),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::Update),
+            5,
+        ));
+        assert_eq!(
+            data.add_group_member_checked("readers".to_string(), manager, 2, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        unwrap!(data.add_group_member_checked("editors".to_string(), manager, 1, owner));
+    }
+
+    #[test]
+    fn entry_permissions() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            User::Key(user),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::Update),
+        );
+        let mut entries = BTreeMap::new();
+        for key in &[&b"public"[..], b"private/a", b"private/shared/a"] {
+            let _ = entries.insert(key.to_vec(), b"value".to_vec());
+        }
+        let mut data = unwrap!(UnseqMutableData::new_with_data(
+            XorName([1; 32]),
+            10_000,
+            entries,
+            permissions,
+            owner
+        ));
+
+        unwrap!(data.set_entry_permissions(
+            b"private/".to_vec(),
+            user,
+            PermissionSet::new().deny(Action::Read).deny(Action::Update),
+            1,
+        ));
+        unwrap!(data.set_entry_permissions(
+            b"private/shared/".to_vec(),
+            user,
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::Update),
+            2,
+        ));
+        assert_eq!(data.version(), 2);
+
+        // the longest matching prefix decides, and the map-wide permissions apply otherwise
+        assert_eq!(
+            data.check_entry_permissions(b"private/a", Action::Read, user),
+            Err(Error::AccessDenied)
+        );
+        assert_eq!(
+            data.check_entry_permissions(b"private/shared/a", Action::Read, user),
+            Ok(())
+        );
+        assert_eq!(
+            data.check_entry_permissions(b"public", Action::Read, user),
+            Ok(())
+        );
+        assert_eq!(
+            data.explain_entry_access(owner, b"private/a", Action::Read),
+            AccessDecision::Owner
+        );
+        assert_eq!(
+            data.explain_entry_access(user, b"private/shared/a", Action::Read),
+            AccessDecision::Granted {
+                user: User::Key(user),
+                prefix: Some(b"private/shared/".to_vec()),
+            }
+        );
+        assert_eq!(
+            data.explain_entry_access(user, b"public", Action::Read),
+            AccessDecision::Granted {
+                user: User::Key(user),
+                prefix: None,
+            }
+        );
+        assert_eq!(
+            data.readable_by(user).keys(),
+            vec![b"private/shared/a".to_vec(), b"public".to_vec()]
+                .into_iter()
+                .collect()
+        );
+        let (entries, next) = data.readable_entries_page(&KeyFilter::All, 1, None, user);
+        assert_eq!(
+            entries.keys().collect::<Vec<_>>(),
+            vec![&b"private/shared/a".to_vec()]
+        );
+        assert_eq!(next, Some(b"private/shared/a".to_vec()));
+        let (entries, next) = data.readable_entries_page(&KeyFilter::All, 1, next.as_deref(), user);
+        assert_eq!(
+            entries.keys().collect::<Vec<_>>(),
+            vec![&b"public".to_vec()]
+        );
+        assert_eq!(next, None);
+
+        let update = |key: &[u8]| UnseqEntryActions::new().update(key.to_vec(), b"new".to_vec());
+        assert_eq!(
+            data.mutate_entries(update(b"private/a"), user),
+            Err(Error::AccessDenied)
+        );
+        unwrap!(data.mutate_entries(update(b"private/shared/a"), user));
+        unwrap!(data.mutate_entries(update(b"public"), user));
+        unwrap!(data.mutate_entries(update(b"private/a"), owner));
+
+        // users without `ManagePermissions` can't set entry permissions
+        assert_eq!(
+            data.set_entry_permissions_checked(
+                b"public".to_vec(),
+                user,
+                PermissionSet::new().allow(Action::Read),
+                3,
+                user,
+            ),
+            Err(Error::AccessDenied)
+        );
+
+        assert_eq!(
+            data.del_entry_permissions(b"private/", user, 2),
+            Err(Error::InvalidSuccessor(2))
+        );
+        assert_eq!(
+            data.del_entry_permissions(b"private", user, 3),
+            Err(Error::NoSuchKey)
+        );
+        unwrap!(data.del_entry_permissions(b"private/", user, 3));
+        assert_eq!(
+            data.check_entry_permissions(b"private/a", Action::Read, user),
+            Ok(())
+        );
+        assert_eq!(data.entry_permissions().len(), 1);
+    }
+
+    #[test]
+    fn del_entry_permissions_checked() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let manager = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            User::Key(manager),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::Update)
+                .allow(Action::ManagePermissions),
+        );
+        let mut data = unwrap!(UnseqMutableData::new_with_data(
+            XorName([1; 32]),
+            10_000,
+            BTreeMap::new(),
+            permissions,
+            owner
+        ));
+        unwrap!(data.add_group_member("staff".to_string(), manager, 1));
+        let restricted = PermissionSet::new().deny(Action::Read).deny(Action::Update);
+        unwrap!(data.set_entry_permissions(b"a/".to_vec(), manager, restricted.clone(), 1));
+        unwrap!(data.set_entry_permissions(
+            b"b/".to_vec(),
+            User::Group("staff".to_string()),
+            restricted.clone(),
+            2,
+        ));
+        unwrap!(data.set_entry_permissions(b"c/".to_vec(), user, restricted, 3));
+
+        // the manager can't lift restrictions on itself or on its groups
+        assert_eq!(
+            data.del_entry_permissions_checked(b"a/", manager, 4, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(
+            data.del_entry_permissions_checked(b"b/", User::Group("staff".to_string()), 4, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(
+            data.del_entry_permissions_checked(b"c/", user, 4, user),
+            Err(Error::AccessDenied)
+        );
+        unwrap!(data.del_entry_permissions_checked(b"c/", user, 4, manager));
+        unwrap!(data.del_entry_permissions_checked(b"a/", manager, 5, owner));
+        assert_eq!(data.entry_permissions().len(), 1);
+    }
+
+    #[test]
+    fn del_user_permissions_checked() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let manager = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            User::Key(manager),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::ManagePermissions),
+        );
+        let _ = permissions.insert(User::Key(user), PermissionSet::new().allow(Action::Read));
+        let _ = permissions.insert(
+            User::Group("staff".to_string()),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::Insert)
+                .allow(Action::Update)
+                .allow(Action::Delete),
+        );
+        let mut data = unwrap!(UnseqMutableData::new_with_data(
+            XorName([1; 32]),
+            10_000,
+            BTreeMap::new(),
+            permissions,
+            owner
+        ));
+        unwrap!(data.add_group_member("staff".to_string(), manager, 1));
+        unwrap!(data.add_group_member("staff".to_string(), user, 2));
+
+        // the manager can't let itself or anyone else fall back to the broader group entry
+        assert_eq!(
+            data.del_user_permissions_checked(manager, 1, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(
+            data.del_user_permissions_checked(user, 1, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(
+            data.del_user_permissions_checked(User::Group("staff".to_string()), 1, user),
+            Err(Error::AccessDenied)
+        );
+        unwrap!(data.del_user_permissions_checked(User::Group("staff".to_string()), 1, manager));
+        unwrap!(data.del_user_permissions_checked(user, 2, owner));
+        assert_eq!(data.permissions().len(), 1);
+    }
+
+    #[test]
+    fn remove_group_member_checked() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let manager = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            User::Key(manager),
+            PermissionSet::new()
+                .allow(Action::Read)
+                .allow(Action::ManagePermissions),
+        );
+        let _ = permissions.insert(User::Key(user), PermissionSet::new().allow(Action::Read));
+        let mut data = unwrap!(UnseqMutableData::new_with_data(
+            XorName([1; 32]),
+            10_000,
+            BTreeMap::new(),
+            permissions,
+            owner
+        ));
+        unwrap!(data.add_group_member("restricted".to_string(), manager, 1));
+        unwrap!(data.add_group_member("restricted".to_string(), user, 2));
+        unwrap!(data.add_group_member("staff".to_string(), user, 1));
+        unwrap!(data.set_entry_permissions(
+            b"private/".to_vec(),
+            User::Group("restricted".to_string()),
+            PermissionSet::new().deny(Action::Read),
+            1,
+        ));
+
+        // leaving the group would lift its restriction on the entries of the prefix
+        assert_eq!(
+            data.remove_group_member_checked("restricted", &manager, 3, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        assert_eq!(
+            data.remove_group_member_checked("restricted", &user, 3, manager),
+            Err(Error::PrivilegeEscalation)
+        );
+        unwrap!(data.remove_group_member_checked("staff", &user, 2, manager));
+        unwrap!(data.remove_group_member_checked("restricted", &user, 3, owner));
+        assert!(!unwrap!(data.group("restricted")).is_member(&user));
+    }
+
+    #[test]
+    fn tombstones() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let other = PublicKey::Bls(SecretKey::random().public_key());
+        let mut data = SeqMutableData::new(XorName([1; 32]), 10_000, owner);
+
+        unwrap!(data.mutate_entries(
+            SeqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec(), 0),
+            owner
+        ));
+        unwrap!(data.mutate_entries(SeqEntryActions::new().del(b"key".to_vec(), 1), owner));
+        assert!(data.entries().is_empty());
+        assert!(data.keys().is_empty());
+        assert_eq!(data.tombstones().get(&b"key".to_vec()), Some(&1));
+
+        // the deleted version can't be reused
+        let mut errors = BTreeMap::new();
+        let _ = errors.insert(b"key".to_vec(), EntryError::InvalidSuccessor(1));
+        assert_eq!(
+            data.mutate_entries(
+                SeqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec(), 0),
+                owner
+            ),
+            Err(Error::InvalidEntryActions(errors))
+        );
+        assert!(data
+            .mutate_entries(
+                SeqEntryActions::new().update(b"key".to_vec(), b"value".to_vec(), 1),
+                owner
+            )
+            .is_err());
+        unwrap!(data.mutate_entries(
+            SeqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec(), 2),
+            owner
+        ));
+        assert!(data.tombstones().is_empty());
+        assert_eq!(data.values().len(), 1);
+
+        // only the owner can purge tombstones
+        unwrap!(data.mutate_entries(SeqEntryActions::new().del(b"key".to_vec(), 3), owner));
+        assert_eq!(data.purge_tombstones(other), Err(Error::AccessDenied));
+        assert_eq!(data.shell().tombstones().len(), 0);
+        unwrap!(data.purge_tombstones(owner));
+        assert!(data.tombstones().is_empty());
+        unwrap!(data.mutate_entries(
+            SeqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec(), 0),
+            owner
+        ));
+    }
+
+    #[test]
+    fn limits() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let new_data = |entries| {
+            UnseqMutableData::new_with_data(
+                XorName([1; 32]),
+                10_000,
+                entries,
+                BTreeMap::new(),
+                owner,
+            )
+        };
+
+        let mut entries: BTreeMap<_, _> = (0..=MAX_MDATA_ENTRIES)
+            .map(|i| (i.to_be_bytes().to_vec(), vec![]))
+            .collect();
+        let mut errors = BTreeMap::new();
+        let _ = errors.insert(
+            MAX_MDATA_ENTRIES.to_be_bytes().to_vec(),
+            EntryError::TooManyEntries,
+        );
+        assert_eq!(
+            new_data(entries.clone()),
+            Err(Error::InvalidEntryActions(errors))
+        );
+
+        // inserting beyond the limit fails for the inserted keys
+        let last = unwrap!(entries.keys().next_back()).clone();
+        let _ = entries.remove(&last);
+        let mut data = unwrap!(new_data(entries));
+        let mut errors = BTreeMap::new();
+        let _ = errors.insert(last.clone(), EntryError::TooManyEntries);
+        assert_eq!(
+            data.mutate_entries(UnseqEntryActions::new().ins(last.clone(), vec![]), owner),
+            Err(Error::InvalidEntryActions(errors))
+        );
+        unwrap!(data.mutate_entries(
+            UnseqEntryActions::new()
+                .del(0u64.to_be_bytes().to_vec())
+                .ins(last, vec![]),
+            owner
+        ));
+
+        let mut data = unwrap!(new_data(BTreeMap::new()));
+        let key = vec![0; MAX_MDATA_KEY_SIZE_IN_BYTES as usize + 1];
+        let mut errors = BTreeMap::new();
+        let _ = errors.insert(key.clone(), EntryError::ExceededSize);
+        assert_eq!(
+            data.mutate_entries(UnseqEntryActions::new().ins(key, vec![]), owner),
+            Err(Error::InvalidEntryActions(errors))
+        );
+
+        // the size of the whole data is limited too
+        unwrap!(data.mutate_entries(
+            UnseqEntryActions::new().ins(vec![1], vec![0; 600 * 1024]),
+            owner
+        ));
+        let mut errors = BTreeMap::new();
+        let _ = errors.insert(vec![2], EntryError::ExceededSize);
+        assert_eq!(
+            data.mutate_entries(
+                UnseqEntryActions::new().ins(vec![2], vec![0; 600 * 1024]),
+                owner
+            ),
+            Err(Error::InvalidEntryActions(errors))
+        );
+        assert_eq!(data.keys().len(), 1);
+        assert!(data.validate_size());
+    }
+
+    #[test]
+    fn validate_entries() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let user = PublicKey::Bls(SecretKey::random().public_key());
+        let mut data = SeqMutableData::new(XorName([1; 32]), 10_000, owner);
+        unwrap!(data.mutate_entries(
+            SeqEntryActions::new().ins(b"key".to_vec(), b"value".to_vec(), 0),
+            owner
+        ));
+        unwrap!(data.set_user_permissions(user, PermissionSet::new().allow(Action::Insert), 1));
+
+        let actions = SeqEntryActions::new()
+            .ins(b"key".to_vec(), b"value".to_vec(), 0)
+            .update(b"missing".to_vec(), b"value".to_vec(), 1)
+            .del(b"key".to_vec(), 1);
+        assert_eq!(
+            data.validate_entries(&actions, user),
+            Err(Error::AccessDenied)
+        );
+        let mut errors = BTreeMap::new();
+        let _ = errors.insert(b"key".to_vec(), EntryError::InvalidSuccessor(0));
+        let _ = errors.insert(b"missing".to_vec(), EntryError::NoSuchEntry);
+        let actions = SeqEntryActions::new()
+            .update(b"missing".to_vec(), b"value".to_vec(), 1)
+            .del(b"key".to_vec(), 2);
+        assert_eq!(
+            data.validate_entries(&actions, owner),
+            Err(Error::InvalidEntryActions(errors.clone()))
+        );
+        assert_eq!(
+            data.mutate_entries(actions, owner),
+            Err(Error::InvalidEntryActions(errors))
+        );
+
+        let actions = SeqEntryActions::new().ins(b"new".to_vec(), b"value".to_vec(), 0);
+        unwrap!(data.validate_entries(&actions, user));
+        assert_eq!(data.keys().len(), 1);
+
+        // the size of the data is predicted exactly, including tombstones
+        let mut data = UnseqMutableData::new(XorName([1; 32]), 10_000, owner);
+        unwrap!(data.mutate_entries(
+            UnseqEntryActions::new().ins(vec![0], vec![0; 512 * 1024]),
+            owner
+        ));
+        let base_size = data.serialised_size();
+        let value_len = (MAX_MDATA_SIZE_IN_BYTES - base_size - 17) as usize;
+        let actions = UnseqEntryActions::new().ins(vec![1], vec![0; value_len + 1]);
+        assert!(data.validate_entries(&actions, owner).is_err());
+        let actions = UnseqEntryActions::new().ins(vec![1], vec![0; value_len]);
+        unwrap!(data.validate_entries(&actions, owner));
+        unwrap!(data.mutate_entries(actions, owner));
+        assert_eq!(data.serialised_size(), MAX_MDATA_SIZE_IN_BYTES);
+
+        let mut data = SeqMutableData::new(XorName([1; 32]), 10_000, owner);
+        unwrap!(data.mutate_entries(
+            SeqEntryActions::new().ins(vec![0], vec![0; 512 * 1024], 0),
+            owner
+        ));
+        let base_size = data.serialised_size();
+        unwrap!(data.mutate_entries(
+            SeqEntryActions::new()
+                .ins(vec![1], vec![], 0)
+                .ins(vec![2], vec![], 0),
+            owner
+        ));
+        // the tombstone of `[1]` takes 17 bytes, the value of `[2]` 25 + its data
+        let value_len = (MAX_MDATA_SIZE_IN_BYTES - base_size - 17 - 25) as usize;
+        let actions =
+            SeqEntryActions::new()
+                .del(vec![1], 1)
+                .update(vec![2], vec![0; value_len + 1], 1);
+        assert!(data.validate_entries(&actions, owner).is_err());
+        let actions = SeqEntryActions::new()
+            .del(vec![1], 1)
+            .update(vec![2], vec![0; value_len], 1);
+        unwrap!(data.mutate_entries(actions, owner));
+        assert_eq!(data.serialised_size(), MAX_MDATA_SIZE_IN_BYTES);
+    }
+
+    #[test]
+    fn pagination() {
+        let owner = PublicKey::Bls(SecretKey::random().public_key());
+        let mut entries = BTreeMap::new();
+        for key in &["a", "ab", "abc", "b", "c"] {
+            let _ = entries.insert(key.as_bytes().to_vec(), b"value".to_vec());
+        }
+        let data = unwrap!(UnseqMutableData::new_with_data(
+            XorName([1; 32]),
+            10_000,
+            entries,
+            BTreeMap::new(),
+            owner
+        ));
+        let keys = |entries: &BTreeMap<Vec<u8>, Vec<u8>>| -> Vec<Vec<u8>> {
+            entries.keys().cloned().collect()
+        };
+
+        let range: Vec<_> = data
+            .range(b"a".to_vec()..b"b".to_vec())
+            .map(|(key, _)| key.clone())
+            .collect();
+        assert_eq!(range, vec![b"a".to_vec(), b"ab".to_vec(), b"abc".to_vec()]);
+        let prefixed: Vec<_> = data
+            .entries_with_prefix(b"ab")
+            .map(|(key, _)| key.clone())
+            .collect();
+        assert_eq!(prefixed, vec![b"ab".to_vec(), b"abc".to_vec()]);
+        let filter = KeyFilter::Range {
+            start: Some(b"ab".to_vec()),
+            end: Some(b"c".to_vec()),
+        };
+        let filtered: Vec<_> = data
+            .filter_entries(&filter, Some(b"abc"))
+            .map(|(key, _)| key.clone())
+            .collect();
+        assert_eq!(filtered, vec![b"b".to_vec()]);
+
+        // page through all the entries
+        let (page, next) = data.entries_page(&KeyFilter::All, 2, None);
+        assert_eq!(keys(&page), vec![b"a".to_vec(), b"ab".to_vec()]);
+        assert_eq!(next, Some(b"ab".to_vec()));
+        let (page, next) = data.entries_page(&KeyFilter::All, 2, next.as_deref());
+        assert_eq!(keys(&page), vec![b"abc".to_vec(), b"b".to_vec()]);
+        let (page, next) = data.entries_page(&KeyFilter::All, 2, next.as_deref());
+        assert_eq!(keys(&page), vec![b"c".to_vec()]);
+        assert_eq!(next, None);
+
+        // page through the entries with a prefix, including a cursor before the prefix
+        let filter = KeyFilter::Prefix(b"ab".to_vec());
+        let (page, next) = data.entries_page(&filter, 1, Some(b"a"));
+        assert_eq!(keys(&page), vec![b"ab".to_vec()]);
+        let (page, next) = data.entries_page(&filter, 1, next.as_deref());
+        assert_eq!(keys(&page), vec![b"abc".to_vec()]);
+        assert_eq!(next, None);
+
+        // empty ranges don't panic
+        let filter = KeyFilter::Range {
+            start: Some(b"c".to_vec()),
+            end: Some(b"a".to_vec()),
+        };
+        assert_eq!(
+            data.entries_page(&filter, 10, None),
+            (BTreeMap::new(), None)
+        );
+        let filter = KeyFilter::Range {
+            start: None,
+            end: Some(b"b".to_vec()),
+        };
+        assert_eq!(
+            data.entries_page(&filter, 10, Some(b"b")),
+            (BTreeMap::new(), None)
+        );
+        assert!(filter.matches(b"abc"));
+        assert!(!filter.matches(b"b"));
+
+        // sequenced data pages through the same keys
+        let mut data = SeqMutableData::new(XorName([1; 32]), 10_000, owner);
+        unwrap!(data.mutate_entries(
+            SeqEntryActions::new()
+                .ins(b"a".to_vec(), b"value".to_vec(), 0)
+                .ins(b"b".to_vec(), b"value".to_vec(), 0),
+            owner
+        ));
+        let (page, next) = Data::from(data).entries_page(&KeyFilter::All, 1, Some(b"a"));
+        match page {
+            Entries::Seq(entries) => assert!(entries.contains_key(&b"b"[..])),
+            Entries::Unseq(_) => panic!("Unexpected unsequenced entries"),
+        }
+        assert_eq!(next, None);
+    }
 }
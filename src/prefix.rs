@@ -0,0 +1,225 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use crate::{XorName, XOR_NAME_BITS};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::{self, Debug, Display, Formatter};
+
+/// A section prefix, i.e. a sequence of bits specifying the part of the network's name space
+/// consisting of all names that start with this sequence.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize)]
+pub struct Prefix {
+    /// Name with all the bits after `bit_count` cleared.
+    name: XorName,
+    bit_count: u16,
+}
+
+impl Prefix {
+    /// Creates a new `Prefix` with the first `bit_count` bits of `name`. Insignificant bits are
+    /// all set to 0.
+    ///
+    /// `bit_count` is capped at [`XOR_NAME_BITS`](constant.XOR_NAME_BITS.html).
+    pub fn new(bit_count: usize, name: XorName) -> Self {
+        let bit_count = bit_count.min(XOR_NAME_BITS);
+        Prefix {
+            name: name.with_remaining_bits(bit_count, false),
+            bit_count: bit_count as u16,
+        }
+    }
+
+    /// Returns the name of this prefix, i.e. its lower bound.
+    pub fn name(&self) -> XorName {
+        self.name
+    }
+
+    /// Returns the number of bits in the prefix.
+    pub fn bit_count(&self) -> usize {
+        self.bit_count as usize
+    }
+
+    /// Returns `true` if this is the empty prefix, i.e. it matches every name.
+    pub fn is_empty(&self) -> bool {
+        self.bit_count == 0
+    }
+
+    /// Returns `self` with an appended bit: `0` if `bit` is `false`, and `1` if `bit` is `true`.
+    ///
+    /// If `self` already contains [`XOR_NAME_BITS`](constant.XOR_NAME_BITS.html) bits, it is
+    /// returned unchanged.
+    pub fn pushed(mut self, bit: bool) -> Self {
+        if self.bit_count() < XOR_NAME_BITS {
+            self.name = self.name.with_bit(self.bit_count(), bit);
+            self.bit_count += 1;
+        }
+        self
+    }
+
+    /// Returns a prefix copying the first `bitcount() - 1` bits from `self`, or `self` if it is
+    /// already empty.
+    pub fn popped(mut self) -> Self {
+        if self.bit_count > 0 {
+            self.bit_count -= 1;
+            self.name = self.name.with_bit(self.bit_count(), false);
+        }
+        self
+    }
+
+    /// Returns `true` if `self` is a prefix of `other` or vice versa.
+    pub fn is_compatible(&self, other: &Prefix) -> bool {
+        let i = self.name.common_prefix_len(&other.name);
+        i >= self.bit_count().min(other.bit_count())
+    }
+
+    /// Returns `true` if `other` is a prefix of `self` and is strictly shorter.
+    pub fn is_extension_of(&self, other: &Prefix) -> bool {
+        self.bit_count > other.bit_count && other.matches(&self.name)
+    }
+
+    /// Returns `true` if `name` starts with the bits of this prefix.
+    pub fn matches(&self, name: &XorName) -> bool {
+        self.name.common_prefix_len(name) >= self.bit_count()
+    }
+
+    /// Returns the smallest name matching the prefix.
+    pub fn lower_bound(&self) -> XorName {
+        self.name
+    }
+
+    /// Returns the largest name matching the prefix.
+    pub fn upper_bound(&self) -> XorName {
+        self.name.with_remaining_bits(self.bit_count(), true)
+    }
+
+    /// Returns `name` with its first `bit_count()` bits replaced by the bits of this prefix.
+    pub fn substituted_in(&self, name: XorName) -> XorName {
+        let mask = XorName::default().with_remaining_bits(self.bit_count(), true);
+        let mut result = self.name;
+        for (byte, (name_byte, mask_byte)) in result.0.iter_mut().zip(name.0.iter().zip(&mask.0)) {
+            *byte |= name_byte & mask_byte;
+        }
+        result
+    }
+}
+
+// Serialised form of a `Prefix`, which may not uphold its invariants.
+#[derive(Deserialize)]
+#[serde(rename = "Prefix")]
+struct SerialisedPrefix {
+    name: XorName,
+    bit_count: u16,
+}
+
+impl<'de> Deserialize<'de> for Prefix {
+    fn deserialize<D: Deserializer<'de>>(deserialiser: D) -> Result<Self, D::Error> {
+        let prefix: SerialisedPrefix = Deserialize::deserialize(deserialiser)?;
+        Ok(Prefix::new(prefix.bit_count as usize, prefix.name))
+    }
+}
+
+impl Debug for Prefix {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "Prefix(")?;
+        for i in 0..self.bit_count() {
+            write!(formatter, "{}", if self.name.bit(i) { '1' } else { '0' })?;
+        }
+        write!(formatter, ")")
+    }
+}
+
+impl Display for Prefix {
+    #[allow(trivial_casts)]
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        (self as &dyn Debug).fmt(formatter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::XOR_NAME_LEN;
+    use unwrap::unwrap;
+
+    fn prefix(bits: &str) -> Prefix {
+        bits.chars()
+            .fold(Prefix::default(), |prefix, bit| prefix.pushed(bit == '1'))
+    }
+
+    #[test]
+    fn push_and_pop() {
+        let prefix_0 = prefix("0");
+        let prefix_01 = prefix("01");
+        assert_eq!(prefix_01.bit_count(), 2);
+        assert_eq!(prefix_01.popped(), prefix_0);
+        assert_eq!(prefix_0.popped(), Prefix::default());
+        assert!(Prefix::default().popped().is_empty());
+        assert_eq!(format!("{:?}", prefix("1011")), "Prefix(1011)");
+
+        let full = Prefix::new(XOR_NAME_BITS, XorName([0xff; XOR_NAME_LEN]));
+        assert_eq!(full.pushed(false), full);
+    }
+
+    #[test]
+    fn matches() {
+        let name = XorName([0b1010_1010; XOR_NAME_LEN]);
+        assert!(Prefix::default().matches(&name));
+        assert!(prefix("1").matches(&name));
+        assert!(prefix("10101010101").matches(&name));
+        assert!(!prefix("0").matches(&name));
+        assert!(!prefix("1011").matches(&name));
+        assert!(Prefix::new(XOR_NAME_BITS, name).matches(&name));
+    }
+
+    #[test]
+    fn compatibility() {
+        assert!(prefix("").is_compatible(&prefix("0")));
+        assert!(prefix("01").is_compatible(&prefix("0")));
+        assert!(prefix("0").is_compatible(&prefix("01")));
+        assert!(!prefix("01").is_compatible(&prefix("00")));
+        assert!(!prefix("1").is_compatible(&prefix("01")));
+
+        assert!(prefix("01").is_extension_of(&prefix("0")));
+        assert!(!prefix("0").is_extension_of(&prefix("0")));
+        assert!(!prefix("0").is_extension_of(&prefix("01")));
+    }
+
+    #[test]
+    fn bounds() {
+        let prefix = prefix("101");
+        let mut lower = [0; XOR_NAME_LEN];
+        lower[0] = 0b1010_0000;
+        let mut upper = [0xff; XOR_NAME_LEN];
+        upper[0] = 0b1011_1111;
+        assert_eq!(prefix.lower_bound(), XorName(lower));
+        assert_eq!(prefix.upper_bound(), XorName(upper));
+        assert!(prefix.matches(&prefix.lower_bound()));
+        assert!(prefix.matches(&prefix.upper_bound()));
+        assert!(!prefix.matches(&prefix.upper_bound().with_flipped_bit(2)));
+    }
+
+    #[test]
+    fn substituted_in() {
+        let name = XorName([0b0101_0101; XOR_NAME_LEN]);
+        let mut expected = name;
+        expected.0[0] = 0b1100_0101;
+        assert_eq!(prefix("1100").substituted_in(name), expected);
+        assert_eq!(Prefix::default().substituted_in(name), name);
+    }
+
+    #[test]
+    fn deserialise_normalises() {
+        let name = XorName([0xff; XOR_NAME_LEN]);
+        let serialised = unwrap!(bincode::serialize(&(name, 3u16)));
+        let deserialised: Prefix = unwrap!(bincode::deserialize(&serialised));
+        assert_eq!(deserialised, prefix("111"));
+
+        let serialised = unwrap!(bincode::serialize(&(name, u16::MAX)));
+        let deserialised: Prefix = unwrap!(bincode::deserialize(&serialised));
+        assert_eq!(deserialised, Prefix::new(XOR_NAME_BITS, name));
+    }
+}
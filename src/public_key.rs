@@ -11,7 +11,6 @@ use crate::{utils, Ed25519Digest, Error, Result, XorName, XOR_NAME_LEN};
 use ed25519_dalek;
 use hex_fmt::HexFmt;
 use multibase::Decodable;
-use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
     fmt::{self, Debug, Display, Formatter},
@@ -20,7 +19,7 @@ use std::{
 use threshold_crypto;
 
 /// Wrapper for different public key types.
-#[derive(Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Eq, PartialEq)]
 pub enum PublicKey {
     /// Ed25519 public key.
     Ed25519(ed25519_dalek::PublicKey),
@@ -140,7 +139,7 @@ impl Display for PublicKey {
 }
 
 /// Wrapper for different signature types.
-#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Eq, PartialEq)]
 #[allow(clippy::large_enum_variant)]
 pub enum Signature {
     /// Ed25519 signature.
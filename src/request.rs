@@ -12,9 +12,9 @@ mod login_packet;
 pub use self::login_packet::{LoginPacket, MAX_LOGIN_PACKET_BYTES};
 use crate::{
     AData, ADataAddress, ADataAppendOperation, ADataIndex, ADataOwner, ADataPubPermissions,
-    ADataUnpubPermissions, ADataUser, AppPermissions, Coins, Error, IData, IDataAddress, MData,
-    MDataAddress, MDataEntryActions, MDataPermissionSet, PublicKey, Response, TransactionId,
-    XorName,
+    ADataUnpubPermissions, ADataUser, AppPermissions, Coins, DataAddress, Error, GroupName, IData,
+    IDataAddress, MData, MDataAddress, MDataEntryActions, MDataKeyFilter, MDataPermissionSet,
+    MDataUser, PublicId, PublicKey, Response, TransactionId, XorName,
 };
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -63,7 +63,7 @@ pub enum Request {
         /// MutableData address.
         address: MDataAddress,
         /// User to set permissions for.
-        user: PublicKey,
+        user: MDataUser,
         /// New permissions.
         permissions: MDataPermissionSet,
         /// Version to set.
@@ -74,7 +74,7 @@ pub enum Request {
         /// MutableData address.
         address: MDataAddress,
         /// User to delete permissions for.
-        user: PublicKey,
+        user: MDataUser,
         /// Version to delete.
         version: u64,
     },
@@ -85,7 +85,7 @@ pub enum Request {
         /// MutableData address.
         address: MDataAddress,
         /// User to get permissions for.
-        user: PublicKey,
+        user: MDataUser,
     },
     /// Mutate MutableData entries.
     MutateMDataEntries {
@@ -164,7 +164,7 @@ pub enum Request {
         /// Permissions index.
         permissions_index: ADataIndex,
         /// User to get permissions for.
-        public_key: PublicKey,
+        public_key: ADataUser,
     },
     /// Get owners at the provided index.
     GetADataOwners {
@@ -273,9 +273,436 @@ pub enum Request {
         /// Incremented version
         version: u64,
     },
+    //
+    // ===== Subscriptions =====
+    //
+    /// Subscribe to notifications of changes to MutableData entries and permissions.
+    SubscribeMData(MDataAddress),
+    /// Unsubscribe from notifications of changes to MutableData.
+    UnsubscribeMData(MDataAddress),
+    /// Subscribe to notifications of entries appended to AppendOnlyData.
+    SubscribeAData(ADataAddress),
+    /// Unsubscribe from notifications of entries appended to AppendOnlyData.
+    UnsubscribeAData(ADataAddress),
+    //
+    // ===== Permission Groups =====
+    //
+    /// Add a member to a MutableData permission group, creating the group if needed.
+    AddMDataGroupMember {
+        /// MutableData address.
+        address: MDataAddress,
+        /// Name of the group.
+        group: GroupName,
+        /// Key to add.
+        member: PublicKey,
+        /// New version of the group.
+        version: u64,
+    },
+    /// Remove a member from a MutableData permission group.
+    RemoveMDataGroupMember {
+        /// MutableData address.
+        address: MDataAddress,
+        /// Name of the group.
+        group: GroupName,
+        /// Key to remove.
+        member: PublicKey,
+        /// New version of the group.
+        version: u64,
+    },
+    /// Add a member to an AppendOnlyData permission group, creating the group if needed.
+    AddADataGroupMember {
+        /// AppendOnlyData address.
+        address: ADataAddress,
+        /// Name of the group.
+        group: GroupName,
+        /// Key to add.
+        member: PublicKey,
+        /// New version of the group.
+        version: u64,
+    },
+    /// Remove a member from an AppendOnlyData permission group.
+    RemoveADataGroupMember {
+        /// AppendOnlyData address.
+        address: ADataAddress,
+        /// Name of the group.
+        group: GroupName,
+        /// Key to remove.
+        member: PublicKey,
+        /// New version of the group.
+        version: u64,
+    },
+    //
+    // ===== Capabilities =====
+    //
+    /// Revoke the capability grant with the given nonce, and every capability delegated from it.
+    /// Only the owner of the data can revoke grants.
+    RevokeCapability {
+        /// Address of the data the grant applies to.
+        address: DataAddress,
+        /// Nonce of the grant.
+        nonce: u64,
+    },
+    //
+    // ===== Entry Permissions =====
+    //
+    /// Set MutableData user permissions on the entries whose key starts with `prefix`.
+    SetMDataEntryPermissions {
+        /// MutableData address.
+        address: MDataAddress,
+        /// Prefix of the keys the permissions apply to.
+        prefix: Vec<u8>,
+        /// User to set permissions for.
+        user: MDataUser,
+        /// New permissions.
+        permissions: MDataPermissionSet,
+        /// Version to set.
+        version: u64,
+    },
+    /// Delete MutableData user permissions on the entries whose key starts with `prefix`.
+    DelMDataEntryPermissions {
+        /// MutableData address.
+        address: MDataAddress,
+        /// Prefix of the keys the permissions apply to.
+        prefix: Vec<u8>,
+        /// User to delete permissions for.
+        user: MDataUser,
+        /// Version to delete.
+        version: u64,
+    },
+    //
+    // ===== Tombstones =====
+    //
+    /// Remove the tombstones of the deleted entries of sequenced MutableData. Only the owner of
+    /// the data can purge tombstones.
+    PurgeMDataTombstones(MDataAddress),
+    //
+    // ===== Paginated Listing =====
+    //
+    /// List a page of the MutableData entries selected by `filter`.
+    ListMDataEntriesPage {
+        /// MutableData address.
+        address: MDataAddress,
+        /// Selection of the entries by key.
+        filter: MDataKeyFilter,
+        /// Maximum number of entries to return.
+        limit: u64,
+        /// Key to continue listing after, as returned with the previous page.
+        cursor: Option<Vec<u8>>,
+    },
+    /// List a page of the MutableData keys selected by `filter`.
+    ListMDataKeysPage {
+        /// MutableData address.
+        address: MDataAddress,
+        /// Selection of the entries by key.
+        filter: MDataKeyFilter,
+        /// Maximum number of keys to return.
+        limit: u64,
+        /// Key to continue listing after, as returned with the previous page.
+        cursor: Option<Vec<u8>>,
+    },
+    /// List a page of the MutableData values selected by `filter`.
+    ListMDataValuesPage {
+        /// MutableData address.
+        address: MDataAddress,
+        /// Selection of the entries by key.
+        filter: MDataKeyFilter,
+        /// Maximum number of values to return.
+        limit: u64,
+        /// Key to continue listing after, as returned with the previous page.
+        cursor: Option<Vec<u8>>,
+    },
+}
+
+/// Destination to which a `Request` must be routed.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum Destination {
+    /// The section holding the data at this address.
+    Data(DataAddress),
+    /// The section managing the coin balance and authorised keys of the client with this name.
+    Client(XorName),
+    /// The section holding the login packet with this name.
+    LoginPacket(XorName),
+}
+
+impl Destination {
+    /// Returns the name of the destination.
+    pub fn name(&self) -> &XorName {
+        match self {
+            Destination::Data(address) => address.name(),
+            Destination::Client(name) | Destination::LoginPacket(name) => name,
+        }
+    }
+}
+
+/// Broad category of a `Request`.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum RequestKind {
+    /// Read-only request.
+    Get,
+    /// Request which modifies stored data, login packets, authorised keys or subscriptions.
+    Mutation,
+    /// Request which creates or moves coins.
+    Transaction,
+}
+
+/// Type of the data or account a `Request` acts on.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum DataType {
+    /// ImmutableData.
+    IData,
+    /// MutableData.
+    MData,
+    /// AppendOnlyData.
+    AData,
+    /// Coin balance.
+    Coins,
+    /// Login packet.
+    LoginPacket,
+    /// Authorised app keys of a client.
+    AuthKeys,
 }
 
 impl Request {
+    /// Returns the kind of this request.
+    ///
+    /// This matches the grouping of [`error_response`](#method.error_response): `Mutation` and
+    /// `Transaction` requests are answered with `Response::Mutation` and `Response::Transaction`
+    /// respectively.
+    pub fn kind(&self) -> RequestKind {
+        use Request::*;
+
+        match *self {
+            // IData
+            GetIData(_) |
+            // MData
+            GetMData(_) |
+            GetMDataValue { .. } |
+            GetMDataShell(_) |
+            GetMDataVersion(_) |
+            ListMDataEntries(_) |
+            ListMDataKeys(_) |
+            ListMDataValues(_) |
+            ListMDataPermissions(_) |
+            ListMDataUserPermissions { .. } |
+            // Paginated Listing
+            ListMDataEntriesPage { .. } |
+            ListMDataKeysPage { .. } |
+            ListMDataValuesPage { .. } |
+            // AData
+            GetAData(_) |
+            GetADataShell { .. } |
+            GetADataValue { .. } |
+            GetADataRange { .. } |
+            GetADataIndices(_) |
+            GetADataLastEntry(_) |
+            GetADataPermissions { .. } |
+            GetPubADataUserPermissions { .. } |
+            GetUnpubADataUserPermissions { .. } |
+            GetADataOwners { .. } |
+            // Coins
+            GetBalance |
+            // Login Packet
+            GetLoginPacket(..) |
+            // Client (Owner) to SrcElders
+            ListAuthKeysAndVersion => RequestKind::Get,
+
+            // Coins
+            TransferCoins { .. } |
+            CreateBalance { .. } => RequestKind::Transaction,
+
+            // IData
+            PutIData(_) |
+            DeleteUnpubIData(_) |
+            // MData
+            PutMData(_) |
+            DeleteMData(_) |
+            SetMDataUserPermissions { .. } |
+            DelMDataUserPermissions { .. } |
+            MutateMDataEntries { .. } |
+            // AData
+            PutAData(_) |
+            DeleteAData(_) |
+            AddPubADataPermissions { .. } |
+            AddUnpubADataPermissions { .. } |
+            SetADataOwner { .. } |
+            AppendSeq { .. } |
+            AppendUnseq(_) |
+            // Login Packet
+            CreateLoginPacket { .. } |
+            CreateLoginPacketFor { .. } |
+            UpdateLoginPacket { .. } |
+            // Client (Owner) to SrcElders
+            InsAuthKey { .. } |
+            DelAuthKey { .. } |
+            // Subscriptions
+            SubscribeMData(_) |
+            UnsubscribeMData(_) |
+            SubscribeAData(_) |
+            UnsubscribeAData(_) |
+            // Permission Groups
+            AddMDataGroupMember { .. } |
+            RemoveMDataGroupMember { .. } |
+            AddADataGroupMember { .. } |
+            RemoveADataGroupMember { .. } |
+            // Capabilities
+            RevokeCapability { .. } |
+            // Entry Permissions
+            SetMDataEntryPermissions { .. } |
+            DelMDataEntryPermissions { .. } |
+            // Tombstones
+            PurgeMDataTombstones(_) => RequestKind::Mutation,
+        }
+    }
+
+    /// Returns `true` if this request must be signed by the requester, i.e. if it is not
+    /// read-only.
+    pub fn requires_signature(&self) -> bool {
+        self.kind() != RequestKind::Get
+    }
+
+    /// Returns the type of the data or account this request acts on.
+    pub fn data_type(&self) -> DataType {
+        use Request::*;
+
+        match *self {
+            PutIData(_) | GetIData(_) | DeleteUnpubIData(_) => DataType::IData,
+            PutMData(_)
+            | GetMData(_)
+            | GetMDataValue { .. }
+            | DeleteMData(_)
+            | GetMDataShell(_)
+            | GetMDataVersion(_)
+            | ListMDataEntries(_)
+            | ListMDataKeys(_)
+            | ListMDataValues(_)
+            | SetMDataUserPermissions { .. }
+            | DelMDataUserPermissions { .. }
+            | ListMDataPermissions(_)
+            | ListMDataUserPermissions { .. }
+            | MutateMDataEntries { .. }
+            | SubscribeMData(_)
+            | UnsubscribeMData(_)
+            | AddMDataGroupMember { .. }
+            | RemoveMDataGroupMember { .. }
+            | SetMDataEntryPermissions { .. }
+            | DelMDataEntryPermissions { .. }
+            | PurgeMDataTombstones(_)
+            | ListMDataEntriesPage { .. }
+            | ListMDataKeysPage { .. }
+            | ListMDataValuesPage { .. } => DataType::MData,
+            PutAData(_)
+            | GetAData(_)
+            | GetADataShell { .. }
+            | GetADataValue { .. }
+            | DeleteAData(_)
+            | GetADataRange { .. }
+            | GetADataIndices(_)
+            | GetADataLastEntry(_)
+            | GetADataPermissions { .. }
+            | GetPubADataUserPermissions { .. }
+            | GetUnpubADataUserPermissions { .. }
+            | GetADataOwners { .. }
+            | AddPubADataPermissions { .. }
+            | AddUnpubADataPermissions { .. }
+            | SetADataOwner { .. }
+            | AppendSeq { .. }
+            | AppendUnseq(_)
+            | SubscribeAData(_)
+            | UnsubscribeAData(_)
+            | AddADataGroupMember { .. }
+            | RemoveADataGroupMember { .. } => DataType::AData,
+            TransferCoins { .. } | GetBalance | CreateBalance { .. } => DataType::Coins,
+            CreateLoginPacket(_)
+            | CreateLoginPacketFor { .. }
+            | UpdateLoginPacket(_)
+            | GetLoginPacket(_) => DataType::LoginPacket,
+            ListAuthKeysAndVersion | InsAuthKey { .. } | DelAuthKey { .. } => DataType::AuthKeys,
+            RevokeCapability { address, .. } => match address {
+                DataAddress::Immutable(_) => DataType::IData,
+                DataAddress::Mutable(_) => DataType::MData,
+                DataAddress::AppendOnly(_) => DataType::AData,
+            },
+        }
+    }
+
+    /// Returns the destination to which this request must be routed when sent by `requester`.
+    ///
+    /// For apps, the destination of coin and authorised key requests is the owner's client
+    /// account.
+    pub fn destination(&self, requester: &PublicId) -> Destination {
+        use Request::*;
+
+        match self {
+            // IData
+            PutIData(data) => Destination::Data((*data.address()).into()),
+            GetIData(address) | DeleteUnpubIData(address) => Destination::Data((*address).into()),
+            // MData
+            PutMData(data) => Destination::Data((*data.address()).into()),
+            GetMData(address)
+            | GetMDataValue { address, .. }
+            | DeleteMData(address)
+            | GetMDataShell(address)
+            | GetMDataVersion(address)
+            | ListMDataEntries(address)
+            | ListMDataKeys(address)
+            | ListMDataValues(address)
+            | SetMDataUserPermissions { address, .. }
+            | DelMDataUserPermissions { address, .. }
+            | ListMDataPermissions(address)
+            | ListMDataUserPermissions { address, .. }
+            | MutateMDataEntries { address, .. }
+            | SubscribeMData(address)
+            | UnsubscribeMData(address)
+            | AddMDataGroupMember { address, .. }
+            | RemoveMDataGroupMember { address, .. }
+            | SetMDataEntryPermissions { address, .. }
+            | DelMDataEntryPermissions { address, .. }
+            | PurgeMDataTombstones(address)
+            | ListMDataEntriesPage { address, .. }
+            | ListMDataKeysPage { address, .. }
+            | ListMDataValuesPage { address, .. } => Destination::Data((*address).into()),
+            // AData
+            PutAData(data) => Destination::Data((*data.address()).into()),
+            GetAData(address)
+            | GetADataShell { address, .. }
+            | DeleteAData(address)
+            | GetADataRange { address, .. }
+            | GetADataValue { address, .. }
+            | GetADataIndices(address)
+            | GetADataLastEntry(address)
+            | GetADataPermissions { address, .. }
+            | GetPubADataUserPermissions { address, .. }
+            | GetUnpubADataUserPermissions { address, .. }
+            | GetADataOwners { address, .. }
+            | AddPubADataPermissions { address, .. }
+            | AddUnpubADataPermissions { address, .. }
+            | SetADataOwner { address, .. }
+            | SubscribeAData(address)
+            | UnsubscribeAData(address)
+            | AddADataGroupMember { address, .. }
+            | RemoveADataGroupMember { address, .. } => Destination::Data((*address).into()),
+            AppendSeq { append, .. } | AppendUnseq(append) => {
+                Destination::Data(append.address.into())
+            }
+            // Capabilities
+            RevokeCapability { address, .. } => Destination::Data(*address),
+            // Login Packet
+            CreateLoginPacket(login_packet) | UpdateLoginPacket(login_packet) => {
+                Destination::LoginPacket(*login_packet.destination())
+            }
+            GetLoginPacket(name) => Destination::LoginPacket(*name),
+            // Coins, and login packets paid for by the requester
+            TransferCoins { .. }
+            | GetBalance
+            | CreateBalance { .. }
+            | CreateLoginPacketFor { .. }
+            // Client (Owner) to SrcElders
+            | ListAuthKeysAndVersion
+            | InsAuthKey { .. }
+            | DelAuthKey { .. } => Destination::Client(*requester.name()),
+        }
+    }
+
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
     pub fn error_response(&self, error: Error) -> Response {
@@ -294,6 +721,9 @@ impl Request {
             ListMDataValues(_) => Response::ListMDataValues(Err(error)),
             ListMDataPermissions(_) => Response::ListMDataPermissions(Err(error)),
             ListMDataUserPermissions { .. } => Response::ListMDataUserPermissions(Err(error)),
+            ListMDataEntriesPage { .. } => Response::ListMDataEntriesPage(Err(error)),
+            ListMDataKeysPage { .. } => Response::ListMDataKeysPage(Err(error)),
+            ListMDataValuesPage { .. } => Response::ListMDataValuesPage(Err(error)),
             // AData
             GetAData(_) => Response::GetAData(Err(error)),
             GetADataShell { .. } => Response::GetADataShell(Err(error)),
@@ -341,7 +771,24 @@ impl Request {
             UpdateLoginPacket { .. } |
             // Client (Owner) to SrcElders
             InsAuthKey { .. } |
-            DelAuthKey { .. } => Response::Mutation(Err(error)),
+            DelAuthKey { .. } |
+            // Subscriptions
+            SubscribeMData(_) |
+            UnsubscribeMData(_) |
+            SubscribeAData(_) |
+            UnsubscribeAData(_) |
+            // Permission Groups
+            AddMDataGroupMember { .. } |
+            RemoveMDataGroupMember { .. } |
+            AddADataGroupMember { .. } |
+            RemoveADataGroupMember { .. } |
+            // Capabilities
+            RevokeCapability { .. } |
+            // Entry Permissions
+            SetMDataEntryPermissions { .. } |
+            DelMDataEntryPermissions { .. } |
+            // Tombstones
+            PurgeMDataTombstones(_) => Response::Mutation(Err(error)),
 
         }
     }
@@ -405,7 +852,124 @@ impl fmt::Debug for Request {
                 ListAuthKeysAndVersion => "Request::ListAuthKeysAndVersion",
                 InsAuthKey { .. } => "Request::InsAuthKey",
                 DelAuthKey { .. } => "Request::DelAuthKey",
+                // Subscriptions
+                SubscribeMData(_) => "Request::SubscribeMData",
+                UnsubscribeMData(_) => "Request::UnsubscribeMData",
+                SubscribeAData(_) => "Request::SubscribeAData",
+                UnsubscribeAData(_) => "Request::UnsubscribeAData",
+                // Permission Groups
+                AddMDataGroupMember { .. } => "Request::AddMDataGroupMember",
+                RemoveMDataGroupMember { .. } => "Request::RemoveMDataGroupMember",
+                AddADataGroupMember { .. } => "Request::AddADataGroupMember",
+                RemoveADataGroupMember { .. } => "Request::RemoveADataGroupMember",
+                // Capabilities
+                RevokeCapability { .. } => "Request::RevokeCapability",
+                SetMDataEntryPermissions { .. } => "Request::SetMDataEntryPermissions",
+                DelMDataEntryPermissions { .. } => "Request::DelMDataEntryPermissions",
+                PurgeMDataTombstones(_) => "Request::PurgeMDataTombstones",
+                ListMDataEntriesPage { .. } => "Request::ListMDataEntriesPage",
+                ListMDataKeysPage { .. } => "Request::ListMDataKeysPage",
+                ListMDataValuesPage { .. } => "Request::ListMDataValuesPage",
             }
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientFullId, PubImmutableData};
+    use unwrap::unwrap;
+
+    #[test]
+    fn destination() {
+        let mut rng = rand::thread_rng();
+        let client_id = ClientFullId::new_ed25519(&mut rng);
+        let requester = PublicId::Client(client_id.public_id().clone());
+
+        let data = IData::from(PubImmutableData::new(vec![1, 2, 3]));
+        let expected = Destination::Data(DataAddress::Immutable(*data.address()));
+        assert_eq!(
+            Request::PutIData(data.clone()).destination(&requester),
+            expected
+        );
+        assert_eq!(
+            Request::GetIData(*data.address()).destination(&requester),
+            expected
+        );
+        assert_eq!(expected.name(), data.name());
+
+        let address = MDataAddress::Unseq {
+            name: rand::random(),
+            tag: 100,
+        };
+        assert_eq!(
+            Request::ListMDataKeys(address).destination(&requester),
+            Destination::Data(DataAddress::Mutable(address))
+        );
+
+        let address = ADataAddress::PubSeq {
+            name: rand::random(),
+            tag: 100,
+        };
+        let append = ADataAppendOperation {
+            address,
+            values: Vec::new(),
+        };
+        assert_eq!(
+            Request::AppendSeq { append, index: 0 }.destination(&requester),
+            Destination::Data(DataAddress::AppendOnly(address))
+        );
+
+        assert_eq!(
+            Request::GetBalance.destination(&requester),
+            Destination::Client(*client_id.public_id().name())
+        );
+
+        let name = rand::random();
+        assert_eq!(
+            Request::GetLoginPacket(name).destination(&requester),
+            Destination::LoginPacket(name)
+        );
+    }
+
+    #[test]
+    fn kind() {
+        let address = MDataAddress::Seq {
+            name: rand::random(),
+            tag: 100,
+        };
+        let get = Request::ListMDataKeys(address);
+        let mutation = Request::DeleteMData(address);
+        let transaction = Request::TransferCoins {
+            destination: rand::random(),
+            amount: unwrap!(Coins::from_nano(1)),
+            transaction_id: 0,
+        };
+
+        // `kind()` must agree with the grouping of `error_response()`.
+        for request in &[get.clone(), mutation.clone(), transaction.clone()] {
+            let expected = match request.error_response(Error::AccessDenied) {
+                Response::Mutation(_) => RequestKind::Mutation,
+                Response::Transaction(_) => RequestKind::Transaction,
+                _ => RequestKind::Get,
+            };
+            assert_eq!(request.kind(), expected);
+        }
+
+        assert!(!get.requires_signature());
+        assert!(mutation.requires_signature());
+        assert!(transaction.requires_signature());
+
+        assert_eq!(get.data_type(), DataType::MData);
+        assert_eq!(transaction.data_type(), DataType::Coins);
+        assert_eq!(
+            Request::GetLoginPacket(rand::random()).data_type(),
+            DataType::LoginPacket
+        );
+        assert_eq!(
+            Request::ListAuthKeysAndVersion.data_type(),
+            DataType::AuthKeys
+        );
+    }
+}
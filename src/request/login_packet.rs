@@ -18,6 +18,7 @@ pub const MAX_LOGIN_PACKET_BYTES: usize = 1024 * 1024; // 1 MB
 pub struct LoginPacket {
     destination: XorName,
     authorised_getter: PublicKey, // deterministically created from passwords
+    #[serde(with = "crate::human_readable::bytes")]
     data: Vec<u8>,
     signature: Signature,
 }
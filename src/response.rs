@@ -10,8 +10,8 @@
 use crate::{
     errors::ErrorDebug, AData, ADataEntries, ADataEntry, ADataIndices, ADataOwner,
     ADataPermissions, ADataPubPermissionSet, ADataUnpubPermissionSet, AppPermissions, Coins, Error,
-    IData, MData, MDataEntries, MDataPermissionSet, MDataValue, MDataValues, PublicKey, Result,
-    Signature, Transaction,
+    IData, MData, MDataEntries, MDataPermissionSet, MDataUser, MDataValue, MDataValues, PublicKey,
+    Result, Signature, Transaction,
 };
 use serde::{Deserialize, Serialize};
 use std::{
@@ -47,7 +47,7 @@ pub enum Response {
     /// Get MutableData permissions for a user.
     ListMDataUserPermissions(Result<MDataPermissionSet>),
     /// List all MutableData permissions.
-    ListMDataPermissions(Result<BTreeMap<PublicKey, MDataPermissionSet>>),
+    ListMDataPermissions(Result<BTreeMap<MDataUser, MDataPermissionSet>>),
     /// Get MutableData value.
     GetMDataValue(Result<MDataValue>),
     //
@@ -95,6 +95,15 @@ pub enum Response {
     //
     /// Return a success or failure status for a mutation operation.
     Mutation(Result<()>),
+    //
+    // ===== Paginated Listing =====
+    //
+    /// List a page of MutableData entries, with the cursor for the next page if there is one.
+    ListMDataEntriesPage(Result<(MDataEntries, Option<Vec<u8>>)>),
+    /// List a page of MutableData keys, with the cursor for the next page if there is one.
+    ListMDataKeysPage(Result<(BTreeSet<Vec<u8>>, Option<Vec<u8>>)>),
+    /// List a page of MutableData values, with the cursor for the next page if there is one.
+    ListMDataValuesPage(Result<(MDataValues, Option<Vec<u8>>)>),
 }
 
 /// Error type for an attempted conversion from `Response` to a type implementing
@@ -131,7 +140,7 @@ try_from!(MDataEntries, ListMDataEntries);
 try_from!(BTreeSet<Vec<u8>>, ListMDataKeys);
 try_from!(MDataValues, ListMDataValues);
 try_from!(MDataPermissionSet, ListMDataUserPermissions);
-try_from!(BTreeMap<PublicKey, MDataPermissionSet>, ListMDataPermissions);
+try_from!(BTreeMap<MDataUser, MDataPermissionSet>, ListMDataPermissions);
 try_from!(MDataValue, GetMDataValue);
 try_from!(Vec<u8>, GetADataValue);
 try_from!(AData, GetAData, GetADataShell);
@@ -150,6 +159,9 @@ try_from!(
 );
 try_from!((Vec<u8>, Signature), GetLoginPacket);
 try_from!((), Mutation);
+try_from!((MDataEntries, Option<Vec<u8>>), ListMDataEntriesPage);
+try_from!((BTreeSet<Vec<u8>>, Option<Vec<u8>>), ListMDataKeysPage);
+try_from!((MDataValues, Option<Vec<u8>>), ListMDataValuesPage);
 
 impl fmt::Debug for Response {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -208,6 +220,16 @@ impl fmt::Debug for Response {
             }
             // Mutation
             Mutation(res) => write!(f, "Response::Mutation({:?})", ErrorDebug(res)),
+            // Paginated Listing
+            ListMDataEntriesPage(res) => {
+                write!(f, "Response::ListMDataEntriesPage({:?})", ErrorDebug(res))
+            }
+            ListMDataKeysPage(res) => {
+                write!(f, "Response::ListMDataKeysPage({:?})", ErrorDebug(res))
+            }
+            ListMDataValuesPage(res) => {
+                write!(f, "Response::ListMDataValuesPage({:?})", ErrorDebug(res))
+            }
         }
     }
 }
@@ -250,13 +272,13 @@ mod tests {
         let mut data = BTreeMap::new();
         let _ = data.insert(vec![1], vec![10]);
         let owners = PublicKey::Bls(threshold_crypto::SecretKey::random().public_key());
-        let m_data = MData::Unseq(UnseqMutableData::new_with_data(
+        let m_data = MData::Unseq(unwrap!(UnseqMutableData::new_with_data(
             *i_data.name(),
             1,
             data.clone(),
             BTreeMap::new(),
             owners,
-        ));
+        )));
         assert_eq!(m_data, unwrap!(GetMData(Ok(m_data.clone())).try_into()));
         assert_eq!(
             TryFromError::Response(e.clone()),
@@ -0,0 +1,294 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Domain-separated, network-bound signatures.
+//!
+//! Every payload signed via a `SigningContext` is prefixed with a tag identifying its purpose and
+//! with the ID of the network it's intended for. A signature made for one network is therefore
+//! rejected by every other network, and a signature made for one purpose (e.g. a challenge
+//! response) can't be passed off as a signature for another one (e.g. a request).
+
+use crate::{
+    utils, Challenge, LoginPacket, Message, MessageId, NodeFullId, PublicId, PublicKey, Request,
+    Response, Result, Signature, Signer, XorName,
+};
+use hex_fmt::HexFmt;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
+
+/// Identifier of a network, e.g. the hash of its genesis key.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct NetworkId(pub [u8; 32]);
+
+impl Debug for NetworkId {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "NetworkId({:<8})", HexFmt(&self.0))
+    }
+}
+
+/// Purpose of a signed payload.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum SignatureDomain {
+    /// `(Request, MessageId)` signed by a client or an app.
+    Request,
+    /// Challenge nonce signed by a client or an app.
+    Challenge,
+    /// Login packet contents signed by the authorised getter.
+    LoginPacket,
+    /// `(Response, MessageId)` signed by a section.
+    SectionResponse,
+    /// Capability grant signed by the owner of the data or a grantee delegating it.
+    Capability,
+}
+
+impl SignatureDomain {
+    /// Returns the tag prefixed to the payloads signed for this purpose.
+    pub fn tag(self) -> &'static str {
+        match self {
+            SignatureDomain::Request => "safe-nd/request/v1",
+            SignatureDomain::Challenge => "safe-nd/challenge/v1",
+            SignatureDomain::LoginPacket => "safe-nd/login-packet/v1",
+            SignatureDomain::SectionResponse => "safe-nd/section-response/v1",
+            SignatureDomain::Capability => "safe-nd/capability/v1",
+        }
+    }
+}
+
+/// Creates and verifies signatures bound to a single network.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct SigningContext {
+    network_id: NetworkId,
+}
+
+impl SigningContext {
+    /// Constructs a context for signing payloads on the network with the given ID.
+    pub fn new(network_id: NetworkId) -> Self {
+        Self { network_id }
+    }
+
+    /// Returns the ID of the network this context signs for.
+    pub fn network_id(&self) -> &NetworkId {
+        &self.network_id
+    }
+
+    /// Returns the bytes to be signed for `content` in the given `domain`.
+    pub fn payload<T: Serialize>(&self, domain: SignatureDomain, content: &T) -> Vec<u8> {
+        utils::serialise(&(domain.tag(), self.network_id, content))
+    }
+
+    // ===== Requests =====
+
+    /// Creates a `Message::Request` with a new random message ID, signed by `signer`.
+    pub fn new_signed_request<S: Signer>(&self, signer: &S, request: Request) -> Message {
+        Message::new_signed_request(self, signer, request)
+    }
+
+    /// Verifies that the request in `message` was signed by `requester` for this network.
+    ///
+    /// Follows the same rules as [`Message::verify`](enum.Message.html#method.verify).
+    pub fn verify_request(&self, message: &Message, requester: &PublicId) -> Result<()> {
+        message.verify(self, requester)
+    }
+
+    /// Returns the bytes signed by the requester for `(request, message_id)`.
+    pub(crate) fn request_payload(&self, request: &Request, message_id: &MessageId) -> Vec<u8> {
+        self.payload(SignatureDomain::Request, &(request, message_id))
+    }
+
+    // ===== Challenges =====
+
+    /// Creates a `Challenge::Response` to the challenge `nonce`, signed by `signer`.
+    pub fn new_challenge_response<S: Signer>(&self, signer: &S, nonce: &[u8]) -> Challenge {
+        let signature = signer.sign_data(&self.payload(SignatureDomain::Challenge, &nonce));
+        Challenge::Response(signer.signer_public_id(), signature)
+    }
+
+    /// Verifies that `signature` was made by `public_id` over the challenge `nonce`.
+    pub fn verify_challenge_response(
+        &self,
+        public_id: &PublicId,
+        signature: &Signature,
+        nonce: &[u8],
+    ) -> Result<()> {
+        public_id
+            .signing_key()
+            .verify(signature, self.payload(SignatureDomain::Challenge, &nonce))
+    }
+
+    // ===== Login Packets =====
+
+    /// Creates a `LoginPacket` holding `data`, retrievable by `signer` and signed by it.
+    pub fn new_login_packet<S: Signer>(
+        &self,
+        signer: &S,
+        destination: XorName,
+        data: Vec<u8>,
+    ) -> Result<LoginPacket> {
+        let authorised_getter = signer.signer_public_id().signing_key();
+        let signature =
+            signer.sign_data(&self.login_packet_payload(&destination, &authorised_getter, &data));
+        LoginPacket::new(destination, authorised_getter, data, signature)
+    }
+
+    /// Verifies that `login_packet` was signed by its authorised getter for this network.
+    pub fn verify_login_packet(&self, login_packet: &LoginPacket) -> Result<()> {
+        login_packet.authorised_getter().verify(
+            login_packet.signature(),
+            self.login_packet_payload(
+                login_packet.destination(),
+                login_packet.authorised_getter(),
+                login_packet.data(),
+            ),
+        )
+    }
+
+    fn login_packet_payload(
+        &self,
+        destination: &XorName,
+        authorised_getter: &PublicKey,
+        data: &[u8],
+    ) -> Vec<u8> {
+        self.payload(
+            SignatureDomain::LoginPacket,
+            &(destination, authorised_getter, data),
+        )
+    }
+
+    // ===== Section Responses =====
+
+    /// Creates a BLS signature share of `(response, message_id)` using `node`'s key share.
+    ///
+    /// Returns `None` if `node` doesn't hold a BLS key share.
+    pub fn sign_section_response(
+        &self,
+        node: &NodeFullId,
+        response: &Response,
+        message_id: &MessageId,
+    ) -> Option<Signature> {
+        node.sign_using_bls(self.payload(SignatureDomain::SectionResponse, &(response, message_id)))
+    }
+
+    /// Verifies that `signature` was made over `(response, message_id)` by the holder of `key`,
+    /// i.e. a section or one of its elders.
+    pub fn verify_section_response(
+        &self,
+        signature: &Signature,
+        key: &PublicKey,
+        response: &Response,
+        message_id: &MessageId,
+    ) -> Result<()> {
+        key.verify(
+            signature,
+            self.payload(SignatureDomain::SectionResponse, &(response, message_id)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientFullId, Error, IDataAddress};
+    use threshold_crypto::SecretKeySet;
+    use unwrap::unwrap;
+
+    fn contexts() -> (SigningContext, SigningContext) {
+        (
+            SigningContext::new(NetworkId([1; 32])),
+            SigningContext::new(NetworkId([2; 32])),
+        )
+    }
+
+    #[test]
+    fn request() {
+        let (ours, theirs) = contexts();
+        let client = ClientFullId::new_ed25519(&mut rand::thread_rng());
+        let requester = PublicId::Client(client.public_id().clone());
+
+        let request = Request::DeleteUnpubIData(IDataAddress::Unpub(rand::random()));
+        let message = ours.new_signed_request(&client, request);
+        unwrap!(ours.verify_request(&message, &requester));
+        assert_eq!(
+            theirs.verify_request(&message, &requester),
+            Err(Error::InvalidSignature)
+        );
+        unwrap!(message.verify(&ours, &requester));
+        assert_eq!(
+            message.verify(&theirs, &requester),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn challenge() {
+        let (ours, theirs) = contexts();
+        let client = ClientFullId::new_ed25519(&mut rand::thread_rng());
+        let nonce: [u8; 32] = rand::random();
+
+        let (public_id, signature) = match ours.new_challenge_response(&client, &nonce) {
+            Challenge::Response(public_id, signature) => (public_id, signature),
+            Challenge::Request(..) => panic!("Unexpected challenge request"),
+        };
+        unwrap!(ours.verify_challenge_response(&public_id, &signature, &nonce));
+        assert_eq!(
+            theirs.verify_challenge_response(&public_id, &signature, &nonce),
+            Err(Error::InvalidSignature)
+        );
+        assert_eq!(
+            ours.verify_challenge_response(&public_id, &signature, &[0; 32]),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn login_packet() {
+        let (ours, theirs) = contexts();
+        let client = ClientFullId::new_bls(&mut rand::thread_rng());
+
+        let login_packet = unwrap!(ours.new_login_packet(&client, rand::random(), vec![1; 16]));
+        assert_eq!(
+            login_packet.authorised_getter(),
+            client.public_id().public_key()
+        );
+        unwrap!(ours.verify_login_packet(&login_packet));
+        assert_eq!(
+            theirs.verify_login_packet(&login_packet),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn section_response() {
+        let (ours, theirs) = contexts();
+        let mut rng = rand::thread_rng();
+        let mut node = NodeFullId::new(&mut rng);
+        let message_id = MessageId::new();
+        let response = Response::Mutation(Ok(()));
+        assert!(ours
+            .sign_section_response(&node, &response, &message_id)
+            .is_none());
+
+        let secret_key_set = SecretKeySet::random(0, &mut rng);
+        node.set_bls_keys(secret_key_set.secret_key_share(0));
+        let key = PublicKey::from(secret_key_set.public_keys().public_key_share(0));
+        let signature = unwrap!(ours.sign_section_response(&node, &response, &message_id));
+        unwrap!(ours.verify_section_response(&signature, &key, &response, &message_id));
+        assert_eq!(
+            theirs.verify_section_response(&signature, &key, &response, &message_id),
+            Err(Error::InvalidSignature)
+        );
+
+        // A signature for another purpose is rejected even if the signed content is identical.
+        let payload = ours.payload(SignatureDomain::Request, &(&response, &message_id));
+        let signature = unwrap!(node.sign_using_bls(payload));
+        assert_eq!(
+            ours.verify_section_response(&signature, &key, &response, &message_id),
+            Err(Error::InvalidSignature)
+        );
+    }
+}
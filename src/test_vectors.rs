@@ -0,0 +1,1199 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Golden wire-format test vectors.
+//!
+//! Builds one instance of every variant of the types sent over the wire, using identities and IDs
+//! derived from fixed seeds, and checks their encodings against the files in `test_vectors/`. Any
+//! change to the wire format makes these tests fail until the files are regenerated by running
+//! the tests with `SAFE_ND_UPDATE_TEST_VECTORS=1` set.
+
+use crate::{
+    utils, AData, ADataAddress, ADataAppendOperation, ADataEntry, ADataIndex, ADataIndices,
+    ADataOwner, ADataPermissions, ADataPubPermissionSet, ADataPubPermissions,
+    ADataUnpubPermissionSet, ADataUnpubPermissions, ADataUser, AppFullId, AppPermissions,
+    AppendOnlyData, Challenge, ClientFullId, Coins, DataAddress, EntryError, Error, IData,
+    IDataAddress, LoginPacket, MData, MDataAction, MDataAddress, MDataEntries, MDataKeyFilter,
+    MDataPermissionSet, MDataSeqEntryActions, MDataSeqValue, MDataUser, MDataValue, MDataValues,
+    Message, MessageId, NetworkId, NodeFullId, Notification, ParseError, PubImmutableData,
+    PubSeqAppendOnlyData, PubUnseqAppendOnlyData, PublicId, PublicKey, Request, Response,
+    SeqAppendOnly, SeqMutableData, Signature, Signer, SigningContext, Transaction,
+    UnpubImmutableData, UnpubSeqAppendOnlyData, UnpubUnseqAppendOnlyData, UnseqAppendOnly,
+    UnseqMutableData, WireEnvelope, XorName,
+};
+use rand::{CryptoRng, Rng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fmt::Write,
+    fs,
+};
+use threshold_crypto::SecretKeySet;
+use unwrap::unwrap;
+
+/// Environment variable which, when set, makes the tests rewrite the vector files instead of
+/// checking them.
+const UPDATE_ENV_VAR: &str = "SAFE_ND_UPDATE_TEST_VECTORS";
+
+const TAG: u64 = 15_000;
+const GROUP: &str = "editors";
+
+/// Generates, for each variant of an enum, the list of variant names in declaration order and a
+/// function returning the name of a value's variant. The `match` is exhaustive, so adding a
+/// variant without listing it here fails to compile.
+macro_rules! variants {
+    ($names:ident, $name_of:ident, $enum:ident { $($variant:ident),* $(,)* }) => {
+        fn $names() -> Vec<&'static str> {
+            vec![$(stringify!($variant)),*]
+        }
+
+        fn $name_of(value: &$enum) -> &'static str {
+            match value {
+                $($enum::$variant { .. } => stringify!($variant),)*
+            }
+        }
+    };
+}
+
+variants!(
+    request_names,
+    request_name,
+    Request {
+        PutIData,
+        GetIData,
+        DeleteUnpubIData,
+        PutMData,
+        GetMData,
+        GetMDataValue,
+        DeleteMData,
+        GetMDataShell,
+        GetMDataVersion,
+        ListMDataEntries,
+        ListMDataKeys,
+        ListMDataValues,
+        SetMDataUserPermissions,
+        DelMDataUserPermissions,
+        ListMDataPermissions,
+        ListMDataUserPermissions,
+        MutateMDataEntries,
+        PutAData,
+        GetAData,
+        GetADataShell,
+        DeleteAData,
+        GetADataRange,
+        GetADataValue,
+        GetADataIndices,
+        GetADataLastEntry,
+        GetADataPermissions,
+        GetPubADataUserPermissions,
+        GetUnpubADataUserPermissions,
+        GetADataOwners,
+        AddPubADataPermissions,
+        AddUnpubADataPermissions,
+        SetADataOwner,
+        AppendSeq,
+        AppendUnseq,
+        TransferCoins,
+        GetBalance,
+        CreateBalance,
+        CreateLoginPacket,
+        CreateLoginPacketFor,
+        UpdateLoginPacket,
+        GetLoginPacket,
+        ListAuthKeysAndVersion,
+        InsAuthKey,
+        DelAuthKey,
+        SubscribeMData,
+        UnsubscribeMData,
+        SubscribeAData,
+        UnsubscribeAData,
+        AddMDataGroupMember,
+        RemoveMDataGroupMember,
+        AddADataGroupMember,
+        RemoveADataGroupMember,
+        RevokeCapability,
+        SetMDataEntryPermissions,
+        DelMDataEntryPermissions,
+        PurgeMDataTombstones,
+        ListMDataEntriesPage,
+        ListMDataKeysPage,
+        ListMDataValuesPage,
+    }
+);
+
+variants!(
+    response_names,
+    response_name,
+    Response {
+        GetIData,
+        GetMData,
+        GetMDataShell,
+        GetMDataVersion,
+        ListMDataEntries,
+        ListMDataKeys,
+        ListMDataValues,
+        ListMDataUserPermissions,
+        ListMDataPermissions,
+        GetMDataValue,
+        GetAData,
+        GetADataShell,
+        GetADataOwners,
+        GetADataRange,
+        GetADataValue,
+        GetADataIndices,
+        GetADataLastEntry,
+        GetADataPermissions,
+        GetPubADataUserPermissions,
+        GetUnpubADataUserPermissions,
+        GetBalance,
+        Transaction,
+        GetLoginPacket,
+        ListAuthKeysAndVersion,
+        Mutation,
+        ListMDataEntriesPage,
+        ListMDataKeysPage,
+        ListMDataValuesPage,
+    }
+);
+
+variants!(
+    error_names,
+    error_name,
+    Error {
+        AccessDenied,
+        NoSuchLoginPacket,
+        LoginPacketExists,
+        NoSuchData,
+        DataExists,
+        NoSuchEntry,
+        TooManyEntries,
+        InvalidEntryActions,
+        NoSuchKey,
+        KeysExist,
+        DuplicateEntryKeys,
+        InvalidOwners,
+        InvalidSuccessor,
+        InvalidOwnersSuccessor,
+        InvalidPermissionsSuccessor,
+        InvalidPermissions,
+        InvalidOperation,
+        SigningKeyTypeMismatch,
+        InvalidSignature,
+        DuplicateMessageId,
+        Internal,
+        LossOfPrecision,
+        ExcessiveValue,
+        FailedToParse,
+        TransactionIdExists,
+        InsufficientBalance,
+        NoSuchBalance,
+        BalanceExists,
+        ExceededSize,
+        NoSuchChallenge,
+        ChallengeExpired,
+        ExceededMessageSize,
+        ExceededKeyLength,
+        ExceededValueLength,
+        ExceededLoginPacketSize,
+        UnsupportedProtocolVersion,
+        GroupMemberExists,
+        PrivilegeEscalation,
+        CapabilityExpired,
+        CapabilityRevoked,
+    }
+);
+
+variants!(
+    message_names,
+    message_name,
+    Message {
+        Request,
+        Response,
+        Notification,
+    }
+);
+
+variants!(
+    notification_names,
+    notification_name,
+    Notification {
+        Transaction,
+        MDataMutated,
+        ADataAppended,
+        ShellChanged,
+    }
+);
+
+variants!(
+    challenge_names,
+    challenge_name,
+    Challenge { Request, Response }
+);
+
+variants!(
+    public_id_names,
+    public_id_name,
+    PublicId { Node, Client, App }
+);
+
+variants!(
+    public_key_names,
+    public_key_name,
+    PublicKey {
+        Ed25519,
+        Bls,
+        BlsShare,
+    }
+);
+
+variants!(
+    signature_names,
+    signature_name,
+    Signature {
+        Ed25519,
+        Bls,
+        BlsShare,
+    }
+);
+
+variants!(idata_names, idata_name, IData { Unpub, Pub });
+
+variants!(mdata_names, mdata_name, MData { Seq, Unseq });
+
+variants!(
+    adata_names,
+    adata_name,
+    AData {
+        PubSeq,
+        PubUnseq,
+        UnpubSeq,
+        UnpubUnseq,
+    }
+);
+
+variants!(
+    data_address_names,
+    data_address_name,
+    DataAddress {
+        Immutable,
+        Mutable,
+        AppendOnly,
+    }
+);
+
+/// Seeded RNG whose output is specified by its algorithm, so the fixtures don't change with the
+/// `rand` release (unlike `StdRng`).
+///
+/// It is not cryptographically secure; it is only marked as `CryptoRng` to generate the fixed keys.
+struct FixtureRng(XorShiftRng);
+
+impl RngCore for FixtureRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+impl CryptoRng for FixtureRng {}
+
+/// Deterministic identities and random values, all derived from a fixed seed.
+pub(crate) struct Fixtures {
+    rng: FixtureRng,
+    client: ClientFullId,
+    bls_client: ClientFullId,
+    app: AppFullId,
+    node: NodeFullId,
+}
+
+impl Fixtures {
+    pub(crate) fn new() -> Self {
+        let mut rng = FixtureRng(XorShiftRng::from_seed([0x5a; 16]));
+        let client = ClientFullId::new_ed25519(&mut rng);
+        let bls_client = ClientFullId::new_bls(&mut rng);
+        let app = AppFullId::new_bls(&mut rng, client.public_id().clone());
+        let mut node = NodeFullId::new(&mut rng);
+        node.set_bls_keys(SecretKeySet::random(0, &mut rng).secret_key_share(0));
+        Self {
+            rng,
+            client,
+            bls_client,
+            app,
+            node,
+        }
+    }
+
+    fn name(&mut self) -> XorName {
+        self.rng.gen()
+    }
+
+    pub(crate) fn message_id(&mut self) -> MessageId {
+        MessageId(self.name())
+    }
+
+    fn client_key(&self) -> PublicKey {
+        *self.client.public_id().public_key()
+    }
+
+    fn app_key(&self) -> PublicKey {
+        *self.app.public_id().public_key()
+    }
+
+    fn node_share_key(&self) -> PublicKey {
+        PublicKey::from(unwrap!(*self.node.public_id().bls_public_key()))
+    }
+
+    fn coins(&self) -> Coins {
+        unwrap!(Coins::from_nano(1_500_000_000))
+    }
+
+    fn login_packet(&mut self) -> LoginPacket {
+        let destination = self.name();
+        let data = b"encrypted account".to_vec();
+        let signature = self.client.sign(&data);
+        unwrap!(LoginPacket::new(
+            destination,
+            self.client_key(),
+            data,
+            signature
+        ))
+    }
+
+    fn mdata_address(&mut self) -> MDataAddress {
+        MDataAddress::Seq {
+            name: self.name(),
+            tag: TAG,
+        }
+    }
+
+    fn adata_address(&mut self) -> ADataAddress {
+        ADataAddress::PubSeq {
+            name: self.name(),
+            tag: TAG,
+        }
+    }
+
+    fn mdata_permissions(&self) -> BTreeMap<MDataUser, MDataPermissionSet> {
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            MDataUser::Key(self.app_key()),
+            MDataPermissionSet::new()
+                .allow(MDataAction::Read)
+                .allow(MDataAction::Insert),
+        );
+        let _ = permissions.insert(
+            MDataUser::Group(GROUP.to_string()),
+            MDataPermissionSet::new().allow(MDataAction::Read),
+        );
+        permissions
+    }
+
+    fn seq_mdata(&mut self) -> SeqMutableData {
+        let mut entries = BTreeMap::new();
+        let _ = entries.insert(
+            b"key".to_vec(),
+            MDataSeqValue {
+                data: b"value".to_vec(),
+                version: 0,
+            },
+        );
+        let name = self.name();
+        let mut data = unwrap!(SeqMutableData::new_with_data(
+            name,
+            TAG,
+            entries,
+            self.mdata_permissions(),
+            self.client_key(),
+        ));
+        unwrap!(data.add_group_member(GROUP.to_string(), self.app_key(), 1));
+        unwrap!(data.set_entry_permissions(
+            b"k".to_vec(),
+            MDataUser::Group(GROUP.to_string()),
+            MDataPermissionSet::new().allow(MDataAction::Update),
+            1,
+        ));
+        let actions = MDataSeqEntryActions::new().ins(b"deleted".to_vec(), b"value".to_vec(), 0);
+        unwrap!(data.mutate_entries(actions, self.client_key()));
+        let actions = MDataSeqEntryActions::new().del(b"deleted".to_vec(), 1);
+        unwrap!(data.mutate_entries(actions, self.client_key()));
+        data
+    }
+
+    fn unseq_mdata(&mut self) -> UnseqMutableData {
+        let mut entries = BTreeMap::new();
+        let _ = entries.insert(b"key".to_vec(), b"value".to_vec());
+        let name = self.name();
+        unwrap!(UnseqMutableData::new_with_data(
+            name,
+            TAG,
+            entries,
+            self.mdata_permissions(),
+            self.client_key(),
+        ))
+    }
+
+    fn adata_owner(&self) -> ADataOwner {
+        ADataOwner {
+            public_key: self.client_key(),
+            entries_index: 0,
+            permissions_index: 0,
+        }
+    }
+
+    fn adata_entries(&self) -> Vec<ADataEntry> {
+        vec![ADataEntry::new(b"key".to_vec(), b"value".to_vec())]
+    }
+
+    fn pub_permissions(&self) -> ADataPubPermissions {
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(ADataUser::Anyone, ADataPubPermissionSet::new(true, None));
+        let _ = permissions.insert(
+            ADataUser::Key(self.app_key()),
+            ADataPubPermissionSet::new(true, false),
+        );
+        let _ = permissions.insert(
+            ADataUser::Group(GROUP.to_string()),
+            ADataPubPermissionSet::new(None, true),
+        );
+        ADataPubPermissions {
+            permissions,
+            entries_index: 0,
+            owners_index: 1,
+        }
+    }
+
+    fn unpub_permissions(&self) -> ADataUnpubPermissions {
+        let mut permissions = BTreeMap::new();
+        let _ = permissions.insert(
+            ADataUser::Key(self.app_key()),
+            ADataUnpubPermissionSet::new(true, true, false),
+        );
+        let _ = permissions.insert(
+            ADataUser::Group(GROUP.to_string()),
+            ADataUnpubPermissionSet::new(true, false, false),
+        );
+        ADataUnpubPermissions {
+            permissions,
+            entries_index: 0,
+            owners_index: 1,
+        }
+    }
+
+    fn pub_seq_adata(&mut self) -> PubSeqAppendOnlyData {
+        let mut data = PubSeqAppendOnlyData::new(self.name(), TAG);
+        unwrap!(data.append_owner(self.adata_owner(), 0));
+        unwrap!(data.append_permissions(self.pub_permissions(), 0));
+        unwrap!(data.append(self.adata_entries(), 0));
+        unwrap!(data.add_group_member(GROUP.to_string(), self.app_key(), 1));
+        data
+    }
+
+    fn pub_unseq_adata(&mut self) -> PubUnseqAppendOnlyData {
+        let mut data = PubUnseqAppendOnlyData::new(self.name(), TAG);
+        unwrap!(data.append_owner(self.adata_owner(), 0));
+        unwrap!(data.append_permissions(self.pub_permissions(), 0));
+        unwrap!(data.append(self.adata_entries()));
+        data
+    }
+
+    fn unpub_seq_adata(&mut self) -> UnpubSeqAppendOnlyData {
+        let mut data = UnpubSeqAppendOnlyData::new(self.name(), TAG);
+        unwrap!(data.append_owner(self.adata_owner(), 0));
+        unwrap!(data.append_permissions(self.unpub_permissions(), 0));
+        unwrap!(data.append(self.adata_entries(), 0));
+        unwrap!(data.add_group_member(GROUP.to_string(), self.app_key(), 1));
+        data
+    }
+
+    fn unpub_unseq_adata(&mut self) -> UnpubUnseqAppendOnlyData {
+        let mut data = UnpubUnseqAppendOnlyData::new(self.name(), TAG);
+        unwrap!(data.append_owner(self.adata_owner(), 0));
+        unwrap!(data.append_permissions(self.unpub_permissions(), 0));
+        unwrap!(data.append(self.adata_entries()));
+        data
+    }
+
+    pub(crate) fn signed_request(&mut self, request: Request) -> Message {
+        let message_id = self.message_id();
+        let payload =
+            SigningContext::new(NetworkId::default()).request_payload(&request, &message_id);
+        let signature = self.client.sign_data(&payload);
+        Message::Request {
+            request,
+            message_id,
+            signature: Some(signature),
+            capability: None,
+        }
+    }
+}
+
+// ===== Instances =====
+
+pub(crate) fn requests(fixtures: &mut Fixtures) -> Vec<Request> {
+    let client_key = fixtures.client_key();
+    let app_key = fixtures.app_key();
+    let append = ADataAppendOperation {
+        address: fixtures.adata_address(),
+        values: fixtures.adata_entries(),
+    };
+
+    vec![
+        Request::PutIData(PubImmutableData::new(b"published".to_vec()).into()),
+        Request::GetIData(IDataAddress::Pub(fixtures.name())),
+        Request::DeleteUnpubIData(IDataAddress::Unpub(fixtures.name())),
+        Request::PutMData(fixtures.seq_mdata().into()),
+        Request::GetMData(fixtures.mdata_address()),
+        Request::GetMDataValue {
+            address: fixtures.mdata_address(),
+            key: b"key".to_vec(),
+        },
+        Request::DeleteMData(fixtures.mdata_address()),
+        Request::GetMDataShell(fixtures.mdata_address()),
+        Request::GetMDataVersion(fixtures.mdata_address()),
+        Request::ListMDataEntries(fixtures.mdata_address()),
+        Request::ListMDataKeys(fixtures.mdata_address()),
+        Request::ListMDataValues(fixtures.mdata_address()),
+        Request::SetMDataUserPermissions {
+            address: fixtures.mdata_address(),
+            user: MDataUser::Key(app_key),
+            permissions: MDataPermissionSet::new().allow(MDataAction::Update),
+            version: 1,
+        },
+        Request::DelMDataUserPermissions {
+            address: fixtures.mdata_address(),
+            user: MDataUser::Key(app_key),
+            version: 2,
+        },
+        Request::ListMDataPermissions(fixtures.mdata_address()),
+        Request::ListMDataUserPermissions {
+            address: fixtures.mdata_address(),
+            user: MDataUser::Group(GROUP.to_string()),
+        },
+        Request::MutateMDataEntries {
+            address: fixtures.mdata_address(),
+            actions: MDataSeqEntryActions::new()
+                .ins(b"new".to_vec(), b"value".to_vec(), 0)
+                .update(b"key".to_vec(), b"updated".to_vec(), 1)
+                .del(b"old".to_vec(), 3)
+                .into(),
+        },
+        Request::PutAData(fixtures.pub_seq_adata().into()),
+        Request::GetAData(fixtures.adata_address()),
+        Request::GetADataShell {
+            address: fixtures.adata_address(),
+            data_index: ADataIndex::FromEnd(1),
+        },
+        Request::DeleteAData(fixtures.adata_address()),
+        Request::GetADataRange {
+            address: fixtures.adata_address(),
+            range: (ADataIndex::FromStart(0), ADataIndex::FromEnd(0)),
+        },
+        Request::GetADataValue {
+            address: fixtures.adata_address(),
+            key: b"key".to_vec(),
+        },
+        Request::GetADataIndices(fixtures.adata_address()),
+        Request::GetADataLastEntry(fixtures.adata_address()),
+        Request::GetADataPermissions {
+            address: fixtures.adata_address(),
+            permissions_index: ADataIndex::FromStart(0),
+        },
+        Request::GetPubADataUserPermissions {
+            address: fixtures.adata_address(),
+            permissions_index: ADataIndex::FromStart(0),
+            user: ADataUser::Key(app_key),
+        },
+        Request::GetUnpubADataUserPermissions {
+            address: fixtures.adata_address(),
+            permissions_index: ADataIndex::FromStart(0),
+            public_key: ADataUser::Key(app_key),
+        },
+        Request::GetADataOwners {
+            address: fixtures.adata_address(),
+            owners_index: ADataIndex::FromEnd(1),
+        },
+        Request::AddPubADataPermissions {
+            address: fixtures.adata_address(),
+            permissions: fixtures.pub_permissions(),
+            permissions_index: 1,
+        },
+        Request::AddUnpubADataPermissions {
+            address: fixtures.adata_address(),
+            permissions: fixtures.unpub_permissions(),
+            permissions_index: 1,
+        },
+        Request::SetADataOwner {
+            address: fixtures.adata_address(),
+            owner: fixtures.adata_owner(),
+            owners_index: 1,
+        },
+        Request::AppendSeq {
+            append: append.clone(),
+            index: 1,
+        },
+        Request::AppendUnseq(append),
+        Request::TransferCoins {
+            destination: fixtures.name(),
+            amount: fixtures.coins(),
+            transaction_id: 1,
+        },
+        Request::GetBalance,
+        Request::CreateBalance {
+            new_balance_owner: client_key,
+            amount: fixtures.coins(),
+            transaction_id: 2,
+        },
+        Request::CreateLoginPacket(fixtures.login_packet()),
+        Request::CreateLoginPacketFor {
+            new_owner: client_key,
+            amount: fixtures.coins(),
+            transaction_id: 3,
+            new_login_packet: fixtures.login_packet(),
+        },
+        Request::UpdateLoginPacket(fixtures.login_packet()),
+        Request::GetLoginPacket(fixtures.name()),
+        Request::ListAuthKeysAndVersion,
+        Request::InsAuthKey {
+            key: app_key,
+            version: 1,
+            permissions: AppPermissions {
+                transfer_coins: true,
+            },
+        },
+        Request::DelAuthKey {
+            key: app_key,
+            version: 2,
+        },
+        Request::SubscribeMData(fixtures.mdata_address()),
+        Request::UnsubscribeMData(fixtures.mdata_address()),
+        Request::SubscribeAData(fixtures.adata_address()),
+        Request::UnsubscribeAData(fixtures.adata_address()),
+        Request::AddMDataGroupMember {
+            address: fixtures.mdata_address(),
+            group: GROUP.to_string(),
+            member: app_key,
+            version: 1,
+        },
+        Request::RemoveMDataGroupMember {
+            address: fixtures.mdata_address(),
+            group: GROUP.to_string(),
+            member: app_key,
+            version: 2,
+        },
+        Request::AddADataGroupMember {
+            address: fixtures.adata_address(),
+            group: GROUP.to_string(),
+            member: app_key,
+            version: 1,
+        },
+        Request::RemoveADataGroupMember {
+            address: fixtures.adata_address(),
+            group: GROUP.to_string(),
+            member: app_key,
+            version: 2,
+        },
+        Request::RevokeCapability {
+            address: fixtures.mdata_address().into(),
+            nonce: 1,
+        },
+        Request::SetMDataEntryPermissions {
+            address: fixtures.mdata_address(),
+            prefix: b"key".to_vec(),
+            user: MDataUser::Key(app_key),
+            permissions: MDataPermissionSet::new().deny(MDataAction::Update),
+            version: 1,
+        },
+        Request::DelMDataEntryPermissions {
+            address: fixtures.mdata_address(),
+            prefix: b"key".to_vec(),
+            user: MDataUser::Key(app_key),
+            version: 2,
+        },
+        Request::PurgeMDataTombstones(fixtures.mdata_address()),
+        Request::ListMDataEntriesPage {
+            address: fixtures.mdata_address(),
+            filter: MDataKeyFilter::All,
+            limit: 10,
+            cursor: None,
+        },
+        Request::ListMDataKeysPage {
+            address: fixtures.mdata_address(),
+            filter: MDataKeyFilter::Range {
+                start: Some(b"a".to_vec()),
+                end: Some(b"n".to_vec()),
+            },
+            limit: 10,
+            cursor: Some(b"key".to_vec()),
+        },
+        Request::ListMDataValuesPage {
+            address: fixtures.mdata_address(),
+            filter: MDataKeyFilter::Prefix(b"k".to_vec()),
+            limit: 1,
+            cursor: None,
+        },
+    ]
+}
+
+pub(crate) fn responses(fixtures: &mut Fixtures) -> Vec<Response> {
+    let mut keys = BTreeSet::new();
+    let _ = keys.insert(b"key".to_vec());
+    let mut auth_keys = BTreeMap::new();
+    let _ = auth_keys.insert(
+        fixtures.app_key(),
+        AppPermissions {
+            transfer_coins: false,
+        },
+    );
+    let seq_value = MDataSeqValue {
+        data: b"value".to_vec(),
+        version: 1,
+    };
+    let mut seq_entries = BTreeMap::new();
+    let _ = seq_entries.insert(b"key".to_vec(), seq_value.clone());
+    let data = b"encrypted account".to_vec();
+    let login_packet_signature = fixtures.client.sign(&data);
+
+    vec![
+        Response::GetIData(Ok(UnpubImmutableData::new(
+            b"unpublished".to_vec(),
+            fixtures.client_key(),
+        )
+        .into())),
+        Response::GetMData(Ok(fixtures.unseq_mdata().into())),
+        Response::GetMDataShell(Ok(fixtures.seq_mdata().shell().into())),
+        Response::GetMDataVersion(Ok(4)),
+        Response::ListMDataEntries(Ok(MDataEntries::Seq(seq_entries.clone()))),
+        Response::ListMDataKeys(Ok(keys.clone())),
+        Response::ListMDataValues(Ok(MDataValues::Unseq(vec![b"value".to_vec()]))),
+        Response::ListMDataUserPermissions(Ok(
+            MDataPermissionSet::new().allow(MDataAction::ManagePermissions)
+        )),
+        Response::ListMDataPermissions(Ok(fixtures.mdata_permissions())),
+        Response::GetMDataValue(Ok(MDataValue::Seq(seq_value))),
+        Response::GetAData(Ok(fixtures.unpub_seq_adata().into())),
+        Response::GetADataShell(Ok(
+            unwrap!(AData::from(fixtures.pub_unseq_adata()).shell(0)),
+        )),
+        Response::GetADataOwners(Ok(fixtures.adata_owner())),
+        Response::GetADataRange(Ok(fixtures.adata_entries())),
+        Response::GetADataValue(Ok(b"value".to_vec())),
+        Response::GetADataIndices(Ok(ADataIndices::new(1, 1, 1))),
+        Response::GetADataLastEntry(Ok(ADataEntry::new(b"key".to_vec(), b"value".to_vec()))),
+        Response::GetADataPermissions(Ok(ADataPermissions::Unpub(fixtures.unpub_permissions()))),
+        Response::GetPubADataUserPermissions(Ok(ADataPubPermissionSet::new(None, true))),
+        Response::GetUnpubADataUserPermissions(Ok(ADataUnpubPermissionSet::new(
+            true, false, false,
+        ))),
+        Response::GetBalance(Err(Error::NoSuchBalance)),
+        Response::Transaction(Ok(Transaction {
+            id: 1,
+            amount: fixtures.coins(),
+        })),
+        Response::GetLoginPacket(Ok((data, login_packet_signature))),
+        Response::ListAuthKeysAndVersion(Ok((auth_keys, 3))),
+        Response::Mutation(Ok(())),
+        Response::ListMDataEntriesPage(Ok((MDataEntries::Seq(seq_entries), None))),
+        Response::ListMDataKeysPage(Ok((keys, Some(b"key".to_vec())))),
+        Response::ListMDataValuesPage(Ok((
+            MDataValues::Unseq(vec![b"value".to_vec()]),
+            Some(b"key".to_vec()),
+        ))),
+    ]
+}
+
+pub(crate) fn errors(_: &mut Fixtures) -> Vec<Error> {
+    let mut entry_errors = BTreeMap::new();
+    let _ = entry_errors.insert(b"missing".to_vec(), EntryError::NoSuchEntry);
+    let _ = entry_errors.insert(b"existing".to_vec(), EntryError::EntryExists(2));
+    let _ = entry_errors.insert(b"stale".to_vec(), EntryError::InvalidSuccessor(5));
+    let _ = entry_errors.insert(b"large".to_vec(), EntryError::ExceededSize);
+
+    vec![
+        Error::AccessDenied,
+        Error::NoSuchLoginPacket,
+        Error::LoginPacketExists,
+        Error::NoSuchData,
+        Error::DataExists,
+        Error::NoSuchEntry,
+        Error::TooManyEntries,
+        Error::InvalidEntryActions(entry_errors),
+        Error::NoSuchKey,
+        Error::KeysExist(vec![ADataEntry::new(b"key".to_vec(), b"value".to_vec())]),
+        Error::DuplicateEntryKeys,
+        Error::InvalidOwners,
+        Error::InvalidSuccessor(1),
+        Error::InvalidOwnersSuccessor(2),
+        Error::InvalidPermissionsSuccessor(3),
+        Error::InvalidPermissions,
+        Error::InvalidOperation,
+        Error::SigningKeyTypeMismatch,
+        Error::InvalidSignature,
+        Error::DuplicateMessageId,
+        Error::Internal("database failure".to_string()),
+        Error::LossOfPrecision,
+        Error::ExcessiveValue,
+        Error::FailedToParse(ParseError::InvalidSerialisation),
+        Error::TransactionIdExists,
+        Error::InsufficientBalance,
+        Error::NoSuchBalance,
+        Error::BalanceExists,
+        Error::ExceededSize,
+        Error::NoSuchChallenge,
+        Error::ChallengeExpired,
+        Error::ExceededMessageSize,
+        Error::ExceededKeyLength,
+        Error::ExceededValueLength,
+        Error::ExceededLoginPacketSize,
+        Error::UnsupportedProtocolVersion(2),
+        Error::GroupMemberExists,
+        Error::PrivilegeEscalation,
+        Error::CapabilityExpired,
+        Error::CapabilityRevoked,
+    ]
+}
+
+fn messages(fixtures: &mut Fixtures) -> Vec<Message> {
+    let request = Request::DeleteMData(fixtures.mdata_address());
+    vec![
+        fixtures.signed_request(request),
+        Message::Response {
+            response: Response::Mutation(Err(Error::AccessDenied)),
+            message_id: fixtures.message_id(),
+        },
+        Message::Notification {
+            notification: Notification::Transaction(Transaction {
+                id: 1,
+                amount: fixtures.coins(),
+            }),
+        },
+    ]
+}
+
+pub(crate) fn notifications(fixtures: &mut Fixtures) -> Vec<Notification> {
+    let mut keys = BTreeSet::new();
+    let _ = keys.insert(b"key".to_vec());
+    vec![
+        Notification::Transaction(Transaction {
+            id: 1,
+            amount: fixtures.coins(),
+        }),
+        Notification::MDataMutated {
+            address: fixtures.mdata_address(),
+            version: 1,
+            keys,
+        },
+        Notification::ADataAppended {
+            address: fixtures.adata_address(),
+            index: 1,
+            keys: vec![b"key".to_vec()],
+        },
+        Notification::ShellChanged {
+            address: fixtures.mdata_address().into(),
+        },
+    ]
+}
+
+fn challenges(fixtures: &mut Fixtures) -> Vec<Challenge> {
+    let nonce: [u8; 32] = fixtures.rng.gen();
+    vec![
+        Challenge::Request(
+            PublicId::Node(fixtures.node.public_id().clone()),
+            nonce.to_vec(),
+        ),
+        Challenge::Response(
+            PublicId::Client(fixtures.client.public_id().clone()),
+            fixtures.client.sign(nonce),
+        ),
+    ]
+}
+
+fn public_ids(fixtures: &mut Fixtures) -> Vec<PublicId> {
+    vec![
+        PublicId::Node(fixtures.node.public_id().clone()),
+        PublicId::Client(fixtures.client.public_id().clone()),
+        PublicId::App(fixtures.app.public_id().clone()),
+    ]
+}
+
+fn public_keys(fixtures: &mut Fixtures) -> Vec<PublicKey> {
+    vec![
+        fixtures.client_key(),
+        *fixtures.bls_client.public_id().public_key(),
+        fixtures.node_share_key(),
+    ]
+}
+
+fn signatures(fixtures: &mut Fixtures) -> Vec<Signature> {
+    let data = b"signed data";
+    vec![
+        fixtures.client.sign(data),
+        fixtures.bls_client.sign(data),
+        unwrap!(fixtures.node.sign_using_bls(data)),
+    ]
+}
+
+fn idata(fixtures: &mut Fixtures) -> Vec<IData> {
+    vec![
+        UnpubImmutableData::new(b"unpublished".to_vec(), fixtures.client_key()).into(),
+        PubImmutableData::new(b"published".to_vec()).into(),
+    ]
+}
+
+fn mdata(fixtures: &mut Fixtures) -> Vec<MData> {
+    vec![fixtures.seq_mdata().into(), fixtures.unseq_mdata().into()]
+}
+
+fn adata(fixtures: &mut Fixtures) -> Vec<AData> {
+    vec![
+        fixtures.pub_seq_adata().into(),
+        fixtures.pub_unseq_adata().into(),
+        fixtures.unpub_seq_adata().into(),
+        fixtures.unpub_unseq_adata().into(),
+    ]
+}
+
+fn data_addresses(fixtures: &mut Fixtures) -> Vec<DataAddress> {
+    vec![
+        IDataAddress::Unpub(fixtures.name()).into(),
+        fixtures.mdata_address().into(),
+        fixtures.adata_address().into(),
+    ]
+}
+
+fn envelopes(fixtures: &mut Fixtures) -> Vec<(&'static str, WireEnvelope)> {
+    let message = Message::Response {
+        response: Response::GetBalance(Ok(fixtures.coins())),
+        message_id: fixtures.message_id(),
+    };
+    let challenge = Challenge::Request(
+        PublicId::Node(fixtures.node.public_id().clone()),
+        vec![7; 32],
+    );
+    vec![
+        ("Message", WireEnvelope::from_message(&message)),
+        ("Challenge", WireEnvelope::from_challenge(&challenge)),
+    ]
+}
+
+// ===== Vector files =====
+
+/// Renders one line per value: its name, then its bincode encoding in hex, then the same encoding
+/// as multibase z-base-32, separated by tabs.
+fn render<T: Serialize>(title: &str, values: &[(&str, T)]) -> String {
+    let mut output = format!(
+        "# {}\n# Generated by `src/test_vectors.rs`. Do not edit by hand.\n\
+         # name\tbincode (hex)\tbincode (multibase z-base-32)\n",
+        title
+    );
+    for (name, value) in values {
+        unwrap!(writeln!(
+            output,
+            "{}\t{}\t{}",
+            name,
+            hex::encode(utils::serialise(value)),
+            utils::encode(value)
+        ));
+    }
+    output
+}
+
+/// Checks `contents` against the checked-in file, or rewrites the file if requested.
+fn check(file: &str, contents: &str) {
+    let path = format!("{}/test_vectors/{}", env!("CARGO_MANIFEST_DIR"), file);
+    if env::var_os(UPDATE_ENV_VAR).is_some() {
+        unwrap!(fs::write(&path, contents));
+        return;
+    }
+
+    let expected = unwrap!(fs::read_to_string(&path));
+    for (expected, actual) in expected.lines().zip(contents.lines()) {
+        assert_eq!(
+            actual, expected,
+            "Encoding in {} has drifted; rerun the tests with {}=1 to regenerate the vectors \
+             if the change is intended.",
+            file, UPDATE_ENV_VAR
+        );
+    }
+    assert_eq!(
+        contents.lines().count(),
+        expected.lines().count(),
+        "Number of vectors in {} has changed",
+        file
+    );
+}
+
+/// Checks that `values` holds exactly one instance of each variant, in declaration order, and
+/// that each one encodes its variant index as bincode does.
+fn check_variants<T: Serialize>(values: &[(&str, T)], names: &[&str]) {
+    let actual: Vec<_> = values.iter().map(|(name, _)| *name).collect();
+    assert_eq!(actual, names);
+    for (index, (name, value)) in values.iter().enumerate() {
+        let bytes = utils::serialise(value);
+        let mut variant_index = [0; 4];
+        variant_index.copy_from_slice(&bytes[..4]);
+        assert_eq!(
+            u32::from_le_bytes(variant_index) as usize,
+            index,
+            "{}",
+            name
+        );
+    }
+}
+
+fn named<T>(values: Vec<T>, name_of: fn(&T) -> &'static str) -> Vec<(&'static str, T)> {
+    values
+        .into_iter()
+        .map(|value| (name_of(&value), value))
+        .collect()
+}
+
+macro_rules! vector_test {
+    ($test:ident, $file:expr, $title:expr, $instances:ident, $names:ident, $name_of:ident) => {
+        #[test]
+        fn $test() {
+            let values = named($instances(&mut Fixtures::new()), $name_of);
+            check_variants(&values, &$names());
+
+            // Generating the instances again must give identical encodings.
+            let contents = render($title, &values);
+            let again = render($title, &named($instances(&mut Fixtures::new()), $name_of));
+            assert_eq!(contents, again);
+
+            check($file, &contents);
+        }
+    };
+}
+
+vector_test!(
+    request_vectors,
+    "requests.txt",
+    "Request",
+    requests,
+    request_names,
+    request_name
+);
+vector_test!(
+    response_vectors,
+    "responses.txt",
+    "Response",
+    responses,
+    response_names,
+    response_name
+);
+vector_test!(
+    error_vectors,
+    "errors.txt",
+    "Error",
+    errors,
+    error_names,
+    error_name
+);
+vector_test!(
+    message_vectors,
+    "messages.txt",
+    "Message",
+    messages,
+    message_names,
+    message_name
+);
+vector_test!(
+    notification_vectors,
+    "notifications.txt",
+    "Notification",
+    notifications,
+    notification_names,
+    notification_name
+);
+vector_test!(
+    challenge_vectors,
+    "challenges.txt",
+    "Challenge",
+    challenges,
+    challenge_names,
+    challenge_name
+);
+vector_test!(
+    public_id_vectors,
+    "public_ids.txt",
+    "PublicId",
+    public_ids,
+    public_id_names,
+    public_id_name
+);
+vector_test!(
+    public_key_vectors,
+    "public_keys.txt",
+    "PublicKey",
+    public_keys,
+    public_key_names,
+    public_key_name
+);
+vector_test!(
+    signature_vectors,
+    "signatures.txt",
+    "Signature",
+    signatures,
+    signature_names,
+    signature_name
+);
+vector_test!(
+    idata_vectors,
+    "idata.txt",
+    "IData",
+    idata,
+    idata_names,
+    idata_name
+);
+vector_test!(
+    mdata_vectors,
+    "mdata.txt",
+    "MData",
+    mdata,
+    mdata_names,
+    mdata_name
+);
+vector_test!(
+    adata_vectors,
+    "adata.txt",
+    "AData",
+    adata,
+    adata_names,
+    adata_name
+);
+vector_test!(
+    data_address_vectors,
+    "data_addresses.txt",
+    "DataAddress",
+    data_addresses,
+    data_address_names,
+    data_address_name
+);
+
+#[test]
+fn envelope_vectors() {
+    let values = envelopes(&mut Fixtures::new());
+    let contents = render("WireEnvelope", &values);
+    assert_eq!(
+        contents,
+        render("WireEnvelope", &envelopes(&mut Fixtures::new()))
+    );
+    check("envelopes.txt", &contents);
+}
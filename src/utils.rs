@@ -7,21 +7,22 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use crate::{Error, MessageId, PublicKey, Request, Result, Signature};
+use crate::{Error, MessageId, ParseError, PublicKey, Request, Result, Signature, SigningContext};
 use bincode;
 use multibase::{self, Base, Decodable};
 use serde::{de::DeserializeOwned, Serialize};
 use unwrap::unwrap;
 
-/// Verify that a signature is valid for a given `Request` + `MessageId` combination.
+/// Verify that a signature is valid for a given `Request` + `MessageId` combination on the network
+/// of `context`.
 pub fn verify_signature(
+    context: &SigningContext,
     signature: &Signature,
     public_key: &PublicKey,
     request: &Request,
     message_id: &MessageId,
 ) -> Result<()> {
-    let message = serialise(&(request, *message_id));
-    public_key.verify(signature, message)
+    public_key.verify(signature, context.request_payload(request, message_id))
 }
 
 /// Wrapper for raw bincode::serialize.
@@ -37,13 +38,11 @@ pub(crate) fn encode<T: Serialize>(data: &T) -> String {
 
 /// Wrapper for z-Base-32 multibase::decode.
 pub(crate) fn decode<I: Decodable, O: DeserializeOwned>(encoded: I) -> Result<O> {
-    let (base, decoded) =
-        multibase::decode(encoded).map_err(|e| Error::FailedToParse(e.to_string()))?;
+    let (base, decoded) = multibase::decode(encoded)
+        .map_err(|_| Error::FailedToParse(ParseError::InvalidMultibase))?;
     if base != Base::Base32z {
-        return Err(Error::FailedToParse(format!(
-            "Expected z-base-32 encoding, but got {:?}",
-            base
-        )));
+        return Err(Error::FailedToParse(ParseError::UnexpectedBase));
     }
-    Ok(bincode::deserialize(&decoded).map_err(|e| Error::FailedToParse(e.to_string()))?)
+    bincode::deserialize(&decoded)
+        .map_err(|_| Error::FailedToParse(ParseError::InvalidSerialisation))
 }
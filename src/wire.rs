@@ -0,0 +1,232 @@
+// Copyright 2019 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// https://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+//! Versioned wire envelope.
+//!
+//! Everything sent between clients and vaults is wrapped in a `WireEnvelope`, whose encoding
+//! starts with the protocol version. The envelope's own layout never changes, so a peer can always
+//! read the version and reject a message it doesn't understand, rather than misparsing it.
+
+use crate::{utils, Challenge, Error, Message, MessageLimits, ParseError, Result};
+use bincode::{self, ErrorKind};
+use serde::{Deserialize, Serialize};
+
+/// Version of the wire protocol used by this release to encode envelopes.
+pub const PROTOCOL_VERSION: u16 = 9;
+
+/// Wire protocol versions which this release can decode.
+///
+/// Earlier versions were never part of a release, so only the current one is supported.
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[u16] = &[9];
+
+/// Releases of this crate, and the protocol version each of them encodes envelopes with.
+///
+/// Each entry is `(release, protocol_version)`, where `release` is a version prefix, e.g. `"0.3"`
+/// for all `0.3.x` releases. The last entry is the upcoming release. `PROTOCOL_VERSION` must be
+/// bumped whenever the encoding of anything carried in an envelope changes, and the last entry
+/// updated, or a new one added once that release is out.
+///
+/// Envelopes were introduced in 0.3. Releases up to 0.2.x send bare messages and aren't supported.
+pub const COMPATIBILITY_TABLE: &[(&str, u16)] = &[("0.3", 9)];
+
+/// Returns `true` if envelopes with the given protocol version can be decoded by this release.
+pub fn is_supported_protocol_version(version: u16) -> bool {
+    SUPPORTED_PROTOCOL_VERSIONS.contains(&version)
+}
+
+/// Kind of the content carried by a `WireEnvelope`.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub enum ContentKind {
+    /// A `Message`.
+    Message,
+    /// A `Challenge`.
+    Challenge,
+}
+
+/// Decoded content of a `WireEnvelope`.
+#[allow(clippy::large_enum_variant)]
+pub enum WireContent {
+    /// A `Message`.
+    Message(Message),
+    /// A `Challenge`.
+    Challenge(Challenge),
+}
+
+/// Envelope carrying an encoded `Message` or `Challenge`, tagged with the protocol version.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct WireEnvelope {
+    protocol_version: u16,
+    content_kind: ContentKind,
+    payload: Vec<u8>,
+}
+
+impl WireEnvelope {
+    /// Wraps `message`, encoded using the current protocol version.
+    pub fn from_message(message: &Message) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            content_kind: ContentKind::Message,
+            payload: message.to_bytes(),
+        }
+    }
+
+    /// Wraps `challenge`, encoded using the current protocol version.
+    pub fn from_challenge(challenge: &Challenge) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            content_kind: ContentKind::Challenge,
+            payload: utils::serialise(challenge),
+        }
+    }
+
+    /// Decodes an envelope from untrusted `bytes`.
+    ///
+    /// Returns `Err(UnsupportedProtocolVersion)` if the envelope uses a protocol version this
+    /// release can't decode, without attempting to decode the rest of the envelope.
+    pub fn from_bytes(bytes: &[u8], limits: &MessageLimits) -> Result<Self> {
+        let protocol_version: u16 = bincode::deserialize(bytes)
+            .map_err(|_| Error::FailedToParse(ParseError::InvalidSerialisation))?;
+        if !is_supported_protocol_version(protocol_version) {
+            return Err(Error::UnsupportedProtocolVersion(protocol_version));
+        }
+        if bytes.len() > limits.max_message_size {
+            return Err(Error::ExceededMessageSize);
+        }
+        bincode::config()
+            .limit(limits.max_message_size as u64)
+            .deserialize(bytes)
+            .map_err(|error| match *error {
+                ErrorKind::SizeLimit => Error::ExceededMessageSize,
+                _ => Error::FailedToParse(ParseError::InvalidSerialisation),
+            })
+    }
+
+    /// Encodes the envelope.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        utils::serialise(self)
+    }
+
+    /// Returns the protocol version used to encode the payload.
+    pub fn protocol_version(&self) -> u16 {
+        self.protocol_version
+    }
+
+    /// Returns the kind of content carried by the envelope.
+    pub fn content_kind(&self) -> ContentKind {
+        self.content_kind
+    }
+
+    /// Returns the encoded content.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Decodes the content, rejecting it if it exceeds any of `limits`.
+    pub fn open(&self, limits: &MessageLimits) -> Result<WireContent> {
+        if !is_supported_protocol_version(self.protocol_version) {
+            return Err(Error::UnsupportedProtocolVersion(self.protocol_version));
+        }
+        match self.content_kind {
+            ContentKind::Message => {
+                Message::from_bytes(&self.payload, limits).map(WireContent::Message)
+            }
+            ContentKind::Challenge => bincode::config()
+                .limit(limits.max_message_size as u64)
+                .deserialize(&self.payload)
+                .map(WireContent::Challenge)
+                .map_err(|_| Error::FailedToParse(ParseError::InvalidSerialisation)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MessageId, NodeFullId, PublicId, Request, Response, XorName};
+    use unwrap::unwrap;
+
+    #[test]
+    fn round_trip() {
+        let limits = MessageLimits::default();
+        let message = Message::Response {
+            response: Response::GetBalance(Err(Error::NoSuchBalance)),
+            message_id: MessageId::new(),
+        };
+        let envelope = WireEnvelope::from_message(&message);
+        assert_eq!(envelope.protocol_version(), PROTOCOL_VERSION);
+        assert_eq!(envelope.content_kind(), ContentKind::Message);
+
+        let decoded = unwrap!(WireEnvelope::from_bytes(&envelope.to_bytes(), &limits));
+        assert_eq!(decoded, envelope);
+        match unwrap!(decoded.open(&limits)) {
+            WireContent::Message(decoded) => assert!(decoded == message),
+            WireContent::Challenge(_) => panic!("Unexpected challenge"),
+        }
+
+        let node_id = PublicId::Node(NodeFullId::new(&mut rand::thread_rng()).public_id().clone());
+        let challenge = Challenge::Request(node_id.clone(), vec![1, 2, 3]);
+        let envelope = WireEnvelope::from_challenge(&challenge);
+        match unwrap!(
+            unwrap!(WireEnvelope::from_bytes(&envelope.to_bytes(), &limits)).open(&limits)
+        ) {
+            WireContent::Challenge(Challenge::Request(id, nonce)) => {
+                assert_eq!(id, node_id);
+                assert_eq!(nonce, vec![1, 2, 3]);
+            }
+            _ => panic!("Unexpected content"),
+        }
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let limits = MessageLimits::default();
+        let message = Message::Request {
+            request: Request::GetLoginPacket(XorName::default()),
+            message_id: MessageId::new(),
+            signature: None,
+            capability: None,
+        };
+        let mut envelope = WireEnvelope::from_message(&message);
+        envelope.protocol_version = PROTOCOL_VERSION + 1;
+
+        // Even content this release can't make sense of is reported as a version mismatch.
+        let mut bytes = envelope.to_bytes();
+        let last = bytes.len() - 1;
+        bytes[2..last].iter_mut().for_each(|byte| *byte = 0xff);
+        assert_eq!(
+            WireEnvelope::from_bytes(&bytes, &limits),
+            Err(Error::UnsupportedProtocolVersion(PROTOCOL_VERSION + 1))
+        );
+        assert!(match envelope.open(&limits) {
+            Err(Error::UnsupportedProtocolVersion(version)) => version == PROTOCOL_VERSION + 1,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn compatibility_table() {
+        assert!(is_supported_protocol_version(PROTOCOL_VERSION));
+        assert!(!is_supported_protocol_version(PROTOCOL_VERSION + 1));
+
+        // The upcoming release is listed last, with the protocol version it actually uses, and
+        // protocol versions only ever increase from one release to the next.
+        assert_eq!(
+            COMPATIBILITY_TABLE.last().map(|&(_, version)| version),
+            Some(PROTOCOL_VERSION)
+        );
+        assert!(COMPATIBILITY_TABLE
+            .windows(2)
+            .all(|pair| pair[0].1 < pair[1].1));
+
+        // Releases before envelopes were introduced can't be listed.
+        assert!(COMPATIBILITY_TABLE
+            .iter()
+            .all(|(release, _)| !["0.1", "0.2"].contains(release)));
+    }
+}